@@ -1,5 +1,34 @@
 use sqlx::{Pool, Postgres};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use crate::commands::referensi::ReferensiBundle;
+use crate::commands::wilayah::WilayahIndex;
  
 pub struct DbPool {
     pub pool: Pool<Postgres>,
-} 
\ No newline at end of file
+    // Hasil menyiapkan skema dapomaster saat startup; Err berisi pesan kegagalannya
+    pub skema_dapomaster: Result<(), String>,
+}
+
+impl DbPool {
+    /// Dipanggil perintah yang memakai tabel `dapomaster`, agar kegagalan saat startup muncul sebagai pesan yang jelas
+    pub fn pastikan_skema_dapomaster(&self) -> Result<(), String> {
+        self.skema_dapomaster.clone().map_err(|e| {
+            format!("Fitur ini memerlukan tabel aplikasi DapoMaster yang gagal disiapkan saat aplikasi dimulai. {}", e)
+        })
+    }
+}
+
+// Indeks ref.mst_wilayah, dimuat saat startup dan bila gagal diisi saat pertama kali dibutuhkan
+#[derive(Default)]
+pub struct WilayahCache {
+    pub index: RwLock<Option<Arc<WilayahIndex>>>,
+}
+
+// Data ref.* yang dipakai form dan ekspor, dimuat saat startup dan diperbarui bila versi_db berubah.
+// versi_db diperiksa paling sering sekali per selang waktu, dicatat di `versi_diperiksa_pada`.
+#[derive(Default)]
+pub struct ReferensiCache {
+    pub bundle: RwLock<Option<Arc<ReferensiBundle>>>,
+    pub versi_diperiksa_pada: RwLock<Option<Instant>>,
+}
@@ -0,0 +1,211 @@
+//! Pemeriksaan konsistensi alamat siswa: kode_wilayah di-resolve lewat ref.mst_wilayah lalu
+//! dibandingkan dengan isian bebas desa_kelurahan.
+
+use crate::app_state::{DbPool, WilayahCache};
+use crate::commands::wilayah::{ambil_index_wilayah, LEVEL_DESA, LEVEL_KABUPATEN, LEVEL_PROVINSI};
+use crate::emit_log;
+use tauri::{AppHandle, State};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid as SqlxUuid;
+
+// Format baku isian desa_kelurahan
+const AWALAN_DESA_KELURAHAN: &str = "Desa/Kel. ";
+
+// Awalan yang sering ditulis operator dan diabaikan saat membandingkan nama desa
+const AWALAN_DIABAIKAN: [&str; 7] = ["desa/kel.", "desa/kel", "kelurahan", "kel.", "desa", "ds.", "kel"];
+
+#[derive(Serialize)]
+pub struct KonsistensiAlamat {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub kode_wilayah: Option<String>,
+    pub desa_kelurahan: Option<String>,
+    pub desa: Option<String>,
+    pub kecamatan: Option<String>,
+    pub kabupaten: Option<String>,
+    pub provinsi: Option<String>,
+    /// sesuai, format, berbeda, atau kode_tidak_valid
+    pub status: String,
+    pub pesan: String,
+    pub saran_desa_kelurahan: Option<String>,
+    pub saran_kode_wilayah: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PerbaikanAlamat {
+    pub peserta_didik_id: SqlxUuid,
+    pub desa_kelurahan: Option<String>,
+    pub kode_wilayah: Option<String>,
+}
+
+/// Menyamakan penulisan nama desa: huruf kecil, tanpa awalan desa/kelurahan, tanpa tanda baca
+pub(crate) fn normalisasi_nama_desa(nama: &str) -> String {
+    let mut teks = nama.trim().to_lowercase();
+    for awalan in AWALAN_DIABAIKAN {
+        // Awalan hanya dibuang bila berdiri sendiri, agar nama seperti "Kelapa" tidak terpotong
+        match teks.strip_prefix(awalan) {
+            Some(sisa) if awalan.ends_with('.') || !sisa.starts_with(char::is_alphanumeric) => {
+                teks = sisa.to_string();
+                break;
+            }
+            _ => {}
+        }
+    }
+    teks.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub(crate) fn format_desa_kelurahan(nama_desa: &str) -> String {
+    format!("{}{}", AWALAN_DESA_KELURAHAN, nama_desa.trim())
+}
+
+/// Memeriksa alamat semua siswa aktif. Bila `hanya_bermasalah` bernilai true, siswa yang alamatnya
+/// sudah sesuai tidak ikut dikembalikan.
+#[tauri::command]
+pub async fn cek_konsistensi_alamat(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, WilayahCache>,
+    hanya_bermasalah: bool,
+) -> Result<Vec<KonsistensiAlamat>, String> {
+    emit_log(&app, "CMD: cek_konsistensi_alamat - Memeriksa kesesuaian desa_kelurahan dengan kode_wilayah");
+
+    let siswa = sqlx::query_as::<_, (SqlxUuid, String, Option<String>, Option<String>)>(
+        "SELECT pd.peserta_didik_id, pd.nama, TRIM(pd.kode_wilayah), pd.desa_kelurahan
+         FROM peserta_didik pd
+         JOIN registrasi_peserta_didik rpd ON rpd.peserta_didik_id = pd.peserta_didik_id
+         WHERE pd.soft_delete = 0 AND rpd.soft_delete = 0 AND rpd.jenis_keluar_id IS NULL
+         ORDER BY pd.nama"
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil alamat siswa: {}", e))?;
+
+    let index = ambil_index_wilayah(&state.pool, &cache).await?;
+
+    let mut hasil = Vec::new();
+    for (peserta_didik_id, nama, kode_wilayah, desa_kelurahan) in siswa {
+        let mut item = KonsistensiAlamat {
+            peserta_didik_id,
+            nama,
+            kode_wilayah: kode_wilayah.clone(),
+            desa_kelurahan: desa_kelurahan.clone(),
+            desa: None,
+            kecamatan: None,
+            kabupaten: None,
+            provinsi: None,
+            status: "sesuai".to_string(),
+            pesan: String::new(),
+            saran_desa_kelurahan: None,
+            saran_kode_wilayah: None,
+        };
+
+        let kode = kode_wilayah.unwrap_or_default();
+        let nama_desa = match index.get(&kode) {
+            Some(w) if w.id_level_wilayah == LEVEL_DESA => w.nama.clone(),
+            Some(_) => {
+                item.status = "kode_tidak_valid".to_string();
+                item.pesan = "Kode wilayah bukan tingkat desa/kelurahan".to_string();
+                hasil.push(item);
+                continue;
+            }
+            None => {
+                item.status = "kode_tidak_valid".to_string();
+                item.pesan = "Kode wilayah kosong atau tidak ditemukan di referensi wilayah".to_string();
+                hasil.push(item);
+                continue;
+            }
+        };
+        let kecamatan = index.kecamatan_dari_desa(&kode);
+        item.desa = Some(nama_desa.clone());
+        item.kecamatan = kecamatan.map(|w| w.nama.clone());
+        item.kabupaten = index.induk_pada_level(&kode, LEVEL_KABUPATEN).map(|w| w.nama.clone());
+        item.provinsi = index.induk_pada_level(&kode, LEVEL_PROVINSI).map(|w| w.nama.clone());
+
+        let isian = desa_kelurahan.unwrap_or_default();
+        let baku = format_desa_kelurahan(&nama_desa);
+        if normalisasi_nama_desa(&isian) == normalisasi_nama_desa(&nama_desa) {
+            if isian.trim() != baku {
+                item.status = "format".to_string();
+                item.pesan = "Penulisan desa/kelurahan belum sesuai format baku".to_string();
+                item.saran_desa_kelurahan = Some(baku);
+            }
+        } else {
+            item.status = "berbeda".to_string();
+            item.saran_desa_kelurahan = Some(baku);
+
+            // Bila isian cocok dengan desa lain di kecamatan yang sama, kemungkinan kode_wilayah yang salah
+            let isian_normal = normalisasi_nama_desa(&isian);
+            let desa_cocok = kecamatan
+                .map(|kec| index.anak(&kec.kode_wilayah))
+                .unwrap_or_default()
+                .into_iter()
+                .find(|w| w.id_level_wilayah == LEVEL_DESA && !isian_normal.is_empty() && normalisasi_nama_desa(&w.nama) == isian_normal);
+            item.pesan = match desa_cocok {
+                Some(w) => {
+                    item.saran_kode_wilayah = Some(w.kode_wilayah.clone());
+                    format!("Isian desa '{}' cocok dengan kode wilayah {} ({}), bukan {}", isian.trim(), w.kode_wilayah, w.nama, nama_desa)
+                }
+                None => format!("Isian desa '{}' tidak sesuai dengan kode wilayah ({})", isian.trim(), nama_desa),
+            };
+        }
+
+        if !hanya_bermasalah || item.status != "sesuai" {
+            hasil.push(item);
+        }
+    }
+
+    emit_log(&app, &format!("CMD: cek_konsistensi_alamat - {} data dikembalikan", hasil.len()));
+    Ok(hasil)
+}
+
+/// Menerapkan saran perbaikan alamat yang dipilih operator, per siswa
+#[tauri::command]
+pub async fn terapkan_perbaikan_alamat(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, WilayahCache>,
+    perbaikan: Vec<PerbaikanAlamat>,
+    pengguna_id: SqlxUuid,
+) -> Result<String, String> {
+    emit_log(&app, &format!("CMD: terapkan_perbaikan_alamat - Menerapkan {} perbaikan alamat", perbaikan.len()));
+
+    let index = ambil_index_wilayah(&state.pool, &cache).await?;
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    let mut jumlah = 0;
+
+    for item in &perbaikan {
+        if item.desa_kelurahan.is_none() && item.kode_wilayah.is_none() {
+            continue;
+        }
+
+        if let Some(kode) = &item.kode_wilayah {
+            if !index.valid(kode, Some(LEVEL_DESA)) {
+                return Err(format!("Kode wilayah {} bukan kode desa/kelurahan yang valid", kode.trim()));
+            }
+        }
+
+        let result = sqlx::query(
+            "UPDATE peserta_didik SET desa_kelurahan = COALESCE($1, desa_kelurahan), kode_wilayah = COALESCE($2, kode_wilayah),
+             last_update = NOW(), updater_id = $3
+             WHERE peserta_didik_id = $4 AND soft_delete = 0"
+        )
+        .bind(item.desa_kelurahan.as_deref().map(str::trim))
+        .bind(item.kode_wilayah.as_deref().map(str::trim))
+        .bind(pengguna_id)
+        .bind(item.peserta_didik_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Gagal memperbarui alamat siswa: {}", e))?;
+        jumlah += result.rows_affected();
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    emit_log(&app, &format!("CMD: terapkan_perbaikan_alamat - Berhasil memperbaiki alamat {} siswa", jumlah));
+    Ok(format!("Berhasil memperbaiki alamat {} siswa.", jumlah))
+}
@@ -0,0 +1,56 @@
+//! Tabel milik aplikasi di skema `dapomaster` pada database Dapodik: catatan tinggal kelas, nomor surat terakhir
+//! per pola, catatan pencatatan siswa keluar dan baris yang ikut terhapus saat siswa dipindahkan ke tempat sampah. Seluruh DDL ada di sini dan dijalankan sekali saat startup.
+
+/// Membuat skema dan tabel aplikasi bila belum ada. Kegagalan (misalnya role database tidak boleh membuat objek)
+/// disimpan di `DbPool` sehingga perintah yang memakai tabel ini menolak dengan pesan yang jelas,
+/// bukan "relation does not exist".
+pub(crate) async fn siapkan_skema_dapomaster(pool: &sqlx::PgPool) -> Result<(), String> {
+    let perintah = [
+        "CREATE SCHEMA IF NOT EXISTS dapomaster",
+        "CREATE TABLE IF NOT EXISTS dapomaster.tinggal_kelas (
+             tinggal_kelas_id uuid PRIMARY KEY,
+             peserta_didik_id uuid NOT NULL,
+             semester_id varchar(5) NOT NULL,
+             alasan text NOT NULL,
+             disetujui_oleh varchar(100),
+             dicatat_oleh uuid NOT NULL,
+             dicatat_pada timestamp NOT NULL DEFAULT NOW(),
+             last_update timestamp NOT NULL DEFAULT NOW(),
+             soft_delete numeric(1,0) NOT NULL DEFAULT 0,
+             updater_id uuid NOT NULL
+         )",
+        "CREATE UNIQUE INDEX IF NOT EXISTS tinggal_kelas_siswa_semester
+             ON dapomaster.tinggal_kelas (peserta_didik_id, semester_id) WHERE soft_delete = 0",
+        "CREATE TABLE IF NOT EXISTS dapomaster.nomor_skl (
+             pola varchar(200) NOT NULL,
+             tahun integer NOT NULL,
+             nomor_terakhir integer NOT NULL,
+             last_update timestamp NOT NULL DEFAULT NOW(),
+             PRIMARY KEY (pola, tahun)
+         )",
+        "CREATE TABLE IF NOT EXISTS dapomaster.siswa_keluar (
+             registrasi_id uuid PRIMARY KEY,
+             waktu_keluar timestamp NOT NULL,
+             keterangan_diubah boolean NOT NULL,
+             keterangan_lama text,
+             anggota_rombel_ids uuid[] NOT NULL
+         )",
+        "CREATE TABLE IF NOT EXISTS dapomaster.siswa_terhapus (
+             peserta_didik_id uuid PRIMARY KEY,
+             registrasi_ids uuid[] NOT NULL,
+             anggota_rombel_ids uuid[] NOT NULL,
+             dihapus_pada timestamp NOT NULL DEFAULT NOW(),
+             nama text,
+             nisn text,
+             dihapus_permanen_oleh uuid,
+             dihapus_permanen_pada timestamp
+         )",
+    ];
+    for sql in perintah {
+        sqlx::query(sql)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Gagal menyiapkan tabel aplikasi di skema dapomaster: {}", e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,516 @@
+//! Pembuatan dokumen surat: pengisian placeholder `{{nama}}` pada template DOCX dan penulis PDF sederhana
+//! (A4, font Helvetica bawaan PDF) untuk sekolah yang tidak punya template sendiri.
+
+use chrono::{Datelike, NaiveDate};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+const NAMA_BULAN: [&str; 12] = [
+    "Januari", "Februari", "Maret", "April", "Mei", "Juni",
+    "Juli", "Agustus", "September", "Oktober", "November", "Desember",
+];
+const BULAN_ROMAWI: [&str; 12] = ["I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X", "XI", "XII"];
+
+/// Tanggal dalam format surat, misalnya "19 Oktober 2026"
+pub fn tanggal_indonesia(tanggal: NaiveDate) -> String {
+    format!("{} {} {}", tanggal.day(), NAMA_BULAN[tanggal.month0() as usize], tanggal.year())
+}
+
+pub fn bulan_romawi(tanggal: NaiveDate) -> &'static str {
+    BULAN_ROMAWI[tanggal.month0() as usize]
+}
+
+/// Menyusun nomor surat dari pola dengan placeholder `{urut}`/`{urut:N}`, `{bulan_romawi}`, dan `{tahun}`
+pub fn format_nomor_surat(pola: &str, urut: u32, tanggal: NaiveDate) -> Result<String, String> {
+    let mut hasil = String::new();
+    let mut ada_urut = false;
+    let mut chars = pola.chars();
+    while let Some(c) = chars.next() {
+        if c != '{' {
+            hasil.push(c);
+            continue;
+        }
+        let nama: String = chars.by_ref().take_while(|&c| c != '}').collect();
+        match nama.as_str() {
+            "urut" => {
+                ada_urut = true;
+                hasil.push_str(&urut.to_string());
+            }
+            "bulan_romawi" => hasil.push_str(bulan_romawi(tanggal)),
+            "tahun" => hasil.push_str(&tanggal.year().to_string()),
+            lain => match lain.strip_prefix("urut:").map(str::parse::<usize>) {
+                Some(Ok(lebar)) if (1..=10).contains(&lebar) => {
+                    ada_urut = true;
+                    hasil.push_str(&format!("{:0lebar$}", urut, lebar = lebar));
+                }
+                _ => return Err(format!("Placeholder '{{{}}}' tidak dikenal pada pola nomor surat", nama)),
+            },
+        }
+    }
+    if !ada_urut {
+        return Err("Pola nomor surat harus memuat {urut}".to_string());
+    }
+    Ok(hasil)
+}
+
+fn escape_xml(teks: &str) -> String {
+    teks.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Mengganti `{{kunci}}` di XML Word. Word sering memecah satu placeholder ke beberapa `<w:t>`, jadi
+/// pencarian dilakukan pada gabungan teks di luar tag; teks pengganti ditaruh di potongan pertama.
+fn isi_placeholder_xml(xml: &str, nilai: &HashMap<String, String>) -> String {
+    // Pisahkan XML menjadi potongan tag dan potongan teks
+    let mut potongan: Vec<(bool, String)> = Vec::new();
+    let mut sisa = xml;
+    while !sisa.is_empty() {
+        let (bagian, lanjut, tag) = match sisa.find('<') {
+            Some(0) => {
+                let akhir = sisa.find('>').map_or(sisa.len(), |i| i + 1);
+                (&sisa[..akhir], &sisa[akhir..], true)
+            }
+            Some(i) => (&sisa[..i], &sisa[i..], false),
+            None => (sisa, "", false),
+        };
+        potongan.push((tag, bagian.to_string()));
+        sisa = lanjut;
+    }
+
+    let teks_idx: Vec<usize> = (0..potongan.len()).filter(|&i| !potongan[i].0).collect();
+    // Pencarian berikutnya dimulai setelah teks pengganti, agar nilai yang memuat "{{" tidak diproses ulang
+    let mut cari_dari = 0;
+    loop {
+        // Gabungan teks beserta posisi awal tiap potongan
+        let mut gabungan = String::new();
+        let mut awal = Vec::with_capacity(teks_idx.len());
+        for &i in &teks_idx {
+            awal.push(gabungan.len());
+            gabungan.push_str(&potongan[i].1);
+        }
+        let Some(mulai) = gabungan[cari_dari..].find("{{").map(|i| i + cari_dari) else { break };
+        let Some(panjang) = gabungan[mulai..].find("}}") else { break };
+        let selesai = mulai + panjang + 2;
+        let kunci = gabungan[mulai + 2..selesai - 2].trim().to_string();
+        let pengganti = nilai.get(&kunci).map(|v| escape_xml(v)).unwrap_or_default();
+
+        let posisi = |offset: usize| awal.iter().rposition(|&a| a <= offset).unwrap_or(0);
+        let (pa, pb) = (posisi(mulai), posisi(selesai - 1));
+        let (oa, ob) = (mulai - awal[pa], selesai - awal[pb]);
+        if pa == pb {
+            potongan[teks_idx[pa]].1.replace_range(oa..ob, &pengganti);
+        } else {
+            potongan[teks_idx[pa]].1.replace_range(oa.., &pengganti);
+            for &i in &teks_idx[pa + 1..pb] {
+                potongan[i].1.clear();
+            }
+            potongan[teks_idx[pb]].1.replace_range(..ob, "");
+        }
+        cari_dari = mulai + pengganti.len();
+    }
+
+    potongan.into_iter().map(|(_, p)| p).collect()
+}
+
+/// Nomor urut terakhir yang sudah dipakai untuk pola dan tahun surat, 0 bila belum ada. SKL dan surat pindah
+/// berbagi tabel `dapomaster.nomor_skl`; keduanya dibedakan oleh polanya.
+pub async fn nomor_surat_terakhir(pool: &sqlx::PgPool, pola: &str, tahun: i32) -> Result<u32, String> {
+    let nomor: Option<i32> = sqlx::query_scalar("SELECT nomor_terakhir FROM dapomaster.nomor_skl WHERE pola = $1 AND tahun = $2")
+        .bind(pola)
+        .bind(tahun)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Gagal mengambil nomor surat terakhir: {}", e))?;
+    Ok(nomor.unwrap_or(0).max(0) as u32)
+}
+
+/// Memesan `jumlah` nomor urut berturut-turut untuk pola dan tahun surat, dimulai dari `nomor_awal` atau nomor
+/// setelah yang terakhir dipakai, dan mengembalikan nomor pertamanya. Baris penghitung dikunci selama pemesanan
+/// agar pembuatan surat yang berjalan bersamaan tidak memakai rentang yang sama. Nomor awal yang sudah terpakai
+/// ditolak; nomor yang dipesan tetap terpakai meskipun dokumen gagal disimpan.
+pub async fn pesan_nomor_surat(
+    pool: &sqlx::PgPool,
+    jenis_surat: &str,
+    pola: &str,
+    tahun: i32,
+    nomor_awal: Option<u32>,
+    jumlah: u32,
+) -> Result<u32, String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO dapomaster.nomor_skl (pola, tahun, nomor_terakhir, last_update) VALUES ($1, $2, 0, NOW())
+         ON CONFLICT (pola, tahun) DO NOTHING"
+    )
+    .bind(pola)
+    .bind(tahun)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal menyiapkan nomor {}: {}", jenis_surat, e))?;
+    let terakhir: i32 = sqlx::query_scalar("SELECT nomor_terakhir FROM dapomaster.nomor_skl WHERE pola = $1 AND tahun = $2 FOR UPDATE")
+        .bind(pola)
+        .bind(tahun)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Gagal mengambil nomor {} terakhir: {}", jenis_surat, e))?;
+    let terakhir = terakhir.max(0) as u32;
+
+    let awal = match nomor_awal {
+        Some(n) if n <= terakhir => return Err(format!(
+            "Nomor urut {} sudah terpakai untuk {} tahun {}; nomor berikutnya {}.",
+            n, jenis_surat, tahun, terakhir + 1
+        )),
+        Some(n) => n,
+        None => terakhir + 1,
+    };
+    sqlx::query("UPDATE dapomaster.nomor_skl SET nomor_terakhir = $3, last_update = NOW() WHERE pola = $1 AND tahun = $2")
+        .bind(pola)
+        .bind(tahun)
+        .bind((awal + jumlah.max(1) - 1) as i32)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Gagal menyimpan nomor {} terakhir: {}", jenis_surat, e))?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(awal)
+}
+
+/// Mengisi template DOCX; placeholder di isi dokumen, header, dan footer ikut diganti
+pub fn isi_template_docx(template: &[u8], nilai: &HashMap<String, String>) -> Result<Vec<u8>, String> {
+    let mut arsip = ZipArchive::new(Cursor::new(template)).map_err(|e| format!("Template DOCX tidak valid: {}", e))?;
+    let mut hasil = ZipWriter::new(Cursor::new(Vec::new()));
+    let opsi = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    for i in 0..arsip.len() {
+        let mut berkas = arsip.by_index(i).map_err(|e| format!("Gagal membaca template: {}", e))?;
+        let nama = berkas.name().to_string();
+        let mut isi = Vec::new();
+        berkas.read_to_end(&mut isi).map_err(|e| format!("Gagal membaca template: {}", e))?;
+
+        let perlu_diisi = nama == "word/document.xml"
+            || (nama.starts_with("word/header") || nama.starts_with("word/footer")) && nama.ends_with(".xml");
+        if perlu_diisi {
+            let xml = String::from_utf8(isi).map_err(|e| format!("Template DOCX tidak valid: {}", e))?;
+            isi = isi_placeholder_xml(&xml, nilai).into_bytes();
+        }
+
+        hasil.start_file(nama, opsi).map_err(|e| format!("Gagal menulis dokumen: {}", e))?;
+        hasil.write_all(&isi).map_err(|e| format!("Gagal menulis dokumen: {}", e))?;
+    }
+
+    let cursor = hasil.finish().map_err(|e| format!("Gagal menulis dokumen: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
+/// Mengemas beberapa berkas menjadi satu ZIP
+pub fn buat_zip(berkas: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let opsi = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (nama, isi) in berkas {
+        zip.start_file(nama.as_str(), opsi).map_err(|e| format!("Gagal menulis ZIP: {}", e))?;
+        zip.write_all(isi).map_err(|e| format!("Gagal menulis ZIP: {}", e))?;
+    }
+    let cursor = zip.finish().map_err(|e| format!("Gagal menulis ZIP: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
+/// Nama berkas yang aman untuk semua sistem operasi
+pub fn nama_berkas_aman(nama: &str) -> String {
+    nama.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Identitas siswa dan sekolah yang dimuat di setiap surat keterangan (SKL, surat pindah)
+#[derive(sqlx::FromRow)]
+pub struct IdentitasSurat {
+    pub nama: String,
+    pub nisn: Option<String>,
+    pub nipd: Option<String>,
+    pub nik: Option<String>,
+    pub tempat_lahir: Option<String>,
+    pub tanggal_lahir: Option<NaiveDate>,
+    pub jenis_kelamin: Option<String>,
+    pub nama_ayah: Option<String>,
+    pub nama_ibu_kandung: Option<String>,
+    pub nama_sekolah: String,
+    pub npsn: Option<String>,
+    pub alamat_sekolah: Option<String>,
+}
+
+/// Tanggal dalam format surat, atau teks kosong bila tanggal tidak ada
+pub fn tanggal_atau_kosong(tanggal: Option<NaiveDate>) -> String {
+    tanggal.map(tanggal_indonesia).unwrap_or_default()
+}
+
+pub fn teks_atau_kosong(teks: &Option<String>) -> String {
+    teks.clone().unwrap_or_default()
+}
+
+/// Kode jenis kelamin Dapodik (L/P) dalam bentuk kata
+pub fn nama_jenis_kelamin(kode: Option<&str>) -> &'static str {
+    match kode.map(str::trim) {
+        Some("L") => "Laki-laki",
+        Some("P") => "Perempuan",
+        _ => "",
+    }
+}
+
+/// Placeholder nomor dan tanggal surat serta identitas siswa dan sekolah, sama untuk semua surat keterangan
+pub fn placeholder_identitas(d: &IdentitasSurat, nomor_surat: &str, tanggal_surat: NaiveDate) -> Vec<(&'static str, String)> {
+    vec![
+        ("nomor_surat", nomor_surat.to_string()),
+        ("tanggal_surat", tanggal_indonesia(tanggal_surat)),
+        ("nama", d.nama.clone()),
+        ("nisn", teks_atau_kosong(&d.nisn)),
+        ("nipd", teks_atau_kosong(&d.nipd)),
+        ("nik", teks_atau_kosong(&d.nik)),
+        ("tempat_lahir", teks_atau_kosong(&d.tempat_lahir)),
+        ("tanggal_lahir", tanggal_atau_kosong(d.tanggal_lahir)),
+        ("tempat_tanggal_lahir", format!("{}, {}", teks_atau_kosong(&d.tempat_lahir), tanggal_atau_kosong(d.tanggal_lahir))),
+        ("jenis_kelamin", nama_jenis_kelamin(d.jenis_kelamin.as_deref()).to_string()),
+        ("nama_ayah", teks_atau_kosong(&d.nama_ayah)),
+        ("nama_ibu", teks_atau_kosong(&d.nama_ibu_kandung)),
+        ("nama_sekolah", d.nama_sekolah.clone()),
+        ("npsn", teks_atau_kosong(&d.npsn)),
+        ("alamat_sekolah", teks_atau_kosong(&d.alamat_sekolah)),
+    ]
+}
+
+pub const LEBAR_A4: f32 = 595.0;
+pub const TINGGI_A4: f32 = 842.0;
+
+/// Lebar glyph Helvetica menurut metrik AFM standar (satuan 1/1000 em) untuk karakter 32-126 dan 160-255
+/// pada WinAnsiEncoding
+const LEBAR_HELVETICA_ASCII: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+const LEBAR_HELVETICA_LATIN1: [u16; 96] = [
+    278, 333, 556, 556, 556, 556, 260, 556, 333, 737, 370, 556, 584, 333, 737, 333,
+    400, 584, 333, 333, 333, 556, 537, 278, 333, 333, 365, 556, 834, 834, 834, 611,
+    667, 667, 667, 667, 667, 667, 1000, 722, 667, 667, 667, 667, 278, 278, 278, 278,
+    722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611,
+    556, 556, 556, 556, 556, 556, 889, 500, 556, 556, 556, 556, 278, 278, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 584, 611, 556, 556, 556, 556, 500, 556, 500,
+];
+const LEBAR_HELVETICA_BOLD_ASCII: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+const LEBAR_HELVETICA_BOLD_LATIN1: [u16; 96] = [
+    278, 333, 556, 556, 556, 556, 280, 556, 333, 737, 370, 556, 584, 333, 737, 333,
+    400, 584, 333, 333, 333, 611, 556, 278, 333, 333, 365, 556, 834, 834, 834, 611,
+    722, 722, 722, 722, 722, 722, 1000, 722, 667, 667, 667, 667, 278, 278, 278, 278,
+    722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611,
+    556, 556, 556, 556, 556, 556, 889, 556, 556, 556, 556, 556, 278, 278, 278, 278,
+    611, 611, 611, 611, 611, 611, 611, 584, 611, 611, 611, 611, 611, 556, 611, 556,
+];
+
+/// Lebar satu karakter seperti yang ditulis `DokumenPdf::teks`; karakter lain dicetak sebagai "?"
+fn lebar_huruf(c: char, tebal: bool) -> u16 {
+    let (ascii, latin1) = if tebal {
+        (&LEBAR_HELVETICA_BOLD_ASCII, &LEBAR_HELVETICA_BOLD_LATIN1)
+    } else {
+        (&LEBAR_HELVETICA_ASCII, &LEBAR_HELVETICA_LATIN1)
+    };
+    match c as u32 {
+        k @ 32..=126 => ascii[(k - 32) as usize],
+        k @ 160..=255 => latin1[(k - 160) as usize],
+        _ => ascii[('?' as u32 - 32) as usize],
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rata {
+    Kiri,
+    Tengah,
+}
+
+/// Penulis PDF minimal: beberapa halaman A4 berisi teks Helvetica. Karakter di luar Latin-1 diganti "?".
+#[derive(Default)]
+pub struct DokumenPdf {
+    halaman: Vec<Vec<u8>>,
+}
+
+impl DokumenPdf {
+    pub fn halaman_baru(&mut self) {
+        self.halaman.push(Vec::new());
+    }
+
+    /// Lebar teks Helvetica atau Helvetica-Bold dalam point, untuk rata tengah dan pemenggalan baris
+    pub fn lebar_teks(teks: &str, ukuran: f32, tebal: bool) -> f32 {
+        teks.chars().map(|c| lebar_huruf(c, tebal) as f32).sum::<f32>() * ukuran / 1000.0
+    }
+
+    pub fn teks(&mut self, x: f32, y: f32, ukuran: f32, tebal: bool, rata: Rata, teks: &str) {
+        let x = match rata {
+            Rata::Kiri => x,
+            Rata::Tengah => x - Self::lebar_teks(teks, ukuran, tebal) / 2.0,
+        };
+        let Some(isi) = self.halaman.last_mut() else { return };
+        let font = if tebal { "F2" } else { "F1" };
+        isi.extend_from_slice(format!("BT /{} {} Tf {:.1} {:.1} Td (", font, ukuran, x, y).as_bytes());
+        for c in teks.chars() {
+            match c {
+                '(' | ')' | '\\' => isi.extend_from_slice(&[b'\\', c as u8]),
+                c if (c as u32) < 256 => isi.push(c as u32 as u8),
+                _ => isi.push(b'?'),
+            }
+        }
+        isi.extend_from_slice(b") Tj ET\n");
+    }
+
+    pub fn garis(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        if let Some(isi) = self.halaman.last_mut() {
+            isi.extend_from_slice(format!("{:.1} {:.1} m {:.1} {:.1} l S\n", x1, y1, x2, y2).as_bytes());
+        }
+    }
+
+    /// Menulis paragraf dengan pemenggalan kata; mengembalikan posisi y setelah baris terakhir
+    pub fn paragraf(&mut self, x: f32, mut y: f32, lebar: f32, ukuran: f32, teks: &str) -> f32 {
+        let mut baris = String::new();
+        for kata in teks.split_whitespace() {
+            let calon = if baris.is_empty() { kata.to_string() } else { format!("{} {}", baris, kata) };
+            if !baris.is_empty() && Self::lebar_teks(&calon, ukuran, false) > lebar {
+                self.teks(x, y, ukuran, false, Rata::Kiri, &baris);
+                y -= ukuran * 1.5;
+                baris = kata.to_string();
+            } else {
+                baris = calon;
+            }
+        }
+        if !baris.is_empty() {
+            self.teks(x, y, ukuran, false, Rata::Kiri, &baris);
+            y -= ukuran * 1.5;
+        }
+        y
+    }
+
+    pub fn simpan(&self) -> Vec<u8> {
+        let mut objek: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            Vec::new(), // daftar halaman, diisi setelah nomor objek halaman diketahui
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+        ];
+        let mut kids = Vec::new();
+        for isi in &self.halaman {
+            let nomor_halaman = objek.len() + 1;
+            kids.push(format!("{} 0 R", nomor_halaman));
+            objek.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                LEBAR_A4, TINGGI_A4, nomor_halaman + 1
+            ).into_bytes());
+            let mut stream = format!("<< /Length {} >>\nstream\n", isi.len()).into_bytes();
+            stream.extend_from_slice(isi);
+            stream.extend_from_slice(b"\nendstream");
+            objek.push(stream);
+        }
+        objek[1] = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), kids.len()).into_bytes();
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offset = Vec::with_capacity(objek.len());
+        for (i, isi) in objek.iter().enumerate() {
+            offset.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            pdf.extend_from_slice(isi);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+        let awal_xref = pdf.len();
+        pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objek.len() + 1).as_bytes());
+        for o in offset {
+            pdf.extend_from_slice(format!("{:010} 00000 n \n", o).as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objek.len() + 1, awal_xref).as_bytes(),
+        );
+        pdf
+    }
+}
+
+/// Kop surat bawaan: nama sekolah, NPSN, dan alamat di halaman aktif; mengembalikan posisi y garis kop
+pub fn kop_surat(pdf: &mut DokumenPdf, nilai: &HashMap<String, String>, kiri: f32) -> f32 {
+    let v = |k: &str| nilai.get(k).map(String::as_str).unwrap_or_default();
+    let tengah = LEBAR_A4 / 2.0;
+    let mut y = TINGGI_A4 - 70.0;
+    pdf.teks(tengah, y, 14.0, true, Rata::Tengah, &v("nama_sekolah").to_uppercase());
+    y -= 16.0;
+    pdf.teks(tengah, y, 10.0, false, Rata::Tengah, &format!("NPSN: {}", v("npsn")));
+    y -= 14.0;
+    pdf.teks(tengah, y, 10.0, false, Rata::Tengah, v("alamat_sekolah"));
+    y -= 10.0;
+    pdf.garis(kiri, y, LEBAR_A4 - kiri, y);
+    y
+}
+
+/// Baris "label : isi" data siswa; mengembalikan posisi y setelah baris terakhir
+pub fn tabel_isian(pdf: &mut DokumenPdf, kiri: f32, mut y: f32, baris: &[(&str, &str)]) -> f32 {
+    for (label, isi) in baris {
+        pdf.teks(kiri + 20.0, y, 11.0, false, Rata::Kiri, label);
+        pdf.teks(kiri + 170.0, y, 11.0, false, Rata::Kiri, &format!(": {}", isi));
+        y -= 18.0;
+    }
+    y
+}
+
+/// Tempat dan tanggal surat serta tanda tangan kepala sekolah di sisi kanan
+pub fn tanda_tangan(pdf: &mut DokumenPdf, nilai: &HashMap<String, String>, kiri: f32, mut y: f32) {
+    let v = |k: &str| nilai.get(k).map(String::as_str).unwrap_or_default();
+    let x_ttd = LEBAR_A4 - kiri - 200.0;
+    let tempat = v("tempat_surat");
+    let tanggal = if tempat.is_empty() { v("tanggal_surat").to_string() } else { format!("{}, {}", tempat, v("tanggal_surat")) };
+    pdf.teks(x_ttd, y, 11.0, false, Rata::Kiri, &tanggal);
+    y -= 16.0;
+    pdf.teks(x_ttd, y, 11.0, false, Rata::Kiri, "Kepala Sekolah,");
+    y -= 70.0;
+    pdf.teks(x_ttd, y, 11.0, true, Rata::Kiri, v("kepala_sekolah"));
+    if !v("nip_kepala_sekolah").is_empty() {
+        y -= 14.0;
+        pdf.teks(x_ttd, y, 11.0, false, Rata::Kiri, &format!("NIP. {}", v("nip_kepala_sekolah")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tanggal(tahun: i32, bulan: u32, hari: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(tahun, bulan, hari).unwrap()
+    }
+
+    #[test]
+    fn nomor_surat_dari_pola() {
+        let t = tanggal(2025, 6, 2);
+        assert_eq!(format_nomor_surat("{urut:3}/SKL/{bulan_romawi}/{tahun}", 7, t), Ok("007/SKL/VI/2025".to_string()));
+        assert_eq!(format_nomor_surat("421.2/{urut}/SPD/{tahun}", 1234, t), Ok("421.2/1234/SPD/2025".to_string()));
+        // Nomor yang lebih panjang dari lebar tidak dipotong
+        assert_eq!(format_nomor_surat("{urut:2}", 123, t), Ok("123".to_string()));
+    }
+
+    #[test]
+    fn bulan_romawi_desember_dan_januari() {
+        assert_eq!(format_nomor_surat("{urut}/{bulan_romawi}", 1, tanggal(2025, 12, 31)), Ok("1/XII".to_string()));
+        assert_eq!(format_nomor_surat("{urut}/{bulan_romawi}", 1, tanggal(2026, 1, 1)), Ok("1/I".to_string()));
+    }
+
+    #[test]
+    fn pola_nomor_surat_tidak_valid() {
+        let t = tanggal(2025, 6, 2);
+        assert!(format_nomor_surat("SKL/{tahun}", 1, t).is_err());
+        assert!(format_nomor_surat("{nomor}/SKL/{tahun}", 1, t).is_err());
+        assert!(format_nomor_surat("{urut:0}/SKL", 1, t).is_err());
+        assert!(format_nomor_surat("{urut:11}/SKL", 1, t).is_err());
+        assert!(format_nomor_surat("{urut:x}/SKL", 1, t).is_err());
+    }
+}
@@ -1,4 +1,6 @@
-use crate::app_state::DbPool;
+use crate::app_state::{DbPool, ReferensiCache, WilayahCache};
+use crate::commands::referensi::ambil_referensi;
+use crate::commands::wilayah::{ambil_index_wilayah, WilayahIndex, LEVEL_KABUPATEN, LEVEL_KECAMATAN};
 use tauri::{AppHandle, State};
 use serde::Serialize;
 use chrono::{DateTime, Utc, NaiveDate};
@@ -6,7 +8,6 @@ use sqlx::types::Uuid as SqlxUuid;
 use bigdecimal::BigDecimal;
 use rust_xlsxwriter::{Workbook, Format, FormatAlign, FormatBorder};
 use calamine::{Reader, open_workbook_auto, DataType};
-use std::collections::HashMap;
 
 #[tauri::command]
 pub async fn open_import_dialog() -> Result<Option<String>, String> {
@@ -20,6 +21,14 @@ pub async fn open_import_dialog() -> Result<Option<String>, String> {
     Ok(file_path.map(|path| path.to_string_lossy().to_string()))
 }
 
+/// Daftar nama sheet di file Excel, agar operator dapat memilih sheet yang akan diimport
+#[tauri::command]
+pub async fn get_daftar_sheet_excel(file_path: String) -> Result<Vec<String>, String> {
+    let workbook = open_workbook_auto(&file_path)
+        .map_err(|e| format!("Gagal membuka file Excel: {}", e))?;
+    Ok(workbook.sheet_names().to_vec())
+}
+
 #[derive(Serialize)]
 pub struct ExportResult {
     pub success: bool,
@@ -48,22 +57,25 @@ pub struct ImportError {
 pub async fn export_lulusan_to_excel(
     app: AppHandle,
     state: State<'_, DbPool>,
+    cache: State<'_, ReferensiCache>,
+    wilayah_cache: State<'_, WilayahCache>,
 ) -> Result<ExportResult, String> {
     crate::emit_log(&app, "CMD: export_lulusan_to_excel - Starting export process.");
+    let referensi = ambil_referensi(&state.pool, &cache).await?;
+    let wilayah = ambil_index_wilayah(&state.pool, &wilayah_cache).await?;
 
     // Ambil semua data lulusan (tanpa kolom yang dihapus)
     let query = "SELECT pd.peserta_didik_id, pd.nama, pd.nisn, pd.tanggal_lahir,
                         pd.nama_ayah, pd.nama_ibu_kandung, ip.jenis_ijazah_id,
-                        ji.nama as nama_ijazah, ip.nomor, ip.penandatangan,
-                        ip.tanggal_ttd
+                        ip.nomor, ip.penandatangan,
+                        ip.tanggal_ttd, pd.kode_wilayah
                  FROM peserta_didik pd 
                  JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id 
                  LEFT JOIN ijazah_pd ip ON rpd.registrasi_id = ip.registrasi_id
-                 LEFT JOIN ref.jenis_ijazah ji ON ip.jenis_ijazah_id = ji.jenis_ijazah_id
                  WHERE pd.soft_delete = 0 AND rpd.jenis_keluar_id = '1'
                  ORDER BY pd.nama";
 
-    let rows = sqlx::query_as::<_, (SqlxUuid, String, String, NaiveDate, Option<String>, String, Option<BigDecimal>, Option<String>, Option<String>, Option<String>, Option<NaiveDate>)>(query)
+    let rows = sqlx::query_as::<_, (SqlxUuid, String, String, NaiveDate, Option<String>, String, Option<BigDecimal>, Option<String>, Option<String>, Option<NaiveDate>, Option<String>)>(query)
         .fetch_all(&state.pool)
         .await
         .map_err(|e| e.to_string())?;
@@ -109,7 +121,7 @@ pub async fn export_lulusan_to_excel(
     // Header (tanpa kolom yang dihapus)
     let headers = [
         "No", "Nama", "NISN", "Tanggal Lahir", "Nama Ayah", "Nama Ibu", 
-        "Jenis Ijazah", "Nomor Ijazah", "Penandatangan", "Tanggal Tanda Tangan"
+        "Jenis Ijazah", "Nomor Ijazah", "Penandatangan", "Tanggal Tanda Tangan", "Kecamatan", "Kabupaten/Kota"
     ];
 
     // Tulis header
@@ -148,28 +160,35 @@ pub async fn export_lulusan_to_excel(
             .map_err(|e| format!("Gagal menulis nama ibu: {}", e))?;
         
         // Jenis Ijazah
-        let jenis_ijazah = row.7.as_deref().unwrap_or("-");
+        let jenis_ijazah = row.6.as_ref().and_then(|id| referensi.nama_jenis_ijazah(id)).unwrap_or("-");
         worksheet.write_string_with_format(row_num, 6, jenis_ijazah, &data_format)
             .map_err(|e| format!("Gagal menulis jenis ijazah: {}", e))?;
         
         // Nomor Ijazah
-        let nomor_ijazah = row.8.as_deref().unwrap_or("-");
+        let nomor_ijazah = row.7.as_deref().unwrap_or("-");
         worksheet.write_string_with_format(row_num, 7, nomor_ijazah, &data_format)
             .map_err(|e| format!("Gagal menulis nomor ijazah: {}", e))?;
         
         // Penandatangan
-        let penandatangan = row.9.as_deref().unwrap_or("-");
+        let penandatangan = row.8.as_deref().unwrap_or("-");
         worksheet.write_string_with_format(row_num, 8, penandatangan, &data_format)
             .map_err(|e| format!("Gagal menulis penandatangan: {}", e))?;
         
         // Tanggal Tanda Tangan
-        if let Some(tanggal_tanda_tangan) = row.10 {
+        if let Some(tanggal_tanda_tangan) = row.9 {
             worksheet.write_string_with_format(row_num, 9, &tanggal_tanda_tangan.format("%Y-%m-%d").to_string(), &date_format)
                 .map_err(|e| format!("Gagal menulis tanggal tanda tangan: {}", e))?;
         } else {
             worksheet.write_string_with_format(row_num, 9, "-", &data_format)
                 .map_err(|e| format!("Gagal menulis tanggal tanda tangan: {}", e))?;
         }
+
+        // Kecamatan dan Kabupaten/Kota
+        let (kecamatan, kabupaten) = nama_kecamatan_kabupaten(&wilayah, row.10.as_deref());
+        worksheet.write_string_with_format(row_num, 10, kecamatan, &data_format)
+            .map_err(|e| format!("Gagal menulis kecamatan: {}", e))?;
+        worksheet.write_string_with_format(row_num, 11, kabupaten, &data_format)
+            .map_err(|e| format!("Gagal menulis kabupaten: {}", e))?;
     }
 
     // Set lebar kolom otomatis
@@ -188,117 +207,27 @@ pub async fn export_lulusan_to_excel(
     })
 }
 
-#[tauri::command]
-pub async fn import_lulusan_from_excel(
-    app: AppHandle,
-    state: State<'_, DbPool>,
-    file_path: String,
-) -> Result<ImportResult, String> {
-    crate::emit_log(&app, "CMD: import_lulusan_from_excel - Starting import process.");
-
-    let mut workbook = open_workbook_auto(&file_path)
-        .map_err(|e| format!("Gagal membuka file Excel: {}", e))?;
-
-    let range = workbook.worksheet_range_at(0)
-        .ok_or("Tidak dapat menemukan worksheet")?
-        .map_err(|e| format!("Gagal membaca worksheet: {}", e))?;
-
-    let mut success_count = 0;
-    let mut error_count = 0;
-    let mut errors = Vec::new();
-    let mut total_rows = 0;
-
-    // Ambil semua jenis ijazah untuk validasi
-    let jenis_ijazah_map = get_jenis_ijazah_map(&state).await?;
-
-    // Mulai dari baris 2 (setelah header)
-    for (row_idx, row) in range.rows().skip(1).enumerate() {
-        total_rows += 1;
-        let excel_row = row_idx + 2; // +2 karena skip header dan index dimulai dari 0
-
-        if row.len() < 6 {
-            errors.push(ImportError {
-                row: excel_row as i32,
-                field: "Semua".to_string(),
-                message: "Jumlah kolom tidak mencukupi".to_string(),
-            });
-            error_count += 1;
-            continue;
-        }
-
-        // Parse data dari Excel
-        let nama = get_string_value(&row[1], "Nama", excel_row, &mut errors);
-        let nisn = get_string_value(&row[2], "NISN", excel_row, &mut errors);
-        let nama_ibu = get_string_value(&row[5], "Nama Ibu", excel_row, &mut errors);
-        
-        let jenis_ijazah = get_optional_string_value(&row[6], "Jenis Ijazah", excel_row, &mut errors);
-        let nomor_ijazah = get_optional_string_value(&row[7], "Nomor Ijazah", excel_row, &mut errors);
-        let penandatangan = get_optional_string_value(&row[8], "Penandatangan", excel_row, &mut errors);
-        let tanggal_tanda_tangan = get_optional_date_value(&row[9], "Tanggal Tanda Tangan", excel_row, &mut errors);
-
-        // Jika ada error pada data wajib, skip baris ini
-        if nama.is_none() || nisn.is_none() || nama_ibu.is_none() {
-            error_count += 1;
-            continue;
-        }
-
-        let nama = nama.unwrap();
-        let nisn = nisn.unwrap();
-        let nama_ibu = nama_ibu.unwrap();
-
-        // Validasi data dengan database
-        match validate_and_update_data(&state, &nama, &nisn, &nama_ibu, &jenis_ijazah, &nomor_ijazah, &penandatangan, &tanggal_tanda_tangan, &jenis_ijazah_map, excel_row, &mut errors).await {
-            Ok(_) => {
-                success_count += 1;
-                crate::emit_log(&app, &format!("CMD: import_lulusan_from_excel - Successfully processed row {}: {} ({})", excel_row, nama, nisn));
-            }
-            Err(e) => {
-                error_count += 1;
-                errors.push(ImportError {
-                    row: excel_row as i32,
-                    field: "Database".to_string(),
-                    message: e,
-                });
-            }
-        }
-    }
-
-    let message = if error_count == 0 {
-        format!("Berhasil mengimport semua {} data lulusan", success_count)
-    } else {
-        format!("Berhasil mengimport {} data, {} error ditemukan", success_count, error_count)
-    };
-
-    crate::emit_log(&app, &format!("CMD: import_lulusan_from_excel - Import completed. Success: {}, Errors: {}", success_count, error_count));
-
-    Ok(ImportResult {
-        success: error_count == 0,
-        message,
-        total_rows,
-        success_count,
-        error_count,
-        errors,
-    })
-}
-
 #[tauri::command]
 pub async fn export_siswa_keluar_to_excel(
     app: AppHandle,
     state: State<'_, DbPool>,
+    cache: State<'_, ReferensiCache>,
+    wilayah_cache: State<'_, WilayahCache>,
 ) -> Result<ExportResult, String> {
     crate::emit_log(&app, "CMD: export_siswa_keluar_to_excel - Starting export process.");
+    let referensi = ambil_referensi(&state.pool, &cache).await?;
+    let wilayah = ambil_index_wilayah(&state.pool, &wilayah_cache).await?;
 
     // Ambil semua data siswa keluar
     let query = "SELECT pd.peserta_didik_id, pd.nama, pd.nisn, pd.nik, pd.tanggal_lahir,
                         pd.nama_ayah, pd.nama_ibu_kandung, rpd.jenis_keluar_id,
-                        jk.nama as ket_keluar, rpd.tanggal_keluar
+                        rpd.tanggal_keluar, pd.kode_wilayah
                  FROM peserta_didik pd 
                  JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id 
-                 LEFT JOIN ref.jenis_keluar jk ON rpd.jenis_keluar_id = jk.jenis_keluar_id
                  WHERE pd.soft_delete = 0 AND rpd.jenis_keluar_id IS NOT NULL AND rpd.jenis_keluar_id != '1'
                  ORDER BY pd.nama";
 
-    let rows = sqlx::query_as::<_, (SqlxUuid, String, String, Option<String>, NaiveDate, Option<String>, String, Option<String>, Option<NaiveDate>)>(query)
+    let rows = sqlx::query_as::<_, (SqlxUuid, String, String, Option<String>, NaiveDate, Option<String>, String, Option<String>, Option<NaiveDate>, Option<String>)>(query)
         .fetch_all(&state.pool)
         .await
         .map_err(|e| e.to_string())?;
@@ -344,7 +273,7 @@ pub async fn export_siswa_keluar_to_excel(
     // Header
     let headers = [
         "No", "Nama", "NISN", "NIK", "Tanggal Lahir", "Nama Ayah", "Nama Ibu", 
-        "Alasan Keluar", "Tanggal Keluar"
+        "Alasan Keluar", "Tanggal Keluar", "Kecamatan", "Kabupaten/Kota"
     ];
 
     // Tulis header
@@ -388,7 +317,7 @@ pub async fn export_siswa_keluar_to_excel(
             .map_err(|e| format!("Gagal menulis nama ibu: {}", e))?;
         
         // Alasan Keluar
-        let ket_keluar = row.7.as_deref().unwrap_or("Tidak diketahui");
+        let ket_keluar = row.7.as_deref().and_then(|id| referensi.nama_jenis_keluar(id)).unwrap_or("Tidak diketahui");
         worksheet.write_string_with_format(row_num, 7, ket_keluar, &data_format)
             .map_err(|e| format!("Gagal menulis alasan keluar: {}", e))?;
         
@@ -400,6 +329,13 @@ pub async fn export_siswa_keluar_to_excel(
             worksheet.write_string_with_format(row_num, 8, "-", &data_format)
                 .map_err(|e| format!("Gagal menulis tanggal keluar: {}", e))?;
         }
+
+        // Kecamatan dan Kabupaten/Kota
+        let (kecamatan, kabupaten) = nama_kecamatan_kabupaten(&wilayah, row.9.as_deref());
+        worksheet.write_string_with_format(row_num, 9, kecamatan, &data_format)
+            .map_err(|e| format!("Gagal menulis kecamatan: {}", e))?;
+        worksheet.write_string_with_format(row_num, 10, kabupaten, &data_format)
+            .map_err(|e| format!("Gagal menulis kabupaten: {}", e))?;
     }
 
     // Set lebar kolom otomatis
@@ -419,22 +355,17 @@ pub async fn export_siswa_keluar_to_excel(
 }
 
 // Helper functions
-async fn get_jenis_ijazah_map(state: &State<'_, DbPool>) -> Result<HashMap<String, BigDecimal>, String> {
-    let rows = sqlx::query_as::<_, (BigDecimal, String)>(
-        "SELECT jenis_ijazah_id, nama FROM ref.jenis_ijazah ORDER BY nama"
-    )
-    .fetch_all(&state.pool)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    let mut map = HashMap::new();
-    for (id, nama) in rows {
-        map.insert(nama.to_lowercase(), id);
-    }
-    Ok(map)
+/// Nama kecamatan dan kabupaten/kota dari kode wilayah siswa, "-" bila tidak ditemukan di referensi
+fn nama_kecamatan_kabupaten<'a>(wilayah: &'a WilayahIndex, kode_wilayah: Option<&str>) -> (&'a str, &'a str) {
+    let nama = |level| {
+        kode_wilayah
+            .and_then(|kode| wilayah.induk_pada_level(kode, level))
+            .map_or("-", |w| w.nama.as_str())
+    };
+    (nama(LEVEL_KECAMATAN), nama(LEVEL_KABUPATEN))
 }
 
-fn get_string_value(cell: &DataType, field_name: &str, row: usize, errors: &mut Vec<ImportError>) -> Option<String> {
+pub(crate) fn get_string_value(cell: &DataType, field_name: &str, row: usize, errors: &mut Vec<ImportError>) -> Option<String> {
     match cell {
         DataType::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
         DataType::Int(i) => Some(i.to_string()),
@@ -450,7 +381,7 @@ fn get_string_value(cell: &DataType, field_name: &str, row: usize, errors: &mut
     }
 }
 
-fn get_optional_string_value(cell: &DataType, _field_name: &str, _row: usize, _errors: &mut Vec<ImportError>) -> Option<String> {
+pub(crate) fn get_optional_string_value(cell: &DataType, _field_name: &str, _row: usize, _errors: &mut Vec<ImportError>) -> Option<String> {
     match cell {
         DataType::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
         DataType::Int(i) => Some(i.to_string()),
@@ -459,7 +390,14 @@ fn get_optional_string_value(cell: &DataType, _field_name: &str, _row: usize, _e
     }
 }
 
-fn get_optional_date_value(cell: &DataType, field_name: &str, row: usize, errors: &mut Vec<ImportError>) -> Option<String> {
+/// Parse tanggal hasil import dengan format yang diterima template (YYYY-MM-DD, DD/MM/YYYY, DD-MM-YYYY)
+pub(crate) fn parse_tanggal_import(tgl_str: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%d/%m/%Y", "%d-%m-%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(tgl_str.trim(), format).ok())
+}
+
+pub(crate) fn get_optional_date_value(cell: &DataType, field_name: &str, row: usize, errors: &mut Vec<ImportError>) -> Option<String> {
     match cell {
         DataType::String(s) if !s.trim().is_empty() => {
             // Coba parse berbagai format tanggal
@@ -539,119 +477,3 @@ fn get_optional_date_value(cell: &DataType, field_name: &str, row: usize, errors
         _ => None
     }
 }
-
-async fn validate_and_update_data(
-    state: &State<'_, DbPool>,
-    nama: &str,
-    nisn: &str,
-    nama_ibu: &str,
-    jenis_ijazah: &Option<String>,
-    nomor_ijazah: &Option<String>,
-    penandatangan: &Option<String>,
-    tanggal_tanda_tangan: &Option<String>,
-    jenis_ijazah_map: &HashMap<String, BigDecimal>,
-    row: usize,
-    errors: &mut Vec<ImportError>,
-) -> Result<(), String> {
-    // Cari siswa berdasarkan nama, NISN, dan nama ibu
-    let siswa = sqlx::query_as::<_, (SqlxUuid, String)>(
-        "SELECT pd.peserta_didik_id, pd.nama 
-         FROM peserta_didik pd 
-         JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id 
-         WHERE pd.soft_delete = 0 AND rpd.jenis_keluar_id = '1' 
-         AND LOWER(pd.nama) = LOWER($1) 
-         AND pd.nisn = $2 
-         AND LOWER(pd.nama_ibu_kandung) = LOWER($3)"
-    )
-    .bind(nama)
-    .bind(nisn)
-    .bind(nama_ibu)
-    .fetch_optional(&state.pool)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    let (peserta_didik_id, nama_db) = match siswa {
-        Some(s) => s,
-        None => {
-            errors.push(ImportError {
-                row: row as i32,
-                field: "Data Siswa".to_string(),
-                message: format!("Siswa tidak ditemukan: {} (NISN: {}, Ibu: {})", nama, nisn, nama_ibu),
-            });
-            return Err("Siswa tidak ditemukan".to_string());
-        }
-    };
-
-    // Validasi nama jika berbeda (case insensitive)
-    if nama.to_lowercase() != nama_db.to_lowercase() {
-        errors.push(ImportError {
-            row: row as i32,
-            field: "Nama".to_string(),
-            message: format!("Nama tidak cocok dengan database: '{}' vs '{}'", nama, nama_db),
-        });
-    }
-
-    // Validasi jenis ijazah jika ada
-    let jenis_ijazah_id = if let Some(ref jenis) = jenis_ijazah {
-        match jenis_ijazah_map.get(&jenis.to_lowercase()) {
-            Some(id) => Some(id.clone()),
-            None => {
-                errors.push(ImportError {
-                    row: row as i32,
-                    field: "Jenis Ijazah".to_string(),
-                    message: format!("Jenis ijazah '{}' tidak ditemukan dalam database", jenis),
-                });
-                None
-            }
-        }
-    } else {
-        None
-    };
-
-    // Parse tanggal tanda tangan
-    let tanggal_ttd = if let Some(ref tgl_str) = tanggal_tanda_tangan {
-        match NaiveDate::parse_from_str(tgl_str, "%Y-%m-%d") {
-            Ok(tgl) => Some(tgl),
-            Err(_) => {
-                // Coba format lain
-                match NaiveDate::parse_from_str(tgl_str, "%d/%m/%Y") {
-                    Ok(tgl) => Some(tgl),
-                    Err(_) => {
-                        match NaiveDate::parse_from_str(tgl_str, "%d-%m-%Y") {
-                            Ok(tgl) => Some(tgl),
-                            Err(_) => {
-                                errors.push(ImportError {
-                                    row: row as i32,
-                                    field: "Tanggal Tanda Tangan".to_string(),
-                                    message: format!("Format tanggal tidak valid: {}", tgl_str),
-                                });
-                                None
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    } else {
-        None
-    };
-
-    // Update data ijazah
-    let result = sqlx::query(
-        "UPDATE ijazah_pd 
-         SET jenis_ijazah_id = $1, nomor = $2, penandatangan = $3, tanggal_ttd = $4, last_update = NOW()
-         WHERE registrasi_id = (SELECT registrasi_id FROM registrasi_peserta_didik WHERE peserta_didik_id = $5)"
-    )
-    .bind(&jenis_ijazah_id)
-    .bind(nomor_ijazah)
-    .bind(penandatangan)
-    .bind(tanggal_ttd)
-    .bind(peserta_didik_id)
-    .execute(&state.pool)
-    .await;
-
-    match result {
-        Ok(_) => Ok(()),
-        Err(e) => Err(format!("Gagal mengupdate data: {}", e))
-    }
-}
\ No newline at end of file
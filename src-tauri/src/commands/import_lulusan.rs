@@ -0,0 +1,360 @@
+//! Import data ijazah lulusan dari Excel. Kolom dikenali dari nama header (dengan beberapa alias), bukan
+//! dari posisi, sehingga urutan kolom dan baris judul tambahan di atas header tidak menjadi masalah.
+//!
+//! Siswa dicocokkan lewat `peserta_didik_id`, NISN, atau NIK. Tingkat kecocokan menentukan apakah nama
+//! dan nama ibu juga harus sama; baris yang hampir cocok dikembalikan untuk dikonfirmasi operator dan
+//! dapat disimpan lewat `update_bulk_ijazah`.
+
+use crate::app_state::{DbPool, ReferensiCache};
+use crate::commands::export::{get_optional_date_value, get_optional_string_value, parse_tanggal_import, ImportError};
+use crate::commands::lulusan::{simpan_satu_ijazah, UpdateIjazahPayload};
+use crate::commands::referensi::ambil_referensi;
+use tauri::{AppHandle, State};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid as SqlxUuid;
+use calamine::{open_workbook_auto, DataType, Range, Reader};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kolom {
+    Id,
+    Nama,
+    Nisn,
+    Nik,
+    NamaIbu,
+    JenisIjazah,
+    Nomor,
+    Penandatangan,
+    TanggalTtd,
+}
+
+// Alias header yang dikenali, sudah dinormalisasi (huruf kecil, tanda baca menjadi spasi)
+const ALIAS_KOLOM: [(Kolom, &[&str]); 9] = [
+    (Kolom::Id, &["id", "peserta didik id", "id peserta didik", "id siswa"]),
+    (Kolom::Nama, &["nama", "nama siswa", "nama lengkap", "nama peserta didik", "nama pd"]),
+    (Kolom::Nisn, &["nisn", "no nisn", "nomor nisn"]),
+    (Kolom::Nik, &["nik", "no nik", "nomor nik", "nik siswa"]),
+    (Kolom::NamaIbu, &["nama ibu", "nama ibu kandung", "ibu kandung", "ibu"]),
+    (Kolom::JenisIjazah, &["jenis ijazah", "ijazah"]),
+    (Kolom::Nomor, &["nomor ijazah", "no ijazah", "nomor seri ijazah", "no seri ijazah", "nomor blanko", "no blanko"]),
+    (Kolom::Penandatangan, &["penandatangan", "penanda tangan", "kepala sekolah"]),
+    (Kolom::TanggalTtd, &["tanggal tanda tangan", "tanggal ttd", "tgl ttd", "tanggal ijazah", "tgl ijazah"]),
+];
+
+// Baris header dicari di beberapa baris teratas, karena template sering diberi judul oleh sekolah
+const BARIS_HEADER_MAKS: usize = 10;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct OpsiImportLulusan {
+    /// Nama sheet; kosong berarti sheet pertama
+    pub nama_sheet: Option<String>,
+    /// ketat: nama dan nama ibu harus sama; sedang (bawaan): nama harus sama; longgar: cukup ID, NISN, atau NIK
+    pub kecocokan: Option<String>,
+}
+
+/// Baris yang siswanya ditemukan tetapi datanya tidak sepenuhnya cocok
+#[derive(Serialize)]
+pub struct KonfirmasiLulusan {
+    pub row: i32,
+    pub nama_excel: String,
+    pub nama_db: String,
+    pub nisn_db: Option<String>,
+    pub alasan: String,
+    /// Data yang akan disimpan bila dikonfirmasi, siap dikirim ke `update_bulk_ijazah`
+    pub peserta_didik_id: String,
+    pub jenis_ijazah_id: Option<String>,
+    pub nomor: Option<String>,
+    pub penandatangan: Option<String>,
+    pub tanggal_tanda_tangan: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ImportLulusanResult {
+    pub success: bool,
+    pub message: String,
+    pub total_rows: i32,
+    pub success_count: i32,
+    pub error_count: i32,
+    pub errors: Vec<ImportError>,
+    pub perlu_konfirmasi: Vec<KonfirmasiLulusan>,
+}
+
+#[derive(sqlx::FromRow)]
+struct Lulusan {
+    peserta_didik_id: SqlxUuid,
+    nama: String,
+    nisn: Option<String>,
+    nik: Option<String>,
+    nama_ibu_kandung: Option<String>,
+}
+
+fn normalisasi_header(teks: &str) -> String {
+    teks.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Nama dalam huruf besar tanpa gelar tanda baca dan spasi ganda, untuk membandingkan nama Excel dan database
+fn normalisasi_nama(nama: &str) -> String {
+    nama.to_uppercase()
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Mencari baris header dengan kolom dikenali terbanyak, lalu memetakan tiap kolom ke indeksnya
+fn deteksi_header(range: &Range<DataType>) -> Option<(usize, HashMap<usize, Kolom>)> {
+    range
+        .rows()
+        .take(BARIS_HEADER_MAKS)
+        .enumerate()
+        .map(|(idx, row)| {
+            let mut peta = HashMap::new();
+            for (col, cell) in row.iter().enumerate() {
+                let header = normalisasi_header(&cell.to_string());
+                if let Some((kolom, _)) = ALIAS_KOLOM.iter().find(|(_, alias)| alias.contains(&header.as_str())) {
+                    if !peta.values().any(|k| k == kolom) {
+                        peta.insert(col, *kolom);
+                    }
+                }
+            }
+            (idx, peta)
+        })
+        .filter(|(_, peta)| peta.values().any(|k| matches!(k, Kolom::Id | Kolom::Nisn | Kolom::Nik)))
+        .max_by_key(|(idx, peta)| (peta.len(), std::cmp::Reverse(*idx)))
+}
+
+fn buka_sheet(file_path: &str, nama_sheet: Option<&str>) -> Result<Range<DataType>, String> {
+    let mut workbook = open_workbook_auto(file_path)
+        .map_err(|e| format!("Gagal membuka file Excel: {}", e))?;
+    match nama_sheet.filter(|n| !n.trim().is_empty()) {
+        Some(nama) => workbook
+            .worksheet_range(nama)
+            .ok_or_else(|| format!("Sheet '{}' tidak ditemukan", nama))?
+            .map_err(|e| format!("Gagal membaca worksheet: {}", e)),
+        None => workbook
+            .worksheet_range_at(0)
+            .ok_or("Tidak dapat menemukan worksheet")?
+            .map_err(|e| format!("Gagal membaca worksheet: {}", e)),
+    }
+}
+
+#[tauri::command]
+pub async fn import_lulusan_from_excel(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, ReferensiCache>,
+    file_path: String,
+    pengguna_id: SqlxUuid,
+    opsi: Option<OpsiImportLulusan>,
+) -> Result<ImportLulusanResult, String> {
+    crate::emit_log(&app, "CMD: import_lulusan_from_excel - Starting import process.");
+
+    let opsi = opsi.unwrap_or_default();
+    let kecocokan = opsi.kecocokan.as_deref().unwrap_or("sedang");
+    if !["ketat", "sedang", "longgar"].contains(&kecocokan) {
+        return Err(format!("Tingkat kecocokan '{}' tidak dikenal", kecocokan));
+    }
+
+    let range = buka_sheet(&file_path, opsi.nama_sheet.as_deref())?;
+    let (baris_header, peta) = deteksi_header(&range)
+        .ok_or("Header tidak dikenali: file harus memiliki kolom ID, NISN, atau NIK")?;
+    let kolom = |k: Kolom| peta.iter().find(|(_, v)| **v == k).map(|(col, _)| *col);
+    // Tanpa kolom pembanding, kecocokan ketat atau sedang diam-diam menjadi longgar
+    if kecocokan != "longgar" && kolom(Kolom::Nama).is_none() {
+        return Err(format!("Kecocokan {} membutuhkan kolom Nama di file Excel", kecocokan));
+    }
+    if kecocokan == "ketat" && kolom(Kolom::NamaIbu).is_none() {
+        return Err("Kecocokan ketat membutuhkan kolom Nama Ibu Kandung di file Excel".to_string());
+    }
+    crate::emit_log(&app, &format!("CMD: import_lulusan_from_excel - Header di baris {}, {} kolom dikenali", baris_header + 1, peta.len()));
+
+    // Referensi jenis ijazah: nama atau kode
+    let referensi = ambil_referensi(&state.pool, &cache).await?;
+    let jenis_ijazah: HashMap<String, String> = referensi
+        .jenis_ijazah
+        .iter()
+        .flat_map(|j| {
+            let id = j.jenis_ijazah_id.to_string();
+            [(j.nama.trim().to_lowercase(), id.clone()), (id.clone(), id)]
+        })
+        .collect();
+
+    let lulusan = sqlx::query_as::<_, Lulusan>(
+        "SELECT pd.peserta_didik_id, pd.nama, pd.nisn, pd.nik, pd.nama_ibu_kandung
+         FROM peserta_didik pd
+         JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id
+         WHERE pd.soft_delete = 0 AND rpd.soft_delete = 0 AND rpd.jenis_keluar_id = '1'"
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil data lulusan: {}", e))?;
+
+    let per_id: HashMap<String, &Lulusan> = lulusan.iter().map(|l| (l.peserta_didik_id.to_string(), l)).collect();
+    let per_nisn: HashMap<&str, &Lulusan> = lulusan.iter().filter_map(|l| Some((l.nisn.as_deref()?.trim(), l))).collect();
+    let per_nik: HashMap<&str, &Lulusan> = lulusan.iter().filter_map(|l| Some((l.nik.as_deref()?.trim(), l))).collect();
+
+    let mut errors = Vec::new();
+    let mut perlu_konfirmasi = Vec::new();
+    let mut siap_disimpan: Vec<(usize, UpdateIjazahPayload)> = Vec::new();
+    let mut total_rows = 0;
+    let mut error_count = 0;
+
+    for (row_idx, row) in range.rows().enumerate().skip(baris_header + 1) {
+        let excel_row = row_idx + 1;
+        let jumlah_error_awal = errors.len();
+        let sel = |k: Kolom, errors: &mut Vec<ImportError>| {
+            kolom(k).and_then(|col| row.get(col)).and_then(|cell| get_optional_string_value(cell, "", excel_row, errors))
+        };
+
+        let id = sel(Kolom::Id, &mut errors);
+        let nisn = sel(Kolom::Nisn, &mut errors);
+        let nik = sel(Kolom::Nik, &mut errors);
+        let nama = sel(Kolom::Nama, &mut errors);
+        if id.is_none() && nisn.is_none() && nik.is_none() && nama.is_none() {
+            continue; // baris kosong
+        }
+        total_rows += 1;
+
+        let nama_ibu = sel(Kolom::NamaIbu, &mut errors);
+        let nomor = sel(Kolom::Nomor, &mut errors);
+        let penandatangan = sel(Kolom::Penandatangan, &mut errors);
+        let tanggal_tanda_tangan = kolom(Kolom::TanggalTtd)
+            .and_then(|col| row.get(col))
+            .and_then(|cell| get_optional_date_value(cell, "Tanggal Tanda Tangan", excel_row, &mut errors))
+            .and_then(|t| parse_tanggal_import(&t))
+            .map(|t| t.format("%Y-%m-%d").to_string());
+        let jenis_ijazah_id = match sel(Kolom::JenisIjazah, &mut errors) {
+            Some(jenis) => match jenis_ijazah.get(&jenis.trim().to_lowercase()) {
+                Some(id) => Some(id.clone()),
+                None => {
+                    errors.push(ImportError {
+                        row: excel_row as i32,
+                        field: "Jenis Ijazah".to_string(),
+                        message: format!("Jenis ijazah '{}' tidak ditemukan dalam database", jenis),
+                    });
+                    None
+                }
+            },
+            None => None,
+        };
+        if errors.len() > jumlah_error_awal {
+            error_count += 1;
+            continue;
+        }
+
+        // Cocokkan siswa: ID, lalu NISN, lalu NIK
+        let ditemukan = id.as_deref().and_then(|v| per_id.get(v.trim().to_lowercase().as_str()))
+            .or_else(|| nisn.as_deref().and_then(|v| per_nisn.get(v.trim())))
+            .or_else(|| nik.as_deref().and_then(|v| per_nik.get(v.trim())))
+            .copied();
+
+        // Sel kosong hanya dianggap sama bila tingkat kecocokan tidak mewajibkan kolom tersebut
+        let nama_sama = |l: &Lulusan| {
+            nama.as_deref().map_or(kecocokan == "longgar", |n| normalisasi_nama(n) == normalisasi_nama(&l.nama))
+        };
+        let ibu_sama = |l: &Lulusan| {
+            nama_ibu.as_deref().map_or(kecocokan != "ketat", |n| {
+                normalisasi_nama(n) == normalisasi_nama(l.nama_ibu_kandung.as_deref().unwrap_or_default())
+            })
+        };
+
+        let (siswa, alasan) = match ditemukan {
+            Some(l) => {
+                let alasan = match kecocokan {
+                    "ketat" if !nama_sama(l) || !ibu_sama(l) => Some("Nama atau nama ibu berbeda dengan database"),
+                    "sedang" if !nama_sama(l) => Some("Nama berbeda dengan database"),
+                    _ => None,
+                };
+                (l, alasan)
+            }
+            None => {
+                // Tanpa identitas yang cocok: tawarkan siswa dengan nama (dan nama ibu) yang sama
+                let kandidat: Vec<&Lulusan> = match &nama {
+                    Some(_) => lulusan.iter().filter(|l| nama_sama(l) && ibu_sama(l)).collect(),
+                    None => Vec::new(),
+                };
+                if kandidat.len() != 1 {
+                    errors.push(ImportError {
+                        row: excel_row as i32,
+                        field: "Data Siswa".to_string(),
+                        message: format!(
+                            "Lulusan tidak ditemukan: {} (NISN: {}, NIK: {})",
+                            nama.as_deref().unwrap_or("-"),
+                            nisn.as_deref().unwrap_or("-"),
+                            nik.as_deref().unwrap_or("-")
+                        ),
+                    });
+                    error_count += 1;
+                    continue;
+                }
+                (kandidat[0], Some("ID, NISN, atau NIK tidak cocok; siswa ditemukan dari nama"))
+            }
+        };
+
+        let payload = UpdateIjazahPayload {
+            peserta_didik_id: siswa.peserta_didik_id.to_string(),
+            jenis_ijazah_id,
+            nomor,
+            penandatangan,
+            tanggal_tanda_tangan,
+        };
+        match alasan {
+            Some(alasan) => perlu_konfirmasi.push(KonfirmasiLulusan {
+                row: excel_row as i32,
+                nama_excel: nama.clone().unwrap_or_default(),
+                nama_db: siswa.nama.clone(),
+                nisn_db: siswa.nisn.clone(),
+                alasan: alasan.to_string(),
+                peserta_didik_id: payload.peserta_didik_id,
+                jenis_ijazah_id: payload.jenis_ijazah_id,
+                nomor: payload.nomor,
+                penandatangan: payload.penandatangan,
+                tanggal_tanda_tangan: payload.tanggal_tanda_tangan,
+            }),
+            None => siap_disimpan.push((excel_row, payload)),
+        }
+    }
+
+    // Simpan baris yang cocok; tiap baris punya savepoint sendiri
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    let mut success_count = 0;
+    for (excel_row, payload) in &siap_disimpan {
+        match simpan_satu_ijazah(&mut tx, payload, pengguna_id).await {
+            Ok(_) => success_count += 1,
+            Err((_, pesan)) => {
+                errors.push(ImportError { row: *excel_row as i32, field: "Data Ijazah".to_string(), message: pesan });
+                error_count += 1;
+            }
+        }
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    let mut message = if error_count == 0 {
+        format!("Berhasil mengimport {} data lulusan", success_count)
+    } else {
+        format!("Berhasil mengimport {} data, {} error ditemukan", success_count, error_count)
+    };
+    if !perlu_konfirmasi.is_empty() {
+        message.push_str(&format!(", {} baris perlu konfirmasi", perlu_konfirmasi.len()));
+    }
+
+    crate::emit_log(&app, &format!(
+        "CMD: import_lulusan_from_excel - Import completed. Success: {}, Errors: {}, Konfirmasi: {}",
+        success_count, error_count, perlu_konfirmasi.len()
+    ));
+
+    Ok(ImportLulusanResult {
+        success: error_count == 0 && perlu_konfirmasi.is_empty(),
+        message,
+        total_rows,
+        success_count,
+        error_count,
+        errors,
+        perlu_konfirmasi,
+    })
+}
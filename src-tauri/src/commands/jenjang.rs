@@ -0,0 +1,112 @@
+//! Aturan naik kelas dan kelulusan berdasarkan bentuk pendidikan sekolah. Tingkat terakhir sebuah
+//! jenjang berarti lulus, tingkat lainnya berarti naik ke tingkat berikutnya.
+
+use crate::app_state::DbPool;
+use crate::commands::pengaturan::{muat_pengaturan, AturanJenjang};
+use tauri::{AppHandle, State};
+use serde::Serialize;
+use sqlx::types::Uuid as SqlxUuid;
+use sqlx::{Pool, Postgres};
+
+// Rentang tingkat bawaan per bentuk pendidikan (nama di ref.bentuk_pendidikan)
+const RENTANG_BAWAAN: [(&[&str], i32, i32); 3] = [
+    (&["SD", "SDLB", "MI"], 1, 6),
+    (&["SMP", "SMPLB", "MTS"], 7, 9),
+    (&["SMA", "SMALB", "SMK", "MA", "MAK"], 10, 12),
+];
+
+#[derive(Serialize, Clone)]
+pub struct AturanSekolah {
+    pub bentuk_pendidikan_id: i32,
+    pub bentuk_pendidikan: String,
+    pub tingkat_awal: i32,
+    pub tingkat_akhir: i32,
+    /// bawaan, pengaturan, atau data_rombel
+    pub sumber: String,
+    /// Pengecualian per jurusan atau kurikulum, misalnya jurusan SMK 4 tahun di sekolah yang jurusan lainnya 3 tahun
+    pub pengecualian: Vec<AturanJenjang>,
+}
+
+impl AturanSekolah {
+    /// Tingkat akhir untuk rombel dengan jurusan dan kurikulum tersebut. Pengecualian yang paling spesifik
+    /// (cocok jurusan dan kurikulum) menang atas pengecualian yang hanya cocok salah satunya.
+    pub fn tingkat_akhir_rombel(&self, jurusan_sp_id: Option<SqlxUuid>, kurikulum_id: Option<i32>) -> i32 {
+        self.pengecualian
+            .iter()
+            .filter(|a| a.jurusan_sp_id.is_none() || a.jurusan_sp_id == jurusan_sp_id)
+            .filter(|a| a.kurikulum_id.is_none() || a.kurikulum_id == kurikulum_id)
+            .max_by_key(|a| a.jurusan_sp_id.is_some() as u8 + a.kurikulum_id.is_some() as u8)
+            .map_or(self.tingkat_akhir, |a| a.tingkat_akhir)
+    }
+
+    /// Tingkat akhir terendah di antara aturan sekolah dan pengecualiannya, batas bawah rombel kandidat lulus
+    pub fn tingkat_akhir_terendah(&self) -> i32 {
+        self.pengecualian.iter().map(|a| a.tingkat_akhir).fold(self.tingkat_akhir, i32::min)
+    }
+
+    pub fn adalah_tingkat_akhir(&self, tingkat: i32, jurusan_sp_id: Option<SqlxUuid>, kurikulum_id: Option<i32>) -> bool {
+        tingkat >= self.tingkat_akhir_rombel(jurusan_sp_id, kurikulum_id)
+    }
+
+    /// Tingkat tujuan naik kelas, None bila tingkat tersebut tingkat akhir (lulus)
+    pub fn tingkat_berikutnya(&self, tingkat: i32, jurusan_sp_id: Option<SqlxUuid>, kurikulum_id: Option<i32>) -> Option<i32> {
+        if self.adalah_tingkat_akhir(tingkat, jurusan_sp_id, kurikulum_id) { None } else { Some(tingkat + 1) }
+    }
+}
+
+/// Menentukan aturan tingkat untuk sekolah di database ini. Urutan prioritas: pengecualian di pengaturan,
+/// rentang bawaan bentuk pendidikan, lalu rentang tingkat rombel yang ada (misalnya untuk PAUD dan SLB).
+/// Pengecualian pengaturan yang menyebut jurusan atau kurikulum berlaku per rombel di atas aturan tersebut.
+pub(crate) async fn aturan_sekolah(pool: &Pool<Postgres>) -> Result<AturanSekolah, String> {
+    let (bentuk_pendidikan_id, bentuk_pendidikan) = sqlx::query_as::<_, (i32, String)>(
+        "SELECT s.bentuk_pendidikan_id::int, TRIM(bp.nama)
+         FROM sekolah s
+         JOIN ref.bentuk_pendidikan bp ON bp.bentuk_pendidikan_id = s.bentuk_pendidikan_id
+         WHERE s.soft_delete = 0
+         LIMIT 1"
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil bentuk pendidikan sekolah: {}", e))?
+    .ok_or("Data sekolah tidak ditemukan")?;
+
+    let nama = bentuk_pendidikan.to_uppercase();
+    let (pengecualian, umum): (Vec<AturanJenjang>, Vec<AturanJenjang>) = muat_pengaturan()
+        .aturan_jenjang
+        .into_iter()
+        .filter(|a| a.bentuk_pendidikan.trim().to_uppercase() == nama)
+        .partition(|a| a.jurusan_sp_id.is_some() || a.kurikulum_id.is_some());
+    let aturan = |tingkat_awal, tingkat_akhir, sumber: &str| AturanSekolah {
+        bentuk_pendidikan_id,
+        bentuk_pendidikan: bentuk_pendidikan.clone(),
+        tingkat_awal,
+        tingkat_akhir,
+        sumber: sumber.to_string(),
+        pengecualian: pengecualian.clone(),
+    };
+
+    if let Some(a) = umum.first() {
+        return Ok(aturan(a.tingkat_awal, a.tingkat_akhir, "pengaturan"));
+    }
+    if let Some((_, awal, akhir)) = RENTANG_BAWAAN.iter().find(|(bentuk, _, _)| bentuk.contains(&nama.as_str())) {
+        return Ok(aturan(*awal, *akhir, "bawaan"));
+    }
+
+    let rentang: (Option<i32>, Option<i32>) = sqlx::query_as(
+        "SELECT MIN(tingkat_pendidikan_id)::int, MAX(tingkat_pendidikan_id)::int FROM rombongan_belajar WHERE soft_delete = 0"
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil rentang tingkat rombel: {}", e))?;
+
+    match rentang {
+        (Some(awal), Some(akhir)) => Ok(aturan(awal, akhir, "data_rombel")),
+        _ => Err(format!("Aturan tingkat untuk bentuk pendidikan {} belum diatur di pengaturan", bentuk_pendidikan)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_aturan_jenjang(app: AppHandle, state: State<'_, DbPool>) -> Result<AturanSekolah, String> {
+    crate::emit_log(&app, "CMD: get_aturan_jenjang - Membaca aturan tingkat sekolah");
+    aturan_sekolah(&state.pool).await
+}
@@ -1,10 +1,41 @@
-use crate::app_state::DbPool;
+use crate::app_state::{DbPool, ReferensiCache};
+use crate::commands::referensi::{ambil_referensi, ReferensiBundle};
 use tauri::{AppHandle, State};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid as SqlxUuid;
 use chrono::NaiveDate;
+use std::collections::HashMap;
 
-#[derive(Serialize)]
+/// Jenis keluar yang dihitung sebagai putus sekolah: dikeluarkan, mengundurkan diri dan putus sekolah.
+/// Mutasi, wafat dan lulus tidak dihitung.
+const JENIS_KELUAR_PUTUS_SEKOLAH: [&str; 3] = ["3", "4", "5"];
+
+/// Klausa FROM/WHERE daftar siswa keluar. Rombel terakhir diambil dari anggota_rombel semester terbaru,
+/// termasuk yang sudah ditutup saat siswa diproses keluar.
+const KLAUSA_SISWA_KELUAR: &str =
+    "FROM peserta_didik pd
+     JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id AND rpd.soft_delete = 0
+     LEFT JOIN LATERAL (
+         SELECT rb.rombongan_belajar_id, rb.nama, rb.tingkat_pendidikan_id::int AS tingkat_pendidikan_id
+         FROM anggota_rombel ar
+         JOIN rombongan_belajar rb ON rb.rombongan_belajar_id = ar.rombongan_belajar_id AND rb.soft_delete = 0
+         WHERE ar.peserta_didik_id = pd.peserta_didik_id
+         ORDER BY rb.semester_id DESC, ar.last_update DESC
+         LIMIT 1
+     ) rt ON TRUE
+     WHERE pd.soft_delete = 0 AND rpd.jenis_keluar_id IS NOT NULL AND TRIM(rpd.jenis_keluar_id) <> '1'
+     AND ($1::text IS NULL OR pd.nama ILIKE $1 OR pd.nisn ILIKE $1)
+     AND ($2::text IS NULL OR TRIM(rpd.jenis_keluar_id) = $2)
+     AND ($3::date IS NULL OR rpd.tanggal_keluar >= $3)
+     AND ($4::date IS NULL OR rpd.tanggal_keluar <= $4)
+     AND ($5::uuid IS NULL OR rt.rombongan_belajar_id = $5)
+     AND ($6::int IS NULL OR rt.tingkat_pendidikan_id = $6)";
+
+fn ket_keluar(referensi: &ReferensiBundle, jenis_keluar_id: &str) -> String {
+    referensi.nama_jenis_keluar(jenis_keluar_id).unwrap_or("Tidak diketahui").to_string()
+}
+
+#[derive(Serialize, sqlx::FromRow)]
 pub struct SiswaKeluar {
     pub peserta_didik_id: SqlxUuid,
     pub nama: String,
@@ -14,8 +45,62 @@ pub struct SiswaKeluar {
     pub nama_ayah: Option<String>,
     pub nama_ibu_kandung: String,
     pub jenis_keluar_id: String,
+    /// Diisi dari cache referensi setelah query
+    #[sqlx(default)]
     pub ket_keluar: String,
     pub tanggal_keluar: Option<NaiveDate>,
+    pub rombongan_belajar_id: Option<SqlxUuid>,
+    pub nama_rombel: Option<String>,
+    pub tingkat_pendidikan_id: Option<i32>,
+}
+
+/// Filter tambahan daftar siswa keluar; semua field opsional
+#[derive(Deserialize, Default)]
+pub struct FilterSiswaKeluar {
+    pub jenis_keluar_id: Option<String>,
+    pub tanggal_dari: Option<String>,
+    pub tanggal_sampai: Option<String>,
+    /// Rombel terakhir siswa sebelum keluar
+    pub rombongan_belajar_id: Option<SqlxUuid>,
+    pub tingkat_pendidikan_id: Option<i32>,
+}
+
+/// Parameter terurai untuk `KLAUSA_SISWA_KELUAR` ($1 sampai $6)
+struct ParameterFilterKeluar {
+    search: Option<String>,
+    jenis_keluar_id: Option<String>,
+    tanggal_dari: Option<NaiveDate>,
+    tanggal_sampai: Option<NaiveDate>,
+    rombongan_belajar_id: Option<SqlxUuid>,
+    tingkat_pendidikan_id: Option<i32>,
+}
+
+fn urai_filter(search: Option<String>, filter: Option<FilterSiswaKeluar>) -> Result<ParameterFilterKeluar, String> {
+    let filter = filter.unwrap_or_default();
+    let tanggal = |nilai: Option<String>, label: &str| -> Result<Option<NaiveDate>, String> {
+        match nilai.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            Some(t) => NaiveDate::parse_from_str(t, "%Y-%m-%d")
+                .map(Some)
+                .map_err(|e| format!("Format {} salah: {}", label, e)),
+            None => Ok(None),
+        }
+    };
+    let tanggal_dari = tanggal(filter.tanggal_dari, "tanggal awal")?;
+    let tanggal_sampai = tanggal(filter.tanggal_sampai, "tanggal akhir")?;
+    if let (Some(dari), Some(sampai)) = (tanggal_dari, tanggal_sampai) {
+        if dari > sampai {
+            return Err("Tanggal awal tidak boleh setelah tanggal akhir.".to_string());
+        }
+    }
+
+    Ok(ParameterFilterKeluar {
+        search: search.filter(|s| !s.is_empty()).map(|s| format!("%{}%", s)),
+        jenis_keluar_id: filter.jenis_keluar_id.map(|j| j.trim().to_string()).filter(|j| !j.is_empty()),
+        tanggal_dari,
+        tanggal_sampai,
+        rombongan_belajar_id: filter.rombongan_belajar_id,
+        tingkat_pendidikan_id: filter.tingkat_pendidikan_id,
+    })
 }
 
 #[tauri::command]
@@ -23,45 +108,22 @@ pub async fn get_total_siswa_keluar(
     app: AppHandle,
     state: State<'_, DbPool>,
     search: Option<String>,
+    filter: Option<FilterSiswaKeluar>,
 ) -> Result<i64, String> {
     crate::emit_log(&app, "CMD: get_total_siswa_keluar - Fetching total count.");
 
-    let query = if let Some(ref search_term) = search {
-        if search_term.is_empty() {
-            "SELECT COUNT(*) FROM peserta_didik pd 
-             JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id 
-             WHERE pd.soft_delete = 0 AND rpd.jenis_keluar_id IS NOT NULL AND rpd.jenis_keluar_id != 1"
-        } else {
-            "SELECT COUNT(*) FROM peserta_didik pd 
-             JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id 
-             WHERE pd.soft_delete = 0 AND rpd.jenis_keluar_id IS NOT NULL AND rpd.jenis_keluar_id != 1 
-             AND (pd.nama ILIKE $1 OR pd.nisn ILIKE $1)"
-        }
-    } else {
-        "SELECT COUNT(*) FROM peserta_didik pd 
-         JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id 
-         WHERE pd.soft_delete = 0 AND rpd.jenis_keluar_id IS NOT NULL AND rpd.jenis_keluar_id != 1"
-    };
-
-    let result = if let Some(ref search_term) = search {
-        if !search_term.is_empty() {
-            let search_pattern = format!("%{}%", search_term);
-            sqlx::query_scalar(query)
-                .bind(search_pattern)
-                .fetch_one(&state.pool)
-                .await
-        } else {
-            sqlx::query_scalar(query)
-                .fetch_one(&state.pool)
-                .await
-        }
-    } else {
-        sqlx::query_scalar(query)
-            .fetch_one(&state.pool)
-            .await
-    };
+    let p = urai_filter(search, filter)?;
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {}", KLAUSA_SISWA_KELUAR))
+        .bind(p.search)
+        .bind(p.jenis_keluar_id)
+        .bind(p.tanggal_dari)
+        .bind(p.tanggal_sampai)
+        .bind(p.rombongan_belajar_id)
+        .bind(p.tingkat_pendidikan_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(|e| e.to_string())?;
 
-    let total: i64 = result.map_err(|e| e.to_string())?;
     crate::emit_log(&app, &format!("CMD: get_total_siswa_keluar - Total: {}", total));
     Ok(total)
 }
@@ -73,87 +135,409 @@ pub async fn get_daftar_siswa_keluar(
     page: i64,
     page_size: i64,
     search: Option<String>,
+    cache: State<'_, ReferensiCache>,
+    filter: Option<FilterSiswaKeluar>,
 ) -> Result<Vec<SiswaKeluar>, String> {
     crate::emit_log(&app, "CMD: get_daftar_siswa_keluar - Fetching data.");
+    let referensi = ambil_referensi(&state.pool, &cache).await?;
 
     let offset = (page - 1) * page_size;
-    let query = if let Some(ref search_term) = search {
-        if search_term.is_empty() {
-            "SELECT pd.peserta_didik_id, pd.nama, pd.nisn, pd.nik, pd.tanggal_lahir,
-                    pd.nama_ayah, pd.nama_ibu_kandung, rpd.jenis_keluar_id,
-                    jk.nama as ket_keluar, rpd.tanggal_keluar
-             FROM peserta_didik pd 
-             JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id 
-             LEFT JOIN ref.jenis_keluar jk ON rpd.jenis_keluar_id = jk.jenis_keluar_id
-             WHERE pd.soft_delete = 0 AND rpd.jenis_keluar_id IS NOT NULL AND rpd.jenis_keluar_id != 1
-             ORDER BY pd.nama
-             LIMIT $1 OFFSET $2"
-        } else {
-            "SELECT pd.peserta_didik_id, pd.nama, pd.nisn, pd.nik, pd.tanggal_lahir,
-                    pd.nama_ayah, pd.nama_ibu_kandung, rpd.jenis_keluar_id,
-                    jk.nama as ket_keluar, rpd.tanggal_keluar
-             FROM peserta_didik pd 
-             JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id 
-             LEFT JOIN ref.jenis_keluar jk ON rpd.jenis_keluar_id = jk.jenis_keluar_id
-             WHERE pd.soft_delete = 0 AND rpd.jenis_keluar_id IS NOT NULL AND rpd.jenis_keluar_id != 1 
-             AND (pd.nama ILIKE $1 OR pd.nisn ILIKE $1)
-             ORDER BY pd.nama
-             LIMIT $2 OFFSET $3"
-        }
-    } else {
+    let p = urai_filter(search, filter)?;
+    let query = format!(
         "SELECT pd.peserta_didik_id, pd.nama, pd.nisn, pd.nik, pd.tanggal_lahir,
-                pd.nama_ayah, pd.nama_ibu_kandung, rpd.jenis_keluar_id,
-                jk.nama as ket_keluar, rpd.tanggal_keluar
-         FROM peserta_didik pd 
-         JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id 
-         LEFT JOIN ref.jenis_keluar jk ON rpd.jenis_keluar_id = jk.jenis_keluar_id
-         WHERE pd.soft_delete = 0 AND rpd.jenis_keluar_id IS NOT NULL AND rpd.jenis_keluar_id != 1
+                pd.nama_ayah, pd.nama_ibu_kandung, TRIM(rpd.jenis_keluar_id) AS jenis_keluar_id, rpd.tanggal_keluar,
+                rt.rombongan_belajar_id, rt.nama AS nama_rombel, rt.tingkat_pendidikan_id
+         {}
          ORDER BY pd.nama
-         LIMIT $1 OFFSET $2"
+         LIMIT $7 OFFSET $8",
+        KLAUSA_SISWA_KELUAR
+    );
+
+    let mut siswa_keluar = sqlx::query_as::<_, SiswaKeluar>(&query)
+        .bind(p.search)
+        .bind(p.jenis_keluar_id)
+        .bind(p.tanggal_dari)
+        .bind(p.tanggal_sampai)
+        .bind(p.rombongan_belajar_id)
+        .bind(p.tingkat_pendidikan_id)
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    for s in &mut siswa_keluar {
+        s.ket_keluar = ket_keluar(&referensi, &s.jenis_keluar_id);
+    }
+
+    crate::emit_log(&app, &format!("CMD: get_daftar_siswa_keluar - Fetched {} records", siswa_keluar.len()));
+    Ok(siswa_keluar)
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct KeluarPerBulan {
+    /// Format YYYY-MM
+    pub bulan: String,
+    pub jenis_keluar_id: String,
+    #[sqlx(default)]
+    pub ket_keluar: String,
+    pub jumlah: i64,
+}
+
+#[derive(Serialize)]
+pub struct JumlahPerJenisKeluar {
+    pub jenis_keluar_id: String,
+    pub ket_keluar: String,
+    pub jumlah: i64,
+}
+
+#[derive(Serialize)]
+pub struct KeluarPerTahunAjaran {
+    /// Tahun awal tahun ajaran, misalnya 2024 untuk 2024/2025
+    pub tahun_ajaran_id: i32,
+    pub nama_tahun_ajaran: String,
+    /// Jumlah siswa berbeda yang terdaftar di rombel tahun ajaran tersebut
+    pub jumlah_siswa: i64,
+    pub jumlah_keluar: i64,
+    pub jumlah_putus_sekolah: i64,
+    pub per_jenis: Vec<JumlahPerJenisKeluar>,
+    /// Persentase putus sekolah terhadap jumlah siswa, None jika jumlah siswa tidak diketahui
+    pub angka_putus_sekolah: Option<f64>,
+    /// Persentase seluruh siswa keluar (selain lulus) terhadap jumlah siswa
+    pub angka_keluar: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct StatistikSiswaKeluar {
+    pub per_bulan: Vec<KeluarPerBulan>,
+    pub per_tahun_ajaran: Vec<KeluarPerTahunAjaran>,
+    pub jenis_putus_sekolah: Vec<String>,
+}
+
+/// Statistik siswa keluar per bulan dan per tahun ajaran beserta angka putus sekolah untuk pelaporan pengawas.
+/// Tahun ajaran dihitung dari tanggal keluar dengan awal tahun ajaran 1 Juli.
+#[tauri::command]
+pub async fn statistik_siswa_keluar(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, ReferensiCache>,
+    tahun_ajaran_id: Option<i32>,
+) -> Result<StatistikSiswaKeluar, String> {
+    crate::emit_log(&app, &format!("CMD: statistik_siswa_keluar - tahun ajaran: {:?}", tahun_ajaran_id));
+    let referensi = ambil_referensi(&state.pool, &cache).await?;
+
+    const TAHUN_AJARAN_KELUAR: &str =
+        "(EXTRACT(YEAR FROM rpd.tanggal_keluar)::int - CASE WHEN EXTRACT(MONTH FROM rpd.tanggal_keluar) < 7 THEN 1 ELSE 0 END)";
+
+    let mut per_bulan = sqlx::query_as::<_, KeluarPerBulan>(&format!(
+        "SELECT TO_CHAR(rpd.tanggal_keluar, 'YYYY-MM') AS bulan, TRIM(rpd.jenis_keluar_id) AS jenis_keluar_id,
+                COUNT(*) AS jumlah
+         FROM registrasi_peserta_didik rpd
+         JOIN peserta_didik pd ON pd.peserta_didik_id = rpd.peserta_didik_id AND pd.soft_delete = 0
+         WHERE rpd.soft_delete = 0 AND rpd.tanggal_keluar IS NOT NULL
+         AND rpd.jenis_keluar_id IS NOT NULL AND TRIM(rpd.jenis_keluar_id) <> '1'
+         AND ($1::int IS NULL OR {} = $1)
+         GROUP BY 1, 2
+         ORDER BY 1, 2",
+        TAHUN_AJARAN_KELUAR
+    ))
+    .bind(tahun_ajaran_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal menghitung siswa keluar per bulan: {}", e))?;
+    for b in &mut per_bulan {
+        b.ket_keluar = ket_keluar(&referensi, &b.jenis_keluar_id);
+    }
+
+    let keluar_per_tahun = sqlx::query_as::<_, (i32, String, i64)>(&format!(
+        "SELECT {} AS tahun_ajaran_id, TRIM(rpd.jenis_keluar_id), COUNT(*)
+         FROM registrasi_peserta_didik rpd
+         JOIN peserta_didik pd ON pd.peserta_didik_id = rpd.peserta_didik_id AND pd.soft_delete = 0
+         WHERE rpd.soft_delete = 0 AND rpd.tanggal_keluar IS NOT NULL
+         AND rpd.jenis_keluar_id IS NOT NULL AND TRIM(rpd.jenis_keluar_id) <> '1'
+         AND ($1::int IS NULL OR {} = $1)
+         GROUP BY 1, 2
+         ORDER BY 1 DESC, 2",
+        TAHUN_AJARAN_KELUAR, TAHUN_AJARAN_KELUAR
+    ))
+    .bind(tahun_ajaran_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal menghitung siswa keluar per tahun ajaran: {}", e))?;
+
+    // Anggota rombel yang ditutup saat siswa keluar ikut dihitung agar siswa keluar tetap masuk penyebut
+    let jumlah_siswa: HashMap<i32, i64> = sqlx::query_as::<_, (i32, i64)>(
+        "SELECT s.tahun_ajaran_id::int, COUNT(DISTINCT ar.peserta_didik_id)
+         FROM anggota_rombel ar
+         JOIN rombongan_belajar rb ON rb.rombongan_belajar_id = ar.rombongan_belajar_id AND rb.soft_delete = 0
+         JOIN ref.semester s ON s.semester_id = rb.semester_id
+         WHERE ($1::int IS NULL OR s.tahun_ajaran_id::int = $1)
+         GROUP BY 1"
+    )
+    .bind(tahun_ajaran_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal menghitung jumlah siswa per tahun ajaran: {}", e))?
+    .into_iter()
+    .collect();
+
+    let persen = |jumlah: i64, total: i64| (total > 0).then(|| (jumlah as f64 * 10000.0 / total as f64).round() / 100.0);
+
+    let mut per_tahun_ajaran: Vec<KeluarPerTahunAjaran> = Vec::new();
+    for (tahun, jenis_keluar_id, jumlah) in keluar_per_tahun {
+        if per_tahun_ajaran.last().is_none_or(|t| t.tahun_ajaran_id != tahun) {
+            per_tahun_ajaran.push(KeluarPerTahunAjaran {
+                tahun_ajaran_id: tahun,
+                nama_tahun_ajaran: format!("{}/{}", tahun, tahun + 1),
+                jumlah_siswa: jumlah_siswa.get(&tahun).copied().unwrap_or(0),
+                jumlah_keluar: 0,
+                jumlah_putus_sekolah: 0,
+                per_jenis: Vec::new(),
+                angka_putus_sekolah: None,
+                angka_keluar: None,
+            });
+        }
+        let Some(t) = per_tahun_ajaran.last_mut() else { continue };
+        t.jumlah_keluar += jumlah;
+        if JENIS_KELUAR_PUTUS_SEKOLAH.contains(&jenis_keluar_id.as_str()) {
+            t.jumlah_putus_sekolah += jumlah;
+        }
+        t.per_jenis.push(JumlahPerJenisKeluar { ket_keluar: ket_keluar(&referensi, &jenis_keluar_id), jenis_keluar_id, jumlah });
+    }
+    for t in &mut per_tahun_ajaran {
+        t.angka_putus_sekolah = persen(t.jumlah_putus_sekolah, t.jumlah_siswa);
+        t.angka_keluar = persen(t.jumlah_keluar, t.jumlah_siswa);
+    }
+
+    crate::emit_log(&app, &format!("CMD: statistik_siswa_keluar - {} bulan, {} tahun ajaran", per_bulan.len(), per_tahun_ajaran.len()));
+
+    Ok(StatistikSiswaKeluar {
+        per_bulan,
+        per_tahun_ajaran,
+        jenis_putus_sekolah: JENIS_KELUAR_PUTUS_SEKOLAH.iter().map(|j| j.to_string()).collect(),
+    })
+}
+
+#[derive(Deserialize)]
+pub struct ProsesKeluarPayload {
+    pub peserta_didik_id: SqlxUuid,
+    pub jenis_keluar_id: String,
+    pub tanggal_keluar: String,
+    pub keterangan: Option<String>,
+    pub pengguna_id: SqlxUuid,
+}
+
+/// Mencatat siswa aktif sebagai keluar (mutasi, mengundurkan diri, dll) dan menutup keanggotaan rombel aktifnya
+#[tauri::command]
+pub async fn proses_siswa_keluar(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    payload: ProsesKeluarPayload,
+) -> Result<String, String> {
+    crate::emit_log(&app, &format!("CMD: proses_siswa_keluar - Memproses siswa keluar: {}", payload.peserta_didik_id));
+    state.pastikan_skema_dapomaster()?;
+
+    let tanggal_keluar = NaiveDate::parse_from_str(&payload.tanggal_keluar, "%Y-%m-%d")
+        .map_err(|e| format!("Format tanggal keluar salah: {}", e))?;
+
+    if payload.jenis_keluar_id.trim() == "1" {
+        return Err("Jenis keluar 'Lulus' tidak diproses melalui mutasi keluar.".to_string());
+    }
+
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+
+    let jenis_keluar_ada: Option<(String,)> = sqlx::query_as("SELECT jenis_keluar_id FROM ref.jenis_keluar WHERE jenis_keluar_id = $1")
+        .bind(payload.jenis_keluar_id.trim())
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| format!("Gagal memeriksa jenis keluar: {}", e))?;
+    if jenis_keluar_ada.is_none() {
+        return Err(format!("Jenis keluar '{}' tidak ditemukan.", payload.jenis_keluar_id));
+    }
+
+    let registrasi = sqlx::query_as::<_, (SqlxUuid, String, Option<NaiveDate>, Option<String>, Option<String>)>(
+        "SELECT rpd.registrasi_id, pd.nama, rpd.tanggal_masuk_sekolah, rpd.jenis_keluar_id, rpd.keterangan
+         FROM registrasi_peserta_didik rpd
+         JOIN peserta_didik pd ON pd.peserta_didik_id = rpd.peserta_didik_id
+         WHERE rpd.peserta_didik_id = $1 AND rpd.soft_delete = 0 AND pd.soft_delete = 0
+         FOR UPDATE OF rpd"
+    )
+    .bind(payload.peserta_didik_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal mengambil data registrasi: {}", e))?;
+
+    let (registrasi_id, nama, tanggal_masuk_sekolah, jenis_keluar_lama, keterangan_lama) = match registrasi {
+        Some(r) => r,
+        None => return Err("Data registrasi siswa tidak ditemukan.".to_string()),
     };
 
-    let result = if let Some(ref search_term) = search {
-        if !search_term.is_empty() {
-            let search_pattern = format!("%{}%", search_term);
-            sqlx::query_as::<_, (SqlxUuid, String, String, Option<String>, NaiveDate, Option<String>, String, String, Option<String>, Option<NaiveDate>)>(query)
-                .bind(search_pattern)
-                .bind(page_size)
-                .bind(offset)
-                .fetch_all(&state.pool)
-                .await
-        } else {
-            sqlx::query_as::<_, (SqlxUuid, String, String, Option<String>, NaiveDate, Option<String>, String, String, Option<String>, Option<NaiveDate>)>(query)
-                .bind(page_size)
-                .bind(offset)
-                .fetch_all(&state.pool)
-                .await
+    if jenis_keluar_lama.is_some() {
+        return Err(format!("Siswa {} sudah tercatat keluar.", nama));
+    }
+
+    if let Some(tanggal_masuk) = tanggal_masuk_sekolah {
+        if tanggal_keluar < tanggal_masuk {
+            return Err(format!(
+                "Tanggal keluar ({}) tidak boleh sebelum tanggal masuk sekolah ({}).",
+                tanggal_keluar.format("%Y-%m-%d"),
+                tanggal_masuk.format("%Y-%m-%d")
+            ));
         }
-    } else {
-        sqlx::query_as::<_, (SqlxUuid, String, String, Option<String>, NaiveDate, Option<String>, String, String, Option<String>, Option<NaiveDate>)>(query)
-            .bind(page_size)
-            .bind(offset)
-            .fetch_all(&state.pool)
-            .await
+    }
+
+    // Keterangan hanya ditimpa bila diisi, agar catatan yang sudah ada tidak hilang
+    let keterangan = payload.keterangan.as_deref().map(str::trim).filter(|k| !k.is_empty());
+    sqlx::query(
+        "UPDATE registrasi_peserta_didik
+         SET jenis_keluar_id = $1, tanggal_keluar = $2, keterangan = COALESCE($3, keterangan), last_update = NOW(), updater_id = $4
+         WHERE registrasi_id = $5"
+    )
+    .bind(payload.jenis_keluar_id.trim())
+    .bind(tanggal_keluar)
+    .bind(keterangan)
+    .bind(payload.pengguna_id)
+    .bind(registrasi_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal mengupdate registrasi_peserta_didik: {}", e))?;
+
+    let rombel_ditutup: Vec<SqlxUuid> = sqlx::query_scalar(
+        "UPDATE anggota_rombel SET soft_delete = 1, last_update = NOW(), updater_id = $1
+         WHERE peserta_didik_id = $2 AND soft_delete = 0
+         AND rombongan_belajar_id IN (
+             SELECT rb.rombongan_belajar_id FROM rombongan_belajar rb
+             JOIN ref.semester s ON rb.semester_id = s.semester_id
+             WHERE s.periode_aktif = 1
+         )
+         RETURNING anggota_rombel_id"
+    )
+    .bind(payload.pengguna_id)
+    .bind(payload.peserta_didik_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal menutup anggota_rombel: {}", e))?;
+
+    // Id anggota rombel yang ditutup disimpan agar pembatalan membuka kembali tepat baris-baris ini
+    sqlx::query(
+        "INSERT INTO dapomaster.siswa_keluar (registrasi_id, waktu_keluar, keterangan_diubah, keterangan_lama, anggota_rombel_ids)
+         VALUES ($1, NOW(), $2, $3, $4)
+         ON CONFLICT (registrasi_id) DO UPDATE
+         SET waktu_keluar = EXCLUDED.waktu_keluar, keterangan_diubah = EXCLUDED.keterangan_diubah,
+             keterangan_lama = EXCLUDED.keterangan_lama, anggota_rombel_ids = EXCLUDED.anggota_rombel_ids"
+    )
+    .bind(registrasi_id)
+    .bind(keterangan.is_some())
+    .bind(&keterangan_lama)
+    .bind(&rombel_ditutup)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal mencatat siswa keluar: {}", e))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    crate::emit_log(&app, &format!("CMD: proses_siswa_keluar - {} tercatat keluar, {} anggota rombel ditutup", nama, rombel_ditutup.len()));
+    Ok(format!("Siswa {} berhasil dicatat keluar per {}.", nama, tanggal_keluar.format("%d-%m-%Y")))
+}
+
+/// Membatalkan pencatatan siswa keluar yang keliru dan membuka kembali keanggotaan rombel aktifnya
+#[tauri::command]
+pub async fn batalkan_siswa_keluar(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    peserta_didik_id: SqlxUuid,
+    pengguna_id: SqlxUuid,
+) -> Result<String, String> {
+    crate::emit_log(&app, &format!("CMD: batalkan_siswa_keluar - Membatalkan siswa keluar: {}", peserta_didik_id));
+    state.pastikan_skema_dapomaster()?;
+
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+
+    let registrasi = sqlx::query_as::<_, (SqlxUuid, String, Option<String>)>(
+        "SELECT rpd.registrasi_id, pd.nama, rpd.jenis_keluar_id
+         FROM registrasi_peserta_didik rpd
+         JOIN peserta_didik pd ON pd.peserta_didik_id = rpd.peserta_didik_id
+         WHERE rpd.peserta_didik_id = $1 AND rpd.soft_delete = 0 AND pd.soft_delete = 0
+         FOR UPDATE OF rpd"
+    )
+    .bind(peserta_didik_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal mengambil data registrasi: {}", e))?;
+
+    let (registrasi_id, nama, jenis_keluar_id) = match registrasi {
+        Some(r) => r,
+        None => return Err("Data registrasi siswa tidak ditemukan.".to_string()),
     };
 
-    let rows = result.map_err(|e| e.to_string())?;
-    
-    let siswa_keluar: Vec<SiswaKeluar> = rows
-        .into_iter()
-        .map(|row| SiswaKeluar {
-            peserta_didik_id: row.0,
-            nama: row.1,
-            nisn: row.2,
-            nik: row.3,
-            tanggal_lahir: row.4,
-            nama_ayah: row.5,
-            nama_ibu_kandung: row.6,
-            jenis_keluar_id: row.7,
-            ket_keluar: row.8.unwrap_or_else(|| "Tidak diketahui".to_string()),
-            tanggal_keluar: row.9,
-        })
-        .collect();
+    match jenis_keluar_id.as_deref().map(str::trim) {
+        None => return Err(format!("Siswa {} tidak tercatat keluar.", nama)),
+        Some("1") => return Err(format!("Siswa {} tercatat lulus, bukan mutasi keluar.", nama)),
+        Some(_) => {}
+    }
 
-    crate::emit_log(&app, &format!("CMD: get_daftar_siswa_keluar - Fetched {} records", siswa_keluar.len()));
-    Ok(siswa_keluar)
-}
\ No newline at end of file
+    // Catatan pencatatan keluar dari aplikasi ini. Tanpa catatan (misalnya dicatat lewat Dapodik) tidak diketahui
+    // rombel mana yang ditutup, sehingga pembatalan ditolak
+    let catatan = sqlx::query_as::<_, (bool, Option<String>, Vec<SqlxUuid>)>(
+        "DELETE FROM dapomaster.siswa_keluar WHERE registrasi_id = $1
+         RETURNING keterangan_diubah, keterangan_lama, anggota_rombel_ids"
+    )
+    .bind(registrasi_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal mengambil catatan siswa keluar: {}", e))?;
+    let (keterangan_diubah, keterangan_lama, anggota_rombel_ids) = catatan.ok_or_else(|| format!(
+        "Pencatatan keluar siswa {} tidak tercatat di DapoMaster, sehingga keanggotaan rombelnya tidak dapat dikembalikan. Batalkan melalui Dapodik.",
+        nama
+    ))?;
+
+    sqlx::query(
+        "UPDATE registrasi_peserta_didik
+         SET jenis_keluar_id = NULL, tanggal_keluar = NULL,
+             keterangan = CASE WHEN $1 THEN $2 ELSE keterangan END,
+             last_update = NOW(), updater_id = $3
+         WHERE registrasi_id = $4"
+    )
+    .bind(keterangan_diubah)
+    .bind(&keterangan_lama)
+    .bind(pengguna_id)
+    .bind(registrasi_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal mengupdate registrasi_peserta_didik: {}", e))?;
+
+    // Buka kembali tepat keanggotaan rombel yang ditutup saat pencatatan keluar
+    let rombel_dibuka = sqlx::query(
+        "UPDATE anggota_rombel SET soft_delete = 0, last_update = NOW(), updater_id = $1
+         WHERE anggota_rombel_id = ANY($2) AND soft_delete = 1"
+    )
+    .bind(pengguna_id)
+    .bind(&anggota_rombel_ids)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal membuka kembali anggota_rombel: {}", e))?
+    .rows_affected();
+
+    if !anggota_rombel_ids.is_empty() && rombel_dibuka == 0 {
+        return Err(format!(
+            "Keanggotaan rombel siswa {} yang ditutup saat keluar tidak ditemukan lagi, pembatalan dibatalkan.",
+            nama
+        ));
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    crate::emit_log(&app, &format!("CMD: batalkan_siswa_keluar - {} dikembalikan aktif, {} dari {} anggota rombel dibuka", nama, rombel_dibuka, anggota_rombel_ids.len()));
+    if anggota_rombel_ids.is_empty() {
+        return Ok(format!(
+            "Status keluar siswa {} berhasil dibatalkan. Siswa belum terdaftar di rombel semester aktif, tempatkan melalui menu rombel.",
+            nama
+        ));
+    }
+    if (rombel_dibuka as usize) < anggota_rombel_ids.len() {
+        return Ok(format!(
+            "Status keluar siswa {} berhasil dibatalkan, tetapi hanya {} dari {} keanggotaan rombel yang dapat dibuka kembali. Periksa rombel siswa.",
+            nama, rombel_dibuka, anggota_rombel_ids.len()
+        ));
+    }
+    Ok(format!("Status keluar siswa {} berhasil dibatalkan.", nama))
+}
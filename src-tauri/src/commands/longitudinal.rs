@@ -0,0 +1,476 @@
+//! Data periodik peserta didik (peserta_didik_longitudinal): tinggi dan berat badan, lingkar kepala,
+//! jarak dan waktu tempuh ke sekolah, serta jumlah saudara kandung. Satu baris per siswa per semester.
+
+use crate::app_state::DbPool;
+use crate::commands::export::{get_optional_string_value, ExportResult, ImportError, ImportResult};
+use tauri::{AppHandle, State};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sqlx::{Connection, Postgres, Transaction};
+use sqlx::types::Uuid as SqlxUuid;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, Workbook};
+use calamine::{open_workbook_auto, DataType, Reader};
+use std::str::FromStr;
+
+const HEADER_LONGITUDINAL: [&str; 11] = [
+    "ID", "No", "Nama", "NISN", "Tinggi Badan (cm)", "Berat Badan (kg)", "Lingkar Kepala (cm)",
+    "Jarak ke Sekolah (km)", "Waktu Tempuh (jam)", "Waktu Tempuh (menit)", "Jumlah Saudara Kandung",
+];
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct LongitudinalSiswa {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nisn: Option<String>,
+    pub semester_id: String,
+    pub tinggi_badan: Option<BigDecimal>,
+    pub berat_badan: Option<BigDecimal>,
+    pub lingkar_kepala: Option<BigDecimal>,
+    pub jarak_rumah_ke_sekolah_km: Option<BigDecimal>,
+    pub waktu_tempuh_ke_sekolah: Option<BigDecimal>,
+    pub menit_tempuh_ke_sekolah: Option<BigDecimal>,
+    pub jumlah_saudara_kandung: Option<BigDecimal>,
+    pub sudah_diisi: bool,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct DataLongitudinal {
+    pub peserta_didik_id: SqlxUuid,
+    pub tinggi_badan: Option<BigDecimal>,
+    pub berat_badan: Option<BigDecimal>,
+    pub lingkar_kepala: Option<BigDecimal>,
+    pub jarak_rumah_ke_sekolah_km: Option<BigDecimal>,
+    pub waktu_tempuh_ke_sekolah: Option<BigDecimal>,
+    pub menit_tempuh_ke_sekolah: Option<BigDecimal>,
+    pub jumlah_saudara_kandung: Option<BigDecimal>,
+}
+
+#[derive(Deserialize)]
+pub struct SimpanLongitudinalMassalPayload {
+    pub semester_id: String,
+    pub pengguna_id: SqlxUuid,
+    pub data: Vec<DataLongitudinal>,
+}
+
+/// Rentang nilai yang masih masuk akal untuk siswa PAUD sampai SMA
+fn validasi_longitudinal(data: &DataLongitudinal) -> Vec<(&'static str, String)> {
+    let rentang: [(&'static str, &Option<BigDecimal>, f64, f64); 7] = [
+        ("Tinggi Badan", &data.tinggi_badan, 40.0, 220.0),
+        ("Berat Badan", &data.berat_badan, 5.0, 200.0),
+        ("Lingkar Kepala", &data.lingkar_kepala, 30.0, 70.0),
+        ("Jarak ke Sekolah", &data.jarak_rumah_ke_sekolah_km, 0.0, 100.0),
+        ("Waktu Tempuh (jam)", &data.waktu_tempuh_ke_sekolah, 0.0, 12.0),
+        ("Waktu Tempuh (menit)", &data.menit_tempuh_ke_sekolah, 0.0, 59.0),
+        ("Jumlah Saudara Kandung", &data.jumlah_saudara_kandung, 0.0, 30.0),
+    ];
+
+    let mut errors = Vec::new();
+    for (label, nilai, min, maks) in rentang {
+        if let Some(n) = nilai.as_ref().and_then(|n| n.to_f64()) {
+            if n < min || n > maks {
+                errors.push((label, format!("{} harus di antara {} dan {}, tertulis {}", label, min, maks, n)));
+            }
+        }
+    }
+    if data.jumlah_saudara_kandung.as_ref().is_some_and(|n| !n.is_integer()) {
+        errors.push(("Jumlah Saudara Kandung", "Jumlah saudara kandung harus bilangan bulat".to_string()));
+    }
+    errors
+}
+
+// Kategori jarak Dapodik: 1 = kurang dari 1 km, 2 = lebih dari 1 km
+fn kategori_jarak(jarak_km: &Option<BigDecimal>) -> Option<i32> {
+    jarak_km.as_ref().and_then(|j| j.to_f64()).map(|j| if j <= 1.0 { 1 } else { 2 })
+}
+
+async fn simpan_satu(tx: &mut Transaction<'_, Postgres>, semester_id: &str, data: &DataLongitudinal, pengguna_id: SqlxUuid) -> Result<(), String> {
+    let updated = sqlx::query(
+        "UPDATE peserta_didik_longitudinal SET tinggi_badan = $3, berat_badan = $4, lingkar_kepala = $5,
+         jarak_rumah_ke_sekolah = COALESCE($6, jarak_rumah_ke_sekolah), jarak_rumah_ke_sekolah_km = $7,
+         waktu_tempuh_ke_sekolah = $8, menit_tempuh_ke_sekolah = $9, jumlah_saudara_kandung = $10,
+         soft_delete = 0, last_update = NOW(), updater_id = $11
+         WHERE peserta_didik_id = $1 AND semester_id = $2"
+    )
+    .bind(data.peserta_didik_id)
+    .bind(semester_id)
+    .bind(&data.tinggi_badan)
+    .bind(&data.berat_badan)
+    .bind(&data.lingkar_kepala)
+    .bind(kategori_jarak(&data.jarak_rumah_ke_sekolah_km))
+    .bind(&data.jarak_rumah_ke_sekolah_km)
+    .bind(&data.waktu_tempuh_ke_sekolah)
+    .bind(&data.menit_tempuh_ke_sekolah)
+    .bind(&data.jumlah_saudara_kandung)
+    .bind(pengguna_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Gagal memperbarui data longitudinal: {}", e))?;
+
+    if updated.rows_affected() == 0 {
+        sqlx::query(
+            "INSERT INTO peserta_didik_longitudinal (peserta_didik_id, semester_id, tinggi_badan, berat_badan, lingkar_kepala,
+             jarak_rumah_ke_sekolah, jarak_rumah_ke_sekolah_km, waktu_tempuh_ke_sekolah, menit_tempuh_ke_sekolah, jumlah_saudara_kandung,
+             create_date, last_update, soft_delete, updater_id)
+             VALUES ($1, $2, $3, $4, $5, COALESCE($6, 1), $7, $8, $9, $10, NOW(), NOW(), 0, $11)"
+        )
+        .bind(data.peserta_didik_id)
+        .bind(semester_id)
+        .bind(&data.tinggi_badan)
+        .bind(&data.berat_badan)
+        .bind(&data.lingkar_kepala)
+        .bind(kategori_jarak(&data.jarak_rumah_ke_sekolah_km))
+        .bind(&data.jarak_rumah_ke_sekolah_km)
+        .bind(&data.waktu_tempuh_ke_sekolah)
+        .bind(&data.menit_tempuh_ke_sekolah)
+        .bind(&data.jumlah_saudara_kandung)
+        .bind(pengguna_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Gagal menyimpan data longitudinal: {}", e))?;
+    }
+    Ok(())
+}
+
+async fn ambil_longitudinal_rombel(state: &State<'_, DbPool>, rombongan_belajar_id: SqlxUuid, semester_id: Option<&str>) -> Result<Vec<LongitudinalSiswa>, String> {
+    sqlx::query_as::<_, LongitudinalSiswa>(
+        "SELECT pd.peserta_didik_id, pd.nama, pd.nisn, sem.semester_id,
+                pdl.tinggi_badan, pdl.berat_badan, pdl.lingkar_kepala, pdl.jarak_rumah_ke_sekolah_km,
+                pdl.waktu_tempuh_ke_sekolah, pdl.menit_tempuh_ke_sekolah, pdl.jumlah_saudara_kandung,
+                (pdl.peserta_didik_id IS NOT NULL) AS sudah_diisi
+         FROM anggota_rombel ar
+         JOIN rombongan_belajar rb ON rb.rombongan_belajar_id = ar.rombongan_belajar_id
+         JOIN peserta_didik pd ON pd.peserta_didik_id = ar.peserta_didik_id
+         CROSS JOIN LATERAL (SELECT COALESCE($2, rb.semester_id) AS semester_id) sem
+         LEFT JOIN peserta_didik_longitudinal pdl ON pdl.peserta_didik_id = pd.peserta_didik_id
+              AND pdl.semester_id = sem.semester_id AND pdl.soft_delete = 0
+         WHERE ar.rombongan_belajar_id = $1 AND ar.soft_delete = 0 AND pd.soft_delete = 0
+         ORDER BY pd.nama"
+    )
+    .bind(rombongan_belajar_id)
+    .bind(semester_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil data longitudinal: {}", e))
+}
+
+/// Data longitudinal seluruh anggota rombel. Bila `semester_id` kosong, dipakai semester rombel tersebut.
+#[tauri::command]
+pub async fn get_longitudinal_rombel(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    rombongan_belajar_id: SqlxUuid,
+    semester_id: Option<String>,
+) -> Result<Vec<LongitudinalSiswa>, String> {
+    crate::emit_log(&app, &format!("CMD: get_longitudinal_rombel - rombel: {}, semester: {:?}", rombongan_belajar_id, semester_id));
+    ambil_longitudinal_rombel(&state, rombongan_belajar_id, semester_id.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn simpan_longitudinal(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    semester_id: String,
+    data: DataLongitudinal,
+    pengguna_id: SqlxUuid,
+) -> Result<String, String> {
+    crate::emit_log(&app, &format!("CMD: simpan_longitudinal - siswa: {}, semester: {}", data.peserta_didik_id, semester_id));
+
+    if let Some((_, pesan)) = validasi_longitudinal(&data).into_iter().next() {
+        return Err(pesan);
+    }
+
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    simpan_satu(&mut tx, &semester_id, &data, pengguna_id).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok("Data longitudinal berhasil disimpan.".to_string())
+}
+
+/// Menyimpan data satu kelas sekaligus. Bila ada satu baris yang tidak lolos validasi, tidak ada yang disimpan.
+#[tauri::command]
+pub async fn simpan_longitudinal_massal(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    payload: SimpanLongitudinalMassalPayload,
+) -> Result<String, String> {
+    crate::emit_log(&app, &format!("CMD: simpan_longitudinal_massal - {} siswa, semester: {}", payload.data.len(), payload.semester_id));
+
+    let kesalahan: Vec<String> = payload
+        .data
+        .iter()
+        .flat_map(|d| validasi_longitudinal(d).into_iter().map(move |(_, pesan)| format!("{}: {}", d.peserta_didik_id, pesan)))
+        .collect();
+    if !kesalahan.is_empty() {
+        return Err(format!("Data tidak valid:\n{}", kesalahan.join("\n")));
+    }
+
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    for data in &payload.data {
+        simpan_satu(&mut tx, &payload.semester_id, data, payload.pengguna_id).await?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    crate::emit_log(&app, &format!("CMD: simpan_longitudinal_massal - Berhasil menyimpan {} siswa", payload.data.len()));
+    Ok(format!("Berhasil menyimpan data longitudinal {} siswa.", payload.data.len()))
+}
+
+/// Menyalin data semester terakhir yang tersedia ke semester tujuan untuk anggota rombel.
+/// Siswa yang sudah punya data di semester tujuan dilewati kecuali `timpa` bernilai true.
+#[tauri::command]
+pub async fn salin_longitudinal_semester_lalu(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    rombongan_belajar_id: SqlxUuid,
+    semester_id: String,
+    pengguna_id: SqlxUuid,
+    timpa: bool,
+) -> Result<String, String> {
+    crate::emit_log(&app, &format!("CMD: salin_longitudinal_semester_lalu - rombel: {}, semester tujuan: {}", rombongan_belajar_id, semester_id));
+
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+
+    let sumber = sqlx::query_as::<_, (SqlxUuid, Option<BigDecimal>, Option<BigDecimal>, Option<BigDecimal>, Option<BigDecimal>, Option<BigDecimal>, Option<BigDecimal>, Option<BigDecimal>, bool)>(
+        "SELECT DISTINCT ON (ar.peserta_didik_id) ar.peserta_didik_id,
+                pdl.tinggi_badan, pdl.berat_badan, pdl.lingkar_kepala, pdl.jarak_rumah_ke_sekolah_km,
+                pdl.waktu_tempuh_ke_sekolah, pdl.menit_tempuh_ke_sekolah, pdl.jumlah_saudara_kandung,
+                EXISTS (SELECT 1 FROM peserta_didik_longitudinal t WHERE t.peserta_didik_id = ar.peserta_didik_id
+                        AND t.semester_id = $2 AND t.soft_delete = 0) AS sudah_ada
+         FROM anggota_rombel ar
+         JOIN peserta_didik_longitudinal pdl ON pdl.peserta_didik_id = ar.peserta_didik_id
+              AND pdl.semester_id < $2 AND pdl.soft_delete = 0
+         WHERE ar.rombongan_belajar_id = $1 AND ar.soft_delete = 0
+         ORDER BY ar.peserta_didik_id, pdl.semester_id DESC"
+    )
+    .bind(rombongan_belajar_id)
+    .bind(&semester_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal mengambil data semester sebelumnya: {}", e))?;
+
+    let mut disalin = 0;
+    let mut dilewati = 0;
+    for (peserta_didik_id, tinggi_badan, berat_badan, lingkar_kepala, jarak_rumah_ke_sekolah_km, waktu_tempuh_ke_sekolah, menit_tempuh_ke_sekolah, jumlah_saudara_kandung, sudah_ada) in sumber {
+        if sudah_ada && !timpa {
+            dilewati += 1;
+            continue;
+        }
+        let data = DataLongitudinal {
+            peserta_didik_id,
+            tinggi_badan,
+            berat_badan,
+            lingkar_kepala,
+            jarak_rumah_ke_sekolah_km,
+            waktu_tempuh_ke_sekolah,
+            menit_tempuh_ke_sekolah,
+            jumlah_saudara_kandung,
+        };
+        simpan_satu(&mut tx, &semester_id, &data, pengguna_id).await?;
+        disalin += 1;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    crate::emit_log(&app, &format!("CMD: salin_longitudinal_semester_lalu - {} disalin, {} dilewati", disalin, dilewati));
+    Ok(format!("Berhasil menyalin data {} siswa dari semester sebelumnya ({} dilewati karena sudah terisi).", disalin, dilewati))
+}
+
+/// Ekspor data longitudinal satu rombel. File yang sama dipakai sebagai lembar isian untuk diimpor kembali.
+#[tauri::command]
+pub async fn export_longitudinal_rombel(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    rombongan_belajar_id: SqlxUuid,
+    semester_id: Option<String>,
+) -> Result<ExportResult, String> {
+    crate::emit_log(&app, &format!("CMD: export_longitudinal_rombel - rombel: {}", rombongan_belajar_id));
+
+    let nama_rombel: String = sqlx::query_scalar("SELECT nama FROM rombongan_belajar WHERE rombongan_belajar_id = $1")
+        .bind(rombongan_belajar_id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Rombel tidak ditemukan")?;
+
+    let rows = ambil_longitudinal_rombel(&state, rombongan_belajar_id, semester_id.as_deref()).await?;
+
+    // Buat nama file dengan timestamp
+    let now: DateTime<Utc> = Utc::now();
+    let timestamp = now.format("%Y%m%d_%H%M%S");
+    let nama_file_rombel: String = nama_rombel.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+    let filename = format!("longitudinal_{}_{}.xlsx", nama_file_rombel, timestamp);
+
+    let desktop_path = dirs::desktop_dir()
+        .ok_or("Tidak dapat menemukan folder Desktop")?
+        .join(&filename);
+
+    let file_path = desktop_path.to_string_lossy().to_string();
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Data Longitudinal")
+        .map_err(|e| format!("Gagal mengatur nama worksheet: {}", e))?;
+
+    let header_format = Format::new()
+        .set_bold()
+        .set_align(FormatAlign::Center)
+        .set_border(FormatBorder::Thin)
+        .set_background_color(rust_xlsxwriter::Color::Gray);
+    let data_format = Format::new()
+        .set_border(FormatBorder::Thin)
+        .set_align(FormatAlign::Left);
+
+    for (col, header) in HEADER_LONGITUDINAL.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *header, &header_format)
+            .map_err(|e| format!("Gagal menulis header: {}", e))?;
+    }
+
+    for (idx, r) in rows.iter().enumerate() {
+        let row_num = (idx + 1) as u32;
+        let teks = [
+            r.peserta_didik_id.to_string(),
+            (idx + 1).to_string(),
+            r.nama.clone(),
+            r.nisn.clone().unwrap_or_default(),
+        ];
+        for (col, isi) in teks.iter().enumerate() {
+            worksheet.write_string_with_format(row_num, col as u16, isi, &data_format)
+                .map_err(|e| format!("Gagal menulis baris {}: {}", idx + 1, e))?;
+        }
+        let angka = [
+            &r.tinggi_badan, &r.berat_badan, &r.lingkar_kepala, &r.jarak_rumah_ke_sekolah_km,
+            &r.waktu_tempuh_ke_sekolah, &r.menit_tempuh_ke_sekolah, &r.jumlah_saudara_kandung,
+        ];
+        for (offset, nilai) in angka.iter().enumerate() {
+            let col = (teks.len() + offset) as u16;
+            match nilai.as_ref().and_then(|n| n.to_f64()) {
+                Some(n) => worksheet.write_number_with_format(row_num, col, n, &data_format),
+                None => worksheet.write_blank(row_num, col, &data_format),
+            }
+            .map_err(|e| format!("Gagal menulis baris {}: {}", idx + 1, e))?;
+        }
+    }
+
+    // Kolom ID dipakai saat impor, disembunyikan agar tidak diubah guru
+    worksheet.set_column_hidden(0).map_err(|e| e.to_string())?;
+    worksheet.autofit();
+
+    workbook.save(&file_path)
+        .map_err(|e| format!("Gagal menyimpan workbook: {}", e))?;
+
+    crate::emit_log(&app, &format!("CMD: export_longitudinal_rombel - Exported {} siswa to {}", rows.len(), file_path));
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Berhasil mengekspor data longitudinal {} siswa ke file Excel: {}", rows.len(), filename),
+        file_path: Some(file_path),
+    })
+}
+
+fn baca_angka(cell: &DataType, label: &str, row: usize, errors: &mut Vec<ImportError>) -> Option<BigDecimal> {
+    let teks = get_optional_string_value(cell, label, row, errors)?;
+    match BigDecimal::from_str(&teks.replace(',', ".")) {
+        Ok(n) => Some(n),
+        Err(_) => {
+            errors.push(ImportError { row: row as i32, field: label.to_string(), message: format!("'{}' bukan angka", teks) });
+            None
+        }
+    }
+}
+
+/// Impor lembar isian hasil `export_longitudinal_rombel`. Baris yang valid disimpan, baris bermasalah dilaporkan.
+/// Tiap baris disimpan dalam savepoint sendiri sehingga kegagalan satu baris tidak membatalkan baris lainnya.
+#[tauri::command]
+pub async fn import_longitudinal_from_excel(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    file_path: String,
+    semester_id: String,
+    pengguna_id: SqlxUuid,
+) -> Result<ImportResult, String> {
+    crate::emit_log(&app, &format!("CMD: import_longitudinal_from_excel - file: {}, semester: {}", file_path, semester_id));
+
+    let mut workbook = open_workbook_auto(&file_path)
+        .map_err(|e| format!("Gagal membuka file Excel: {}", e))?;
+
+    let range = workbook.worksheet_range_at(0)
+        .ok_or("Tidak dapat menemukan worksheet")?
+        .map_err(|e| format!("Gagal membaca worksheet: {}", e))?;
+
+    let header: Vec<String> = range.rows().next().unwrap_or_default().iter().map(|c| c.to_string().trim().to_string()).collect();
+    if header.len() < HEADER_LONGITUDINAL.len() || header.iter().zip(HEADER_LONGITUDINAL).any(|(h, e)| h != e) {
+        return Err("Format file tidak sesuai. Gunakan file hasil ekspor data longitudinal.".to_string());
+    }
+
+    let mut errors = Vec::new();
+    let mut valid = Vec::new();
+    let mut total_rows = 0;
+    let mut error_count = 0;
+
+    for (row_idx, row) in range.rows().skip(1).enumerate() {
+        let excel_row = row_idx + 2;
+        let kosong = DataType::Empty;
+        let cell = |idx: usize| row.get(idx).unwrap_or(&kosong);
+        if (4..HEADER_LONGITUDINAL.len()).all(|i| matches!(cell(i), DataType::Empty)) {
+            continue;
+        }
+        total_rows += 1;
+        let jumlah_error_awal = errors.len();
+
+        let peserta_didik_id = match SqlxUuid::parse_str(cell(0).to_string().trim()) {
+            Ok(id) => Some(id),
+            Err(_) => {
+                errors.push(ImportError { row: excel_row as i32, field: "ID".to_string(), message: "ID siswa tidak valid, jangan ubah kolom ID".to_string() });
+                None
+            }
+        };
+        let data = DataLongitudinal {
+            peserta_didik_id: peserta_didik_id.unwrap_or_default(),
+            tinggi_badan: baca_angka(cell(4), "Tinggi Badan", excel_row, &mut errors),
+            berat_badan: baca_angka(cell(5), "Berat Badan", excel_row, &mut errors),
+            lingkar_kepala: baca_angka(cell(6), "Lingkar Kepala", excel_row, &mut errors),
+            jarak_rumah_ke_sekolah_km: baca_angka(cell(7), "Jarak ke Sekolah", excel_row, &mut errors),
+            waktu_tempuh_ke_sekolah: baca_angka(cell(8), "Waktu Tempuh (jam)", excel_row, &mut errors),
+            menit_tempuh_ke_sekolah: baca_angka(cell(9), "Waktu Tempuh (menit)", excel_row, &mut errors),
+            jumlah_saudara_kandung: baca_angka(cell(10), "Jumlah Saudara Kandung", excel_row, &mut errors),
+        };
+        for (field, message) in validasi_longitudinal(&data) {
+            errors.push(ImportError { row: excel_row as i32, field: field.to_string(), message });
+        }
+
+        if errors.len() > jumlah_error_awal {
+            error_count += 1;
+        } else {
+            valid.push((excel_row, data));
+        }
+    }
+
+    let mut success_count = 0;
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    for (excel_row, data) in &valid {
+        let mut sp = tx.begin().await.map_err(|e| e.to_string())?;
+        match simpan_satu(&mut sp, &semester_id, data, pengguna_id).await {
+            Ok(()) => {
+                sp.commit().await.map_err(|e| e.to_string())?;
+                success_count += 1;
+            }
+            Err(e) => {
+                sp.rollback().await.map_err(|e| e.to_string())?;
+                errors.push(ImportError { row: *excel_row as i32, field: "Simpan".to_string(), message: e });
+                error_count += 1;
+            }
+        }
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    crate::emit_log(&app, &format!("CMD: import_longitudinal_from_excel - {} berhasil, {} gagal", success_count, error_count));
+
+    Ok(ImportResult {
+        success: error_count == 0,
+        message: format!("Import selesai. {} baris berhasil, {} baris bermasalah.", success_count, error_count),
+        total_rows,
+        success_count,
+        error_count,
+        errors,
+    })
+}
@@ -1,10 +1,17 @@
-use crate::app_state::DbPool;
+use crate::app_state::{DbPool, ReferensiCache};
+use crate::commands::jenjang::{aturan_sekolah, AturanSekolah};
+use crate::commands::nomor_ijazah::validasi_ijazah;
+use crate::commands::referensi::ambil_referensi;
+use crate::commands::tinggal_kelas::tinggal_kelas_semester;
 use tauri::{AppHandle, State};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid as SqlxUuid;
 use chrono::NaiveDate;
 use uuid::Uuid;
 use bigdecimal::BigDecimal;
+use sqlx::Connection;
+use std::collections::HashSet;
+use std::str::FromStr;
 
 #[derive(Serialize)]
 pub struct SiswaLulus {
@@ -33,6 +40,32 @@ pub struct UpdateIjazahPayload {
 #[derive(Deserialize)]
 pub struct BulkUpdateIjazahPayload {
     pub updates: Vec<UpdateIjazahPayload>,
+    /// Batalkan semua perubahan bila ada satu baris yang gagal
+    #[serde(default)]
+    pub semua_atau_tidak: bool,
+    pub pengguna_id: SqlxUuid,
+}
+
+#[derive(Serialize)]
+pub struct HasilBarisIjazah {
+    pub index: usize,
+    pub peserta_didik_id: String,
+    /// diupdate, ditambahkan, tidak_ditemukan, tidak_valid, atau gagal
+    pub status: String,
+    pub pesan: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct HasilBulkIjazah {
+    pub diupdate: i32,
+    pub ditambahkan: i32,
+    pub tidak_ditemukan: i32,
+    /// Baris tidak valid atau gagal disimpan
+    pub gagal: i32,
+    /// true bila mode semua_atau_tidak membatalkan seluruh perubahan
+    pub dibatalkan: bool,
+    pub pesan: String,
+    pub hasil: Vec<HasilBarisIjazah>,
 }
 
 #[tauri::command]
@@ -182,69 +215,136 @@ pub async fn get_daftar_siswa_lulus(
     Ok(siswa_lulus)
 }
 
+/// Mengisi data ijazah banyak siswa sekaligus. Baris ijazah_pd yang belum ada akan dibuat. Dengan
+/// `semua_atau_tidak`, satu baris gagal membatalkan seluruh perubahan; tanpa itu, baris yang gagal saja yang dilewati.
 #[tauri::command]
 pub async fn update_bulk_ijazah(
     app: AppHandle,
     state: State<'_, DbPool>,
     payload: BulkUpdateIjazahPayload,
-) -> Result<String, String> {
-    crate::emit_log(&app, "CMD: update_bulk_ijazah - Starting bulk update process.");
+) -> Result<HasilBulkIjazah, String> {
+    crate::emit_log(&app, &format!("CMD: update_bulk_ijazah - {} baris, semua_atau_tidak: {}", payload.updates.len(), payload.semua_atau_tidak));
 
     let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
-    let mut success_count = 0;
-
-    for update in payload.updates {
-        let peserta_didik_id = match Uuid::parse_str(&update.peserta_didik_id) {
-            Ok(id) => id,
-            Err(_) => {
-                crate::emit_log(&app, &format!("CMD: update_bulk_ijazah - Invalid UUID: {}", update.peserta_didik_id));
-                continue;
-            }
-        };
+    let mut hasil = HasilBulkIjazah::default();
 
-        let tanggal_tanda_tangan = if let Some(tgl_str) = &update.tanggal_tanda_tangan {
-            match NaiveDate::parse_from_str(tgl_str, "%Y-%m-%d") {
-                Ok(tgl) => Some(tgl),
-                Err(_) => {
-                    crate::emit_log(&app, &format!("CMD: update_bulk_ijazah - Invalid date format: {}", tgl_str));
-                    continue;
-                }
+    for (index, update) in payload.updates.iter().enumerate() {
+        let baris = match simpan_satu_ijazah(&mut tx, update, payload.pengguna_id).await {
+            Ok(status) => HasilBarisIjazah { index, peserta_didik_id: update.peserta_didik_id.clone(), status: status.to_string(), pesan: None },
+            Err((status, pesan)) => {
+                crate::emit_log(&app, &format!("CMD: update_bulk_ijazah - Baris {} ({}): {}", index + 1, update.peserta_didik_id, pesan));
+                HasilBarisIjazah { index, peserta_didik_id: update.peserta_didik_id.clone(), status: status.to_string(), pesan: Some(pesan) }
             }
-        } else {
-            None
         };
+        match baris.status.as_str() {
+            "diupdate" => hasil.diupdate += 1,
+            "ditambahkan" => hasil.ditambahkan += 1,
+            "tidak_ditemukan" => hasil.tidak_ditemukan += 1,
+            _ => hasil.gagal += 1,
+        }
+        hasil.hasil.push(baris);
+    }
+
+    let ada_gagal = hasil.tidak_ditemukan + hasil.gagal > 0;
+    if payload.semua_atau_tidak && ada_gagal {
+        tx.rollback().await.map_err(|e| e.to_string())?;
+        hasil.dibatalkan = true;
+        hasil.pesan = format!(
+            "Tidak ada data yang disimpan: {} baris tidak ditemukan dan {} baris gagal.",
+            hasil.tidak_ditemukan, hasil.gagal
+        );
+    } else {
+        tx.commit().await.map_err(|e| e.to_string())?;
+        hasil.pesan = format!(
+            "{} data ijazah diupdate, {} ditambahkan, {} tidak ditemukan, {} gagal.",
+            hasil.diupdate, hasil.ditambahkan, hasil.tidak_ditemukan, hasil.gagal
+        );
+    }
+
+    crate::emit_log(&app, &format!("CMD: update_bulk_ijazah - {}", hasil.pesan));
+    Ok(hasil)
+}
+
+/// Menyimpan satu baris dalam savepoint sendiri, agar kegagalan satu baris tidak merusak transaksi luar
+pub(crate) async fn simpan_satu_ijazah(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    update: &UpdateIjazahPayload,
+    pengguna_id: SqlxUuid,
+) -> Result<&'static str, (&'static str, String)> {
+    let tidak_valid = |pesan: String| ("tidak_valid", pesan);
+
+    let peserta_didik_id = Uuid::parse_str(&update.peserta_didik_id)
+        .map_err(|_| tidak_valid(format!("ID peserta didik tidak valid: {}", update.peserta_didik_id)))?;
+    let tanggal_tanda_tangan = match update.tanggal_tanda_tangan.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        Some(tgl) => Some(
+            NaiveDate::parse_from_str(tgl, "%Y-%m-%d").map_err(|_| tidak_valid(format!("Format tanggal tanda tangan salah: {}", tgl)))?,
+        ),
+        None => None,
+    };
+    let jenis_ijazah_id = match update.jenis_ijazah_id.as_deref().map(str::trim).filter(|j| !j.is_empty()) {
+        Some(j) => Some(BigDecimal::from_str(j).map_err(|_| tidak_valid(format!("Jenis ijazah tidak valid: {}", j)))?),
+        None => None,
+    };
+
+    let gagal = |e: sqlx::Error| ("gagal", e.to_string());
+    let mut sp = tx.begin().await.map_err(gagal)?;
+
+    let registrasi_id: Option<SqlxUuid> = sqlx::query_scalar(
+        "SELECT registrasi_id FROM registrasi_peserta_didik WHERE peserta_didik_id = $1 AND soft_delete = 0"
+    )
+    .bind(peserta_didik_id)
+    .fetch_optional(&mut *sp)
+    .await
+    .map_err(gagal)?;
+    let Some(registrasi_id) = registrasi_id else {
+        return Err(("tidak_ditemukan", "Registrasi siswa tidak ditemukan".to_string()));
+    };
+
+    if let Err(pesan) = validasi_ijazah(&mut sp, registrasi_id, jenis_ijazah_id.as_ref(), update.nomor.as_deref(), tanggal_tanda_tangan).await {
+        return Err(("tidak_valid", pesan));
+    }
+
+    let diupdate = sqlx::query(
+        "UPDATE ijazah_pd
+         SET jenis_ijazah_id = $1, nomor = $2, penandatangan = $3, tanggal_ttd = $4, last_update = NOW(),
+             updater_id = $5
+         WHERE registrasi_id = $6 AND soft_delete = 0"
+    )
+    .bind(&jenis_ijazah_id)
+    .bind(&update.nomor)
+    .bind(&update.penandatangan)
+    .bind(tanggal_tanda_tangan)
+    .bind(pengguna_id)
+    .bind(registrasi_id)
+    .execute(&mut *sp)
+    .await
+    .map_err(gagal)?
+    .rows_affected();
 
-        let result = sqlx::query(
-            "UPDATE ijazah_pd 
-             SET jenis_ijazah_id = $1, nomor = $2, penandatangan = $3, tanggal_ttd = $4, last_update = NOW()
-             WHERE registrasi_id = (SELECT registrasi_id FROM registrasi_peserta_didik WHERE peserta_didik_id = $5)"
+    let status = if diupdate > 0 {
+        "diupdate"
+    } else {
+        sqlx::query(
+            "INSERT INTO ijazah_pd (registrasi_id, jenis_ijazah_id, nomor, penandatangan, tanggal_ttd, create_date, last_update, soft_delete, updater_id)
+             VALUES ($1, $2, $3, $4, $5, NOW(), NOW(), 0, $6)"
         )
-        .bind(&update.jenis_ijazah_id)
+        .bind(registrasi_id)
+        .bind(&jenis_ijazah_id)
         .bind(&update.nomor)
         .bind(&update.penandatangan)
         .bind(tanggal_tanda_tangan)
-        .bind(peserta_didik_id)
-        .execute(&mut *tx)
-        .await;
-
-        match result {
-            Ok(_) => {
-                success_count += 1;
-                crate::emit_log(&app, &format!("CMD: update_bulk_ijazah - Successfully updated peserta_didik_id: {}", peserta_didik_id));
-            }
-            Err(e) => {
-                crate::emit_log(&app, &format!("CMD: update_bulk_ijazah - Error updating peserta_didik_id {}: {}", peserta_didik_id, e));
-            }
-        }
-    }
-
-    tx.commit().await.map_err(|e| e.to_string())?;
+        .bind(pengguna_id)
+        .execute(&mut *sp)
+        .await
+        .map_err(gagal)?;
+        "ditambahkan"
+    };
 
-    crate::emit_log(&app, &format!("CMD: update_bulk_ijazah - Completed. Successfully updated {} records", success_count));
-    Ok(format!("Berhasil mengupdate {} data ijazah", success_count))
+    sp.commit().await.map_err(gagal)?;
+    Ok(status)
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct JenisIjazah {
     pub jenis_ijazah_id: BigDecimal,
     pub nama: String,
@@ -254,24 +354,261 @@ pub struct JenisIjazah {
 pub async fn get_all_jenis_ijazah(
     app: AppHandle,
     state: State<'_, DbPool>,
+    cache: State<'_, ReferensiCache>,
 ) -> Result<Vec<JenisIjazah>, String> {
     crate::emit_log(&app, "CMD: get_all_jenis_ijazah - Fetching all jenis ijazah.");
 
-    let rows = sqlx::query_as::<_, (BigDecimal, String)>(
-        "SELECT jenis_ijazah_id, nama FROM ref.jenis_ijazah ORDER BY nama"
+    let jenis_ijazah = ambil_referensi(&state.pool, &cache).await?.jenis_ijazah.clone();
+
+    crate::emit_log(&app, &format!("CMD: get_all_jenis_ijazah - Found {} jenis ijazah", jenis_ijazah.len()));
+    Ok(jenis_ijazah)
+}
+
+#[derive(Serialize)]
+pub struct KandidatLulus {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nisn: Option<String>,
+    pub nama_rombel: String,
+    pub sudah_ada_ijazah: bool,
+    /// Alasan siswa tidak ikut diluluskan; None berarti siap diproses
+    pub alasan_dilewati: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PreviewKelulusan {
+    pub semester_id: String,
+    pub tingkat_akhir: i32,
+    pub siap_diproses: i32,
+    pub kandidat: Vec<KandidatLulus>,
+}
+
+#[derive(Deserialize)]
+pub struct ProsesKelulusanPayload {
+    pub semester_id: String,
+    pub peserta_didik_ids: Vec<SqlxUuid>,
+    pub tanggal_lulus: String,
+    pub jenis_ijazah_id: Option<BigDecimal>,
+    pub pengguna_id: SqlxUuid,
+}
+
+#[derive(Serialize)]
+pub struct GagalLulus {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub pesan: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct RingkasanKelulusan {
+    pub berhasil: i32,
+    pub ijazah_dibuat: i32,
+    pub anggota_rombel_ditutup: i32,
+    pub dilewati: Vec<GagalLulus>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SiswaTingkatAkhir {
+    peserta_didik_id: SqlxUuid,
+    nama: String,
+    nisn: Option<String>,
+    nama_rombel: String,
+    jenis_keluar_id: Option<String>,
+    sudah_ada_ijazah: bool,
+    tingkat_pendidikan_id: i32,
+    jurusan_sp_id: Option<SqlxUuid>,
+    kurikulum_id: Option<i32>,
+}
+
+/// Siswa di rombel tingkat akhir pada semester tersebut, satu baris per siswa. Tingkat akhir ditentukan
+/// per rombel karena jurusan atau kurikulum tertentu dapat memiliki tingkat akhir sendiri.
+async fn siswa_tingkat_akhir(
+    conn: &mut sqlx::PgConnection,
+    semester_id: &str,
+    aturan: &AturanSekolah,
+) -> Result<Vec<SiswaTingkatAkhir>, String> {
+    let siswa = sqlx::query_as::<_, SiswaTingkatAkhir>(
+        "SELECT DISTINCT ON (pd.nama, pd.peserta_didik_id)
+                pd.peserta_didik_id, pd.nama, pd.nisn, rb.nama AS nama_rombel, TRIM(rpd.jenis_keluar_id) AS jenis_keluar_id,
+                EXISTS (SELECT 1 FROM ijazah_pd ip WHERE ip.registrasi_id = rpd.registrasi_id AND ip.soft_delete = 0) AS sudah_ada_ijazah,
+                rb.tingkat_pendidikan_id::int AS tingkat_pendidikan_id, rb.jurusan_sp_id, rb.kurikulum_id::int AS kurikulum_id
+         FROM anggota_rombel ar
+         JOIN rombongan_belajar rb ON rb.rombongan_belajar_id = ar.rombongan_belajar_id
+         JOIN peserta_didik pd ON pd.peserta_didik_id = ar.peserta_didik_id
+         JOIN registrasi_peserta_didik rpd ON rpd.peserta_didik_id = pd.peserta_didik_id AND rpd.soft_delete = 0
+         WHERE rb.semester_id = $1 AND rb.tingkat_pendidikan_id >= $2
+         AND ar.soft_delete = 0 AND rb.soft_delete = 0 AND pd.soft_delete = 0
+         ORDER BY pd.nama, pd.peserta_didik_id, rb.nama"
     )
-    .fetch_all(&state.pool)
+    .bind(semester_id)
+    .bind(aturan.tingkat_akhir_terendah())
+    .fetch_all(conn)
     .await
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| format!("Gagal mengambil siswa tingkat akhir: {}", e))?;
 
-    let jenis_ijazah: Vec<JenisIjazah> = rows
+    Ok(siswa
         .into_iter()
-        .map(|row| JenisIjazah {
-            jenis_ijazah_id: row.0,
-            nama: row.1,
+        .filter(|s| aturan.adalah_tingkat_akhir(s.tingkat_pendidikan_id, s.jurusan_sp_id, s.kurikulum_id))
+        .collect())
+}
+
+fn alasan_dilewati(siswa: &SiswaTingkatAkhir, tinggal_kelas: &HashSet<SqlxUuid>) -> Option<String> {
+    match siswa.jenis_keluar_id.as_deref() {
+        Some("1") => Some("Sudah tercatat lulus".to_string()),
+        Some(_) => Some("Sudah tercatat keluar".to_string()),
+        None if tinggal_kelas.contains(&siswa.peserta_didik_id) => Some("Ditandai tinggal kelas".to_string()),
+        None => None,
+    }
+}
+
+/// Pratinjau kelulusan: siswa rombel tingkat akhir pada semester tersebut (bawaan: semester aktif)
+#[tauri::command]
+pub async fn preview_kelulusan(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    semester_id: Option<String>,
+) -> Result<PreviewKelulusan, String> {
+    crate::emit_log(&app, &format!("CMD: preview_kelulusan - semester: {:?}", semester_id));
+    state.pastikan_skema_dapomaster()?;
+
+    let semester_id = match semester_id {
+        Some(s) => s,
+        None => sqlx::query_scalar("SELECT semester_id FROM ref.semester WHERE periode_aktif = 1 LIMIT 1")
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(|e| format!("Gagal mengambil semester aktif: {}", e))?
+            .ok_or("Semester aktif tidak ditemukan")?,
+    };
+    let aturan = aturan_sekolah(&state.pool).await?;
+
+    let mut conn = state.pool.acquire().await.map_err(|e| e.to_string())?;
+    let tinggal_kelas: HashSet<SqlxUuid> = tinggal_kelas_semester(&mut conn, &semester_id).await?.into_keys().collect();
+    let siswa = siswa_tingkat_akhir(&mut conn, &semester_id, &aturan).await?;
+
+    let kandidat: Vec<KandidatLulus> = siswa
+        .iter()
+        .map(|s| KandidatLulus {
+            peserta_didik_id: s.peserta_didik_id,
+            nama: s.nama.clone(),
+            nisn: s.nisn.clone(),
+            nama_rombel: s.nama_rombel.clone(),
+            sudah_ada_ijazah: s.sudah_ada_ijazah,
+            alasan_dilewati: alasan_dilewati(s, &tinggal_kelas),
         })
         .collect();
+    let siap_diproses = kandidat.iter().filter(|k| k.alasan_dilewati.is_none()).count() as i32;
 
-    crate::emit_log(&app, &format!("CMD: get_all_jenis_ijazah - Found {} jenis ijazah", jenis_ijazah.len()));
-    Ok(jenis_ijazah)
-}
\ No newline at end of file
+    crate::emit_log(&app, &format!("CMD: preview_kelulusan - {} siswa tingkat akhir, {} siap diproses", kandidat.len(), siap_diproses));
+    Ok(PreviewKelulusan { semester_id, tingkat_akhir: aturan.tingkat_akhir, siap_diproses, kandidat })
+}
+
+/// Meluluskan siswa tingkat akhir: mengisi status keluar "Lulus" dan tanggal lulus, menutup anggota rombel
+/// semester tersebut, dan membuat baris ijazah_pd kosong bila belum ada. Semua dalam satu transaksi.
+#[tauri::command]
+pub async fn proses_kelulusan(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    payload: ProsesKelulusanPayload,
+) -> Result<RingkasanKelulusan, String> {
+    crate::emit_log(&app, &format!("CMD: proses_kelulusan - semester: {}, {} siswa", payload.semester_id, payload.peserta_didik_ids.len()));
+    state.pastikan_skema_dapomaster()?;
+
+    if payload.peserta_didik_ids.is_empty() {
+        return Err("Belum ada siswa yang dipilih.".to_string());
+    }
+    let tanggal_lulus = NaiveDate::parse_from_str(&payload.tanggal_lulus, "%Y-%m-%d")
+        .map_err(|e| format!("Format tanggal lulus salah: {}", e))?;
+
+    let aturan = aturan_sekolah(&state.pool).await?;
+    let dipilih: HashSet<SqlxUuid> = payload.peserta_didik_ids.iter().copied().collect();
+
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    let tinggal_kelas: HashSet<SqlxUuid> = tinggal_kelas_semester(&mut tx, &payload.semester_id).await?.into_keys().collect();
+    let siswa = siswa_tingkat_akhir(&mut tx, &payload.semester_id, &aturan).await?;
+
+    let mut ringkasan = RingkasanKelulusan::default();
+    let mut ditemukan = HashSet::new();
+
+    for s in siswa.iter().filter(|s| dipilih.contains(&s.peserta_didik_id)) {
+        ditemukan.insert(s.peserta_didik_id);
+        if let Some(pesan) = alasan_dilewati(s, &tinggal_kelas) {
+            ringkasan.dilewati.push(GagalLulus { peserta_didik_id: s.peserta_didik_id, nama: s.nama.clone(), pesan });
+            continue;
+        }
+
+        let (registrasi_id, tanggal_masuk) = sqlx::query_as::<_, (SqlxUuid, Option<NaiveDate>)>(
+            "SELECT registrasi_id, tanggal_masuk_sekolah FROM registrasi_peserta_didik
+             WHERE peserta_didik_id = $1 AND soft_delete = 0 FOR UPDATE"
+        )
+        .bind(s.peserta_didik_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Gagal mengambil registrasi {}: {}", s.nama, e))?;
+
+        if tanggal_masuk.is_some_and(|t| tanggal_lulus < t) {
+            ringkasan.dilewati.push(GagalLulus {
+                peserta_didik_id: s.peserta_didik_id,
+                nama: s.nama.clone(),
+                pesan: "Tanggal lulus sebelum tanggal masuk sekolah".to_string(),
+            });
+            continue;
+        }
+
+        sqlx::query(
+            "UPDATE registrasi_peserta_didik
+             SET jenis_keluar_id = '1', tanggal_keluar = $1, last_update = NOW(), updater_id = $2
+             WHERE registrasi_id = $3"
+        )
+        .bind(tanggal_lulus)
+        .bind(payload.pengguna_id)
+        .bind(registrasi_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Gagal mengupdate registrasi {}: {}", s.nama, e))?;
+
+        let ditutup = sqlx::query(
+            "UPDATE anggota_rombel SET soft_delete = 1, last_update = NOW(), updater_id = $1
+             WHERE peserta_didik_id = $2 AND soft_delete = 0
+             AND rombongan_belajar_id IN (SELECT rombongan_belajar_id FROM rombongan_belajar WHERE semester_id = $3)"
+        )
+        .bind(payload.pengguna_id)
+        .bind(s.peserta_didik_id)
+        .bind(&payload.semester_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Gagal menutup anggota rombel {}: {}", s.nama, e))?;
+        ringkasan.anggota_rombel_ditutup += ditutup.rows_affected() as i32;
+
+        if !s.sudah_ada_ijazah {
+            sqlx::query(
+                "INSERT INTO ijazah_pd (registrasi_id, jenis_ijazah_id, create_date, last_update, soft_delete, updater_id)
+                 VALUES ($1, $2, NOW(), NOW(), 0, $3)"
+            )
+            .bind(registrasi_id)
+            .bind(&payload.jenis_ijazah_id)
+            .bind(payload.pengguna_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Gagal membuat data ijazah {}: {}", s.nama, e))?;
+            ringkasan.ijazah_dibuat += 1;
+        }
+
+        ringkasan.berhasil += 1;
+    }
+
+    for peserta_didik_id in dipilih.difference(&ditemukan) {
+        ringkasan.dilewati.push(GagalLulus {
+            peserta_didik_id: *peserta_didik_id,
+            nama: String::new(),
+            pesan: format!("Tidak terdaftar di rombel tingkat akhir semester {}", payload.semester_id),
+        });
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    crate::emit_log(&app, &format!(
+        "CMD: proses_kelulusan - {} lulus, {} ijazah dibuat, {} dilewati",
+        ringkasan.berhasil, ringkasan.ijazah_dibuat, ringkasan.dilewati.len()
+    ));
+    Ok(ringkasan)
+}
@@ -6,4 +6,23 @@ pub mod lulusan;
 pub mod keluar;
 pub mod export;
 pub mod validasi;
-pub mod naik_kelas; 
\ No newline at end of file
+pub mod naik_kelas;
+pub mod rombel;
+pub mod pengaturan;
+pub mod ppdb;
+pub mod nipd;
+pub mod alamat;
+pub mod wilayah;
+pub mod riwayat;
+pub mod longitudinal;
+pub mod usia;
+pub mod jenjang;
+pub mod tinggal_kelas;
+pub mod nomor_ijazah;
+pub mod import_lulusan;
+pub mod dokumen;
+pub mod skl;
+pub mod tracer;
+pub mod nilai;
+pub mod surat_pindah;
+pub mod dapomaster;
@@ -1,10 +1,14 @@
 use crate::app_state::DbPool;
+use crate::commands::jenjang::aturan_sekolah;
+use crate::commands::tinggal_kelas::tinggal_kelas_semester;
 use crate::emit_log;
 use tauri::{AppHandle, State};
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use sqlx::types::Uuid as SqlxUuid;
 use bigdecimal::BigDecimal;
+use uuid::Uuid;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SiswaRombel {
@@ -16,7 +20,7 @@ pub struct SiswaRombel {
     pub tingkat_pendidikan_nama: String,
 }
 
-/// Mendapatkan daftar siswa yang akan naik kelas dari semester sebelumnya
+/// Mendapatkan daftar siswa yang akan naik kelas dari semester sebelumnya (tanpa tingkat akhir jenjang)
 #[tauri::command]
 pub async fn get_siswa_naik_kelas(
     app: AppHandle,
@@ -32,6 +36,9 @@ pub async fn get_siswa_naik_kelas(
             pd.nisn,
             rb.nama as nama_rombel,
             rb.tingkat_pendidikan_id,
+            rb.tingkat_pendidikan_id::int as tingkat,
+            rb.jurusan_sp_id,
+            rb.kurikulum_id::int as kurikulum_id,
             tp.nama as tingkat_pendidikan_nama
         FROM anggota_rombel ar
         JOIN rombongan_belajar rb ON ar.rombongan_belajar_id = rb.rombongan_belajar_id
@@ -41,10 +48,12 @@ pub async fn get_siswa_naik_kelas(
         AND rb.soft_delete = 0 
         AND pd.soft_delete = 0 
         AND rb.semester_id = $1
-        AND rb.tingkat_pendidikan_id < 6
         ORDER BY rb.tingkat_pendidikan_id, rb.nama, pd.nama
     "#;
     
+    // Siswa di tingkat akhir jenjang tidak naik kelas, melainkan diproses lewat kelulusan
+    let aturan = aturan_sekolah(&state.pool).await?;
+
     let rows = sqlx::query(query)
         .bind(&semester_sebelumnya)
         .fetch_all(&state.pool)
@@ -53,6 +62,9 @@ pub async fn get_siswa_naik_kelas(
     
     let mut siswa_list = Vec::new();
     for row in rows {
+        if aturan.adalah_tingkat_akhir(row.get("tingkat"), row.get("jurusan_sp_id"), row.get("kurikulum_id")) {
+            continue;
+        }
         siswa_list.push(SiswaRombel {
             peserta_didik_id: row.get("peserta_didik_id"),
             nama: row.get("nama"),
@@ -103,4 +115,625 @@ pub async fn get_daftar_semester(
     
     emit_log(&app, &format!("CMD: get_daftar_semester - Berhasil mengambil {} semester", semester_list.len()));
     Ok(semester_list)
-} 
\ No newline at end of file
+}
+
+#[derive(Deserialize)]
+pub struct PemetaanRombel {
+    pub rombongan_belajar_id_lama: SqlxUuid,
+    pub rombongan_belajar_id_baru: SqlxUuid,
+}
+
+/// Penempatan khusus satu siswa; `rombongan_belajar_id_baru` kosong berarti siswa tidak diproses
+#[derive(Deserialize)]
+pub struct PengecualianNaikKelas {
+    pub peserta_didik_id: SqlxUuid,
+    pub rombongan_belajar_id_baru: Option<SqlxUuid>,
+}
+
+#[derive(Deserialize)]
+pub struct ProsesNaikKelasPayload {
+    pub semester_lama: String,
+    pub pemetaan: Vec<PemetaanRombel>,
+    #[serde(default)]
+    pub pengecualian: Vec<PengecualianNaikKelas>,
+    /// Semester tujuan; wajib bila tidak ada rombel tujuan, misalnya saat hanya menempatkan siswa tinggal kelas
+    #[serde(default)]
+    pub semester_baru: Option<String>,
+    pub pengguna_id: SqlxUuid,
+}
+
+#[derive(Serialize)]
+pub struct KonflikNaikKelas {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub rombel_lama: String,
+    pub pesan: String,
+}
+
+#[derive(Serialize, Default)]
+pub struct RingkasanNaikKelas {
+    pub semester_baru: String,
+    pub berhasil: i32,
+    pub dilewati_keluar: i32,
+    pub dilewati_pengecualian: i32,
+    pub tidak_dipetakan: i32,
+    /// Siswa tinggal kelas yang ditempatkan di rombel setingkat (sudah termasuk dalam `berhasil`)
+    pub tinggal_kelas: i32,
+    pub konflik: Vec<KonflikNaikKelas>,
+    pub peringatan: Vec<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct AnggotaSemesterLama {
+    peserta_didik_id: SqlxUuid,
+    nama: String,
+    rombongan_belajar_id: SqlxUuid,
+    nama_rombel: String,
+    tingkat_pendidikan_id: i32,
+    jurusan_sp_id: Option<SqlxUuid>,
+    jenis_pendaftaran_id: Option<BigDecimal>,
+    sudah_keluar: bool,
+    rombel_semester_baru: Option<String>,
+}
+
+struct RombelNaikKelas {
+    nama: String,
+    semester_id: String,
+    tingkat_pendidikan_id: i32,
+    jurusan_sp_id: Option<SqlxUuid>,
+    kurikulum_id: Option<i32>,
+}
+
+async fn ambil_rombel_naik_kelas(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ids: &[SqlxUuid],
+) -> Result<HashMap<SqlxUuid, RombelNaikKelas>, String> {
+    let rows = sqlx::query_as::<_, (SqlxUuid, String, String, i32, Option<SqlxUuid>, Option<i32>)>(
+        "SELECT rombongan_belajar_id, nama, semester_id, tingkat_pendidikan_id::int, jurusan_sp_id, kurikulum_id::int
+         FROM rombongan_belajar WHERE rombongan_belajar_id = ANY($1) AND soft_delete = 0"
+    )
+    .bind(ids)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| format!("Gagal mengambil data rombel: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, nama, semester_id, tingkat_pendidikan_id, jurusan_sp_id, kurikulum_id)| {
+            (id, RombelNaikKelas { nama, semester_id, tingkat_pendidikan_id, jurusan_sp_id, kurikulum_id })
+        })
+        .collect())
+}
+
+/// Rombel semester baru untuk siswa tinggal kelas. Bila ada lebih dari satu rombel setingkat, hanya rombel
+/// dengan akhiran nama dan jurusan yang sama dengan rombel lama (misalnya "7B" tetap ke "7B") yang dipakai;
+/// selain itu operator harus memilih rombel tujuan lewat pengecualian.
+fn rombel_tinggal_kelas<'a>(
+    rombel: &'a [RombelPemetaan],
+    nama_rombel_lama: &str,
+    tingkat_pendidikan_id: i32,
+    jurusan_sp_id: Option<SqlxUuid>,
+) -> Result<&'a RombelPemetaan, String> {
+    let setingkat: Vec<&RombelPemetaan> = rombel
+        .iter()
+        .filter(|r| r.tingkat_pendidikan_id == tingkat_pendidikan_id)
+        .collect();
+    if let [satu] = setingkat.as_slice() {
+        return Ok(satu);
+    }
+    if setingkat.is_empty() {
+        return Err(format!("Belum ada rombel tingkat {} di semester baru", tingkat_pendidikan_id));
+    }
+
+    let (_, akhiran_lama) = urai_nama_rombel(nama_rombel_lama);
+    let cocok: Vec<&RombelPemetaan> = setingkat
+        .into_iter()
+        .filter(|r| urai_nama_rombel(&r.nama).1 == akhiran_lama && r.jurusan_sp_id == jurusan_sp_id)
+        .collect();
+    match cocok.as_slice() {
+        [satu] => Ok(satu),
+        [] => Err(format!("Tidak ada rombel tingkat {} dengan nama dan jurusan yang sama dengan {}; pilih rombel tujuan lewat pengecualian", tingkat_pendidikan_id, nama_rombel_lama)),
+        _ => Err(format!("Lebih dari satu rombel tingkat {} cocok dengan {}; pilih rombel tujuan lewat pengecualian", tingkat_pendidikan_id, nama_rombel_lama)),
+    }
+}
+
+/// Menaikkan siswa semester lama ke rombel semester baru sesuai pemetaan rombel dan pengecualian per siswa.
+/// Siswa yang sudah keluar dilewati; siswa yang sudah punya rombel di semester baru dicatat sebagai konflik.
+/// Siswa yang ditandai tinggal kelas (tanpa pengecualian) ditempatkan di rombel setingkat pada semester baru.
+#[tauri::command]
+pub async fn proses_naik_kelas(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    payload: ProsesNaikKelasPayload,
+) -> Result<RingkasanNaikKelas, String> {
+    emit_log(&app, &format!("CMD: proses_naik_kelas - Semester lama: {}, {} pemetaan rombel", payload.semester_lama, payload.pemetaan.len()));
+    state.pastikan_skema_dapomaster()?;
+
+    let aturan = aturan_sekolah(&state.pool).await?;
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+
+    let tinggal_kelas = tinggal_kelas_semester(&mut tx, &payload.semester_lama).await?;
+    if payload.pemetaan.is_empty() && payload.pengecualian.is_empty() && tinggal_kelas.is_empty() {
+        return Err("Pemetaan rombel belum diisi dan tidak ada siswa tinggal kelas.".to_string());
+    }
+
+    // Validasi rombel asal dan tujuan
+    let id_lama: Vec<SqlxUuid> = payload.pemetaan.iter().map(|p| p.rombongan_belajar_id_lama).collect();
+    let id_baru: Vec<SqlxUuid> = payload
+        .pemetaan
+        .iter()
+        .map(|p| p.rombongan_belajar_id_baru)
+        .chain(payload.pengecualian.iter().filter_map(|p| p.rombongan_belajar_id_baru))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let rombel_lama = ambil_rombel_naik_kelas(&mut tx, &id_lama).await?;
+    let mut rombel_baru = ambil_rombel_naik_kelas(&mut tx, &id_baru).await?;
+
+    let mut semester_tujuan: HashSet<String> = payload.semester_baru.iter().cloned().collect();
+    for id in &id_baru {
+        let rombel = rombel_baru.get(id).ok_or_else(|| format!("Rombel tujuan {} tidak ditemukan.", id))?;
+        semester_tujuan.insert(rombel.semester_id.clone());
+    }
+    if semester_tujuan.len() > 1 {
+        return Err("Semua rombel tujuan harus berada di semester tujuan yang sama.".to_string());
+    }
+    let semester_baru = semester_tujuan.into_iter().next().ok_or("Semester tujuan belum dipilih.")?;
+    if semester_baru <= payload.semester_lama {
+        return Err(format!("Semester tujuan {} harus setelah semester {}.", semester_baru, payload.semester_lama));
+    }
+
+    let mut pemetaan = HashMap::new();
+    for p in &payload.pemetaan {
+        let lama = rombel_lama.get(&p.rombongan_belajar_id_lama)
+            .filter(|r| r.semester_id == payload.semester_lama)
+            .ok_or_else(|| format!("Rombel asal {} tidak ditemukan di semester {}.", p.rombongan_belajar_id_lama, payload.semester_lama))?;
+        let baru = &rombel_baru[&p.rombongan_belajar_id_baru];
+        if aturan.adalah_tingkat_akhir(lama.tingkat_pendidikan_id, lama.jurusan_sp_id, lama.kurikulum_id) {
+            return Err(format!("Rombel {} adalah tingkat akhir; siswanya diproses lewat kelulusan, bukan naik kelas.", lama.nama));
+        }
+        if Some(baru.tingkat_pendidikan_id) != aturan.tingkat_berikutnya(lama.tingkat_pendidikan_id, lama.jurusan_sp_id, lama.kurikulum_id) {
+            return Err(format!(
+                "Rombel {} (tingkat {}) tidak dapat dipetakan ke {} (tingkat {}).",
+                lama.nama, lama.tingkat_pendidikan_id, baru.nama, baru.tingkat_pendidikan_id
+            ));
+        }
+        pemetaan.insert(p.rombongan_belajar_id_lama, p.rombongan_belajar_id_baru);
+    }
+    let pengecualian: HashMap<SqlxUuid, Option<SqlxUuid>> = payload
+        .pengecualian
+        .iter()
+        .map(|p| (p.peserta_didik_id, p.rombongan_belajar_id_baru))
+        .collect();
+
+    let rombel_setingkat = if tinggal_kelas.is_empty() {
+        Vec::new()
+    } else {
+        sqlx::query_as::<_, RombelPemetaan>(QUERY_ROMBEL_SEMESTER)
+            .bind(&semester_baru)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| format!("Gagal mengambil rombel semester baru: {}", e))?
+    };
+
+    // Siswa semester lama beserta status keluar dan rombel semester baru (jika sudah ada)
+    let siswa = sqlx::query_as::<_, AnggotaSemesterLama>(
+        "SELECT ar.peserta_didik_id, pd.nama, ar.rombongan_belajar_id, rb.nama AS nama_rombel,
+                rb.tingkat_pendidikan_id::int AS tingkat_pendidikan_id, rb.jurusan_sp_id, ar.jenis_pendaftaran_id,
+                EXISTS (SELECT 1 FROM registrasi_peserta_didik rpd WHERE rpd.peserta_didik_id = pd.peserta_didik_id
+                        AND rpd.soft_delete = 0 AND rpd.jenis_keluar_id IS NOT NULL) AS sudah_keluar,
+                (SELECT rb2.nama FROM anggota_rombel ar2
+                 JOIN rombongan_belajar rb2 ON rb2.rombongan_belajar_id = ar2.rombongan_belajar_id
+                 WHERE ar2.peserta_didik_id = pd.peserta_didik_id AND ar2.soft_delete = 0 AND rb2.soft_delete = 0
+                 AND rb2.semester_id = $2 LIMIT 1) AS rombel_semester_baru
+         FROM anggota_rombel ar
+         JOIN rombongan_belajar rb ON ar.rombongan_belajar_id = rb.rombongan_belajar_id
+         JOIN peserta_didik pd ON ar.peserta_didik_id = pd.peserta_didik_id
+         WHERE rb.semester_id = $1 AND ar.soft_delete = 0 AND rb.soft_delete = 0 AND pd.soft_delete = 0
+         ORDER BY rb.nama, pd.nama"
+    )
+    .bind(&payload.semester_lama)
+    .bind(&semester_baru)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal mengambil siswa semester lama: {}", e))?;
+
+    // Satu siswa bisa tercatat di lebih dari satu rombel (misalnya ekskul); pilih yang dipetakan
+    let mut per_siswa: HashMap<SqlxUuid, AnggotaSemesterLama> = HashMap::new();
+    let mut urutan = Vec::new();
+    for anggota in siswa {
+        let dipetakan = pemetaan.contains_key(&anggota.rombongan_belajar_id);
+        match per_siswa.get(&anggota.peserta_didik_id) {
+            Some(lama) if pemetaan.contains_key(&lama.rombongan_belajar_id) || !dipetakan => continue,
+            None => urutan.push(anggota.peserta_didik_id),
+            _ => {}
+        }
+        per_siswa.insert(anggota.peserta_didik_id, anggota);
+    }
+
+    let mut ringkasan = RingkasanNaikKelas { semester_baru: semester_baru.clone(), ..Default::default() };
+    let mut rombel_terisi = HashSet::new();
+
+    for peserta_didik_id in urutan {
+        let anggota = &per_siswa[&peserta_didik_id];
+        // Diperiksa sebelum mencari rombel tujuan, agar siswa tinggal kelas yang sudah keluar tidak dicatat sebagai konflik
+        if anggota.sudah_keluar {
+            ringkasan.dilewati_keluar += 1;
+            continue;
+        }
+
+        let tujuan = match pengecualian.get(&peserta_didik_id) {
+            Some(None) => {
+                ringkasan.dilewati_pengecualian += 1;
+                continue;
+            }
+            Some(Some(id)) => *id,
+            None if tinggal_kelas.contains_key(&peserta_didik_id) => match rombel_tinggal_kelas(
+                &rombel_setingkat,
+                &anggota.nama_rombel,
+                anggota.tingkat_pendidikan_id,
+                anggota.jurusan_sp_id,
+            ) {
+                Ok(rombel) => {
+                    rombel_baru.entry(rombel.rombongan_belajar_id).or_insert_with(|| RombelNaikKelas {
+                        nama: rombel.nama.clone(),
+                        semester_id: semester_baru.clone(),
+                        tingkat_pendidikan_id: rombel.tingkat_pendidikan_id,
+                        jurusan_sp_id: rombel.jurusan_sp_id,
+                        kurikulum_id: rombel.kurikulum_id,
+                    });
+                    rombel.rombongan_belajar_id
+                }
+                Err(alasan) => {
+                    ringkasan.konflik.push(KonflikNaikKelas {
+                        peserta_didik_id,
+                        nama: anggota.nama.clone(),
+                        rombel_lama: anggota.nama_rombel.clone(),
+                        pesan: format!("Tinggal kelas: {}", alasan),
+                    });
+                    continue;
+                }
+            },
+            None => match pemetaan.get(&anggota.rombongan_belajar_id) {
+                Some(id) => *id,
+                None => {
+                    ringkasan.tidak_dipetakan += 1;
+                    continue;
+                }
+            },
+        };
+        if let Some(rombel_ada) = &anggota.rombel_semester_baru {
+            ringkasan.konflik.push(KonflikNaikKelas {
+                peserta_didik_id,
+                nama: anggota.nama.clone(),
+                rombel_lama: anggota.nama_rombel.clone(),
+                pesan: format!("Sudah terdaftar di rombel {} pada semester {}", rombel_ada, semester_baru),
+            });
+            continue;
+        }
+
+        sqlx::query(
+            "INSERT INTO anggota_rombel (anggota_rombel_id, rombongan_belajar_id, peserta_didik_id, jenis_pendaftaran_id, create_date, last_update, soft_delete, updater_id)
+             VALUES ($1, $2, $3, $4, NOW(), NOW(), 0, $5)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(tujuan)
+        .bind(peserta_didik_id)
+        .bind(anggota.jenis_pendaftaran_id.clone().unwrap_or_else(|| BigDecimal::from(1)))
+        .bind(payload.pengguna_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Gagal menambahkan {} ke rombel baru: {}", anggota.nama, e))?;
+
+        rombel_terisi.insert(tujuan);
+        ringkasan.berhasil += 1;
+        if tinggal_kelas.contains_key(&peserta_didik_id) && !pengecualian.contains_key(&peserta_didik_id) {
+            ringkasan.tinggal_kelas += 1;
+        }
+    }
+
+    for rombel_id in rombel_terisi {
+        if let Err(pesan) = crate::commands::rombel::cek_kapasitas(&mut tx, rombel_id, &rombel_baru[&rombel_id].nama).await {
+            ringkasan.peringatan.push(pesan);
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    emit_log(&app, &format!(
+        "CMD: proses_naik_kelas - {} berhasil ({} tinggal kelas), {} konflik, {} keluar, {} tidak dipetakan",
+        ringkasan.berhasil, ringkasan.tinggal_kelas, ringkasan.konflik.len(), ringkasan.dilewati_keluar, ringkasan.tidak_dipetakan
+    ));
+    Ok(ringkasan)
+}
+
+const ANGKA_ROMAWI: [(&str, i32); 13] = [
+    ("XIII", 13), ("XII", 12), ("XI", 11), ("X", 10), ("IX", 9), ("VIII", 8), ("VII", 7),
+    ("VI", 6), ("V", 5), ("IV", 4), ("III", 3), ("II", 2), ("I", 1),
+];
+
+/// Memecah nama rombel menjadi angka kelas dan sisa nama, misalnya "4A" -> (4, "A"),
+/// "VII-B" -> (7, "B"), "X IPA 1" -> (10, "IPA 1")
+fn urai_nama_rombel(nama: &str) -> (Option<i32>, String) {
+    let nama = nama.to_uppercase();
+    let token: Vec<&str> = nama.split(|c: char| !c.is_alphanumeric()).filter(|t| !t.is_empty()).collect();
+    let mut kelas = None;
+    let mut sisa = Vec::new();
+
+    for t in token {
+        if kelas.is_none() {
+            let digit: String = t.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if !digit.is_empty() {
+                kelas = digit.parse().ok();
+                let ekor = &t[digit.len()..];
+                if !ekor.is_empty() {
+                    sisa.push(ekor.to_string());
+                }
+                continue;
+            }
+            // Angka romawi hanya diterima bila diikuti paling banyak satu huruf, agar "IPA" tidak terbaca "I"
+            if let Some((romawi, nilai)) = ANGKA_ROMAWI.iter().find(|(r, _)| t.starts_with(r) && t.len() - r.len() <= 1) {
+                kelas = Some(*nilai);
+                let ekor = &t[romawi.len()..];
+                if !ekor.is_empty() {
+                    sisa.push(ekor.to_string());
+                }
+                continue;
+            }
+            if t == "KELAS" || t == "KLS" {
+                continue;
+            }
+        }
+        sisa.push(t.to_string());
+    }
+
+    (kelas, sisa.join(" "))
+}
+
+const QUERY_ROMBEL_SEMESTER: &str = "SELECT rombongan_belajar_id, nama, tingkat_pendidikan_id::int AS tingkat_pendidikan_id, jurusan_sp_id,
+            kurikulum_id::int AS kurikulum_id
+     FROM rombongan_belajar WHERE semester_id = $1 AND soft_delete = 0 ORDER BY tingkat_pendidikan_id, nama";
+
+#[derive(sqlx::FromRow)]
+struct RombelPemetaan {
+    rombongan_belajar_id: SqlxUuid,
+    nama: String,
+    tingkat_pendidikan_id: i32,
+    jurusan_sp_id: Option<SqlxUuid>,
+    kurikulum_id: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct KandidatRombel {
+    pub rombongan_belajar_id: SqlxUuid,
+    pub nama: String,
+    pub keyakinan: f64,
+}
+
+#[derive(Serialize)]
+pub struct SaranPemetaanRombel {
+    pub rombongan_belajar_id_lama: SqlxUuid,
+    pub nama_lama: String,
+    pub tingkat_lama: i32,
+    pub rombongan_belajar_id_baru: Option<SqlxUuid>,
+    pub nama_baru: Option<String>,
+    pub keyakinan: f64,
+    pub alasan: Vec<String>,
+    pub alternatif: Vec<KandidatRombel>,
+}
+
+/// Skor kecocokan 0..1 antara rombel lama dan calon rombel baru, beserta alasannya
+fn nilai_kandidat(lama: &RombelPemetaan, baru: &RombelPemetaan) -> (f64, Vec<String>) {
+    let mut skor = 0.0;
+    let mut alasan = Vec::new();
+
+    if baru.tingkat_pendidikan_id == lama.tingkat_pendidikan_id + 1 {
+        skor += 0.4;
+        alasan.push(format!("Tingkat {} ke {}", lama.tingkat_pendidikan_id, baru.tingkat_pendidikan_id));
+    }
+
+    let (kelas_lama, sisa_lama) = urai_nama_rombel(&lama.nama);
+    let (kelas_baru, sisa_baru) = urai_nama_rombel(&baru.nama);
+    if sisa_lama == sisa_baru {
+        skor += 0.35;
+        if !sisa_lama.is_empty() {
+            alasan.push(format!("Nama paralel sama ({})", sisa_lama));
+        }
+    }
+    if let (Some(k_lama), Some(k_baru)) = (kelas_lama, kelas_baru) {
+        if k_baru == k_lama + 1 {
+            skor += 0.1;
+            alasan.push(format!("Angka kelas {} ke {}", k_lama, k_baru));
+        }
+    }
+
+    match (lama.jurusan_sp_id, baru.jurusan_sp_id) {
+        (Some(j_lama), Some(j_baru)) if j_lama == j_baru => {
+            skor += 0.15;
+            alasan.push("Jurusan sama".to_string());
+        }
+        (Some(_), Some(_)) => {
+            skor -= 0.3;
+            alasan.push("Jurusan berbeda".to_string());
+        }
+        (None, None) => skor += 0.15,
+        _ => {}
+    }
+
+    (f64::max(skor, 0.0).min(1.0), alasan)
+}
+
+/// Mengusulkan rombel tujuan semester baru untuk setiap rombel semester lama. Usulan hanya saran;
+/// operator tetap mengirim pemetaan final ke `proses_naik_kelas`.
+#[tauri::command]
+pub async fn sarankan_pemetaan_rombel(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    semester_lama: String,
+    semester_baru: String,
+) -> Result<Vec<SaranPemetaanRombel>, String> {
+    emit_log(&app, &format!("CMD: sarankan_pemetaan_rombel - {} ke {}", semester_lama, semester_baru));
+
+    let rombel_lama = sqlx::query_as::<_, RombelPemetaan>(QUERY_ROMBEL_SEMESTER)
+        .bind(&semester_lama)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| format!("Gagal mengambil rombel semester lama: {}", e))?;
+    let rombel_baru = sqlx::query_as::<_, RombelPemetaan>(QUERY_ROMBEL_SEMESTER)
+        .bind(&semester_baru)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| format!("Gagal mengambil rombel semester baru: {}", e))?;
+
+    let aturan = aturan_sekolah(&state.pool).await?;
+
+    let mut saran = Vec::new();
+    for lama in &rombel_lama {
+        let Some(tingkat_tujuan) = aturan.tingkat_berikutnya(lama.tingkat_pendidikan_id, lama.jurusan_sp_id, lama.kurikulum_id) else {
+            saran.push(SaranPemetaanRombel {
+                rombongan_belajar_id_lama: lama.rombongan_belajar_id,
+                nama_lama: lama.nama.clone(),
+                tingkat_lama: lama.tingkat_pendidikan_id,
+                rombongan_belajar_id_baru: None,
+                nama_baru: None,
+                keyakinan: 1.0,
+                alasan: vec![format!("Tingkat {} adalah tingkat akhir {}, siswa diproses lewat kelulusan", lama.tingkat_pendidikan_id, aturan.bentuk_pendidikan)],
+                alternatif: Vec::new(),
+            });
+            continue;
+        };
+        let mut kandidat: Vec<(&RombelPemetaan, f64, Vec<String>)> = rombel_baru
+            .iter()
+            .filter(|baru| baru.tingkat_pendidikan_id == tingkat_tujuan)
+            .map(|baru| {
+                let (skor, alasan) = nilai_kandidat(lama, baru);
+                (baru, skor, alasan)
+            })
+            .collect();
+        kandidat.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.nama.cmp(&b.0.nama)));
+
+        let mut kandidat = kandidat.into_iter();
+        let terbaik = kandidat.next();
+        let alternatif = kandidat
+            .take(3)
+            .map(|(r, skor, _)| KandidatRombel { rombongan_belajar_id: r.rombongan_belajar_id, nama: r.nama.clone(), keyakinan: skor })
+            .collect();
+
+        saran.push(match terbaik {
+            Some((baru, skor, alasan)) => SaranPemetaanRombel {
+                rombongan_belajar_id_lama: lama.rombongan_belajar_id,
+                nama_lama: lama.nama.clone(),
+                tingkat_lama: lama.tingkat_pendidikan_id,
+                rombongan_belajar_id_baru: Some(baru.rombongan_belajar_id),
+                nama_baru: Some(baru.nama.clone()),
+                keyakinan: skor,
+                alasan,
+                alternatif,
+            },
+            None => SaranPemetaanRombel {
+                rombongan_belajar_id_lama: lama.rombongan_belajar_id,
+                nama_lama: lama.nama.clone(),
+                tingkat_lama: lama.tingkat_pendidikan_id,
+                rombongan_belajar_id_baru: None,
+                nama_baru: None,
+                keyakinan: 0.0,
+                alasan: vec![format!("Tidak ada rombel tingkat {} di semester {}", tingkat_tujuan, semester_baru)],
+                alternatif,
+            },
+        });
+    }
+
+    emit_log(&app, &format!("CMD: sarankan_pemetaan_rombel - {} usulan pemetaan", saran.len()));
+    Ok(saran)
+}
+
+
+#[derive(Serialize)]
+pub struct SaranTinggalKelas {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub rombel_lama: Option<String>,
+    pub rombongan_belajar_id_baru: Option<SqlxUuid>,
+    pub nama_baru: Option<String>,
+    /// Alasan bila rombel tujuan tidak dapat ditentukan otomatis
+    pub pesan: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct RombelLamaTinggalKelas {
+    peserta_didik_id: SqlxUuid,
+    nama: String,
+    nama_rombel: Option<String>,
+    tingkat_pendidikan_id: Option<i32>,
+    jurusan_sp_id: Option<SqlxUuid>,
+}
+
+/// Pratinjau penempatan siswa tinggal kelas oleh `proses_naik_kelas`. Siswa tanpa rombel tujuan harus
+/// diberi pengecualian dengan rombel tujuan eksplisit.
+#[tauri::command]
+pub async fn sarankan_rombel_tinggal_kelas(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    semester_lama: String,
+    semester_baru: String,
+) -> Result<Vec<SaranTinggalKelas>, String> {
+    emit_log(&app, &format!("CMD: sarankan_rombel_tinggal_kelas - {} ke {}", semester_lama, semester_baru));
+    state.pastikan_skema_dapomaster()?;
+
+    let mut conn = state.pool.acquire().await.map_err(|e| e.to_string())?;
+    let tinggal_kelas = tinggal_kelas_semester(&mut conn, &semester_lama).await?;
+    if tinggal_kelas.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ids: Vec<SqlxUuid> = tinggal_kelas.keys().copied().collect();
+
+    let siswa = sqlx::query_as::<_, RombelLamaTinggalKelas>(
+        "SELECT pd.peserta_didik_id, pd.nama, rb.nama AS nama_rombel, rb.tingkat_pendidikan_id, rb.jurusan_sp_id
+         FROM peserta_didik pd
+         LEFT JOIN LATERAL (
+             SELECT rb.nama, rb.tingkat_pendidikan_id::int AS tingkat_pendidikan_id, rb.jurusan_sp_id FROM anggota_rombel ar
+             JOIN rombongan_belajar rb ON rb.rombongan_belajar_id = ar.rombongan_belajar_id
+             WHERE ar.peserta_didik_id = pd.peserta_didik_id AND ar.soft_delete = 0 AND rb.soft_delete = 0
+             AND rb.semester_id = $2
+             ORDER BY rb.tingkat_pendidikan_id DESC LIMIT 1
+         ) rb ON TRUE
+         WHERE pd.peserta_didik_id = ANY($1) AND pd.soft_delete = 0
+         ORDER BY rb.nama, pd.nama"
+    )
+    .bind(&ids)
+    .bind(&semester_lama)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Gagal mengambil siswa tinggal kelas: {}", e))?;
+
+    let rombel_baru = sqlx::query_as::<_, RombelPemetaan>(QUERY_ROMBEL_SEMESTER)
+        .bind(&semester_baru)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Gagal mengambil rombel semester baru: {}", e))?;
+
+    let saran: Vec<SaranTinggalKelas> = siswa
+        .into_iter()
+        .map(|s| {
+            let hasil = match (&s.nama_rombel, s.tingkat_pendidikan_id) {
+                (Some(nama_rombel), Some(tingkat)) => rombel_tinggal_kelas(&rombel_baru, nama_rombel, tingkat, s.jurusan_sp_id),
+                _ => Err(format!("Tidak terdaftar di rombel semester {}", semester_lama)),
+            };
+            let (rombel, pesan) = match hasil {
+                Ok(r) => (Some(r), None),
+                Err(pesan) => (None, Some(pesan)),
+            };
+            SaranTinggalKelas {
+                peserta_didik_id: s.peserta_didik_id,
+                nama: s.nama,
+                rombel_lama: s.nama_rombel,
+                rombongan_belajar_id_baru: rombel.map(|r| r.rombongan_belajar_id),
+                nama_baru: rombel.map(|r| r.nama.clone()),
+                pesan,
+            }
+        })
+        .collect();
+
+    emit_log(&app, &format!("CMD: sarankan_rombel_tinggal_kelas - {} siswa tinggal kelas", saran.len()));
+    Ok(saran)
+}
@@ -0,0 +1,397 @@
+//! Nilai rapor dari skema `nilai` dan leger per rombel. Nilai dibaca dari `nilai.nilai_rapor` melalui
+//! `nilai.matev_rapor` (satu mata evaluasi rapor per `pembelajaran`); aplikasi ini tidak mengubah nilai.
+//!
+//! Kolom yang dipakai: `matev_rapor.id_evaluasi`, `matev_rapor.pembelajaran_id`, `nilai_rapor.anggota_rombel_id`,
+//! `nilai_kognitif_angka`/`nilai_kognitif_huruf` (pengetahuan) dan `nilai_psim_angka`/`nilai_psim_huruf` (keterampilan).
+
+use crate::app_state::DbPool;
+use crate::commands::export::ExportResult;
+use tauri::{AppHandle, State};
+use serde::Serialize;
+use chrono::{DateTime, Utc};
+use sqlx::types::Uuid as SqlxUuid;
+use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, Workbook};
+use std::collections::HashMap;
+
+const QUERY_NILAI_RAPOR: &str =
+    "SELECT ar.anggota_rombel_id, pd.peserta_didik_id, pd.nama, pd.nisn,
+            rb.rombongan_belajar_id, rb.nama AS nama_rombel, rb.semester_id,
+            pb.pembelajaran_id, pb.mata_pelajaran_id, COALESCE(pb.nama_mata_pelajaran, mp.nama) AS nama_mata_pelajaran,
+            nr.nilai_kognitif_angka::float8 AS nilai_pengetahuan, nr.nilai_kognitif_huruf AS predikat_pengetahuan,
+            nr.nilai_psim_angka::float8 AS nilai_keterampilan, nr.nilai_psim_huruf AS predikat_keterampilan
+     FROM anggota_rombel ar
+     JOIN rombongan_belajar rb ON rb.rombongan_belajar_id = ar.rombongan_belajar_id AND rb.soft_delete = 0
+     JOIN peserta_didik pd ON pd.peserta_didik_id = ar.peserta_didik_id AND pd.soft_delete = 0
+     JOIN pembelajaran pb ON pb.rombongan_belajar_id = rb.rombongan_belajar_id AND pb.soft_delete = 0
+     JOIN ref.mata_pelajaran mp ON mp.mata_pelajaran_id = pb.mata_pelajaran_id
+     LEFT JOIN nilai.matev_rapor mr ON mr.pembelajaran_id = pb.pembelajaran_id AND mr.soft_delete = 0
+     LEFT JOIN nilai.nilai_rapor nr ON nr.id_evaluasi = mr.id_evaluasi AND nr.anggota_rombel_id = ar.anggota_rombel_id AND nr.soft_delete = 0
+     WHERE ar.soft_delete = 0 AND rb.semester_id = $1
+     AND ($2::uuid IS NULL OR rb.rombongan_belajar_id = $2)
+     AND ($3::int IS NULL OR pb.mata_pelajaran_id = $3)
+     AND ($4::uuid IS NULL OR pd.peserta_didik_id = $4)
+     ORDER BY rb.nama, pd.nama, pd.peserta_didik_id, pb.mata_pelajaran_id";
+
+const QUERY_MAPEL_ROMBEL: &str =
+    "SELECT pb.pembelajaran_id, pb.mata_pelajaran_id, COALESCE(pb.nama_mata_pelajaran, mp.nama) AS nama_mata_pelajaran
+     FROM pembelajaran pb
+     JOIN ref.mata_pelajaran mp ON mp.mata_pelajaran_id = pb.mata_pelajaran_id
+     WHERE pb.rombongan_belajar_id = $1 AND pb.soft_delete = 0
+     ORDER BY pb.mata_pelajaran_id";
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct NilaiRapor {
+    pub anggota_rombel_id: SqlxUuid,
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nisn: Option<String>,
+    pub rombongan_belajar_id: SqlxUuid,
+    pub nama_rombel: String,
+    pub semester_id: String,
+    pub pembelajaran_id: SqlxUuid,
+    pub mata_pelajaran_id: i32,
+    pub nama_mata_pelajaran: String,
+    pub nilai_pengetahuan: Option<f64>,
+    pub predikat_pengetahuan: Option<String>,
+    pub nilai_keterampilan: Option<f64>,
+    pub predikat_keterampilan: Option<String>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct MataPelajaranLeger {
+    pub pembelajaran_id: SqlxUuid,
+    pub mata_pelajaran_id: i32,
+    pub nama_mata_pelajaran: String,
+}
+
+#[derive(Serialize)]
+pub struct BarisLeger {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nisn: Option<String>,
+    /// Nilai per mata pelajaran, urutannya sama dengan `LegerNilai::mata_pelajaran`
+    pub nilai: Vec<Option<f64>>,
+    pub jumlah: f64,
+    pub rata_rata: Option<f64>,
+    pub peringkat: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct LegerNilai {
+    pub rombongan_belajar_id: SqlxUuid,
+    pub nama_rombel: String,
+    pub semester_id: String,
+    pub wali_kelas: Option<String>,
+    pub aspek: String,
+    pub mata_pelajaran: Vec<MataPelajaranLeger>,
+    pub siswa: Vec<BarisLeger>,
+    pub rata_rata_mapel: Vec<Option<f64>>,
+}
+
+fn bulatkan(nilai: f64) -> f64 {
+    (nilai * 100.0).round() / 100.0
+}
+
+fn rata_rata(nilai: impl Iterator<Item = f64>) -> Option<f64> {
+    let (jumlah, banyak) = nilai.fold((0.0, 0), |(j, n), v| (j + v, n + 1));
+    (banyak > 0).then(|| bulatkan(jumlah / banyak as f64))
+}
+
+/// Nilai rapor satu siswa di satu rombel, dipakai untuk ringkasan nilai pada surat
+pub(crate) async fn nilai_rapor_siswa(
+    pool: &sqlx::PgPool,
+    peserta_didik_id: SqlxUuid,
+    rombongan_belajar_id: SqlxUuid,
+    semester_id: &str,
+) -> Result<Vec<NilaiRapor>, String> {
+    sqlx::query_as::<_, NilaiRapor>(QUERY_NILAI_RAPOR)
+        .bind(semester_id)
+        .bind(rombongan_belajar_id)
+        .bind(None::<i32>)
+        .bind(peserta_didik_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Gagal mengambil nilai rapor: {}", e))
+}
+
+async fn semester_atau_aktif(state: &State<'_, DbPool>, semester_id: Option<String>) -> Result<String, String> {
+    match semester_id {
+        Some(s) => Ok(s),
+        None => sqlx::query_scalar("SELECT semester_id FROM ref.semester WHERE periode_aktif = 1 LIMIT 1")
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(|e| format!("Gagal mengambil semester aktif: {}", e))?
+            .ok_or_else(|| "Semester aktif tidak ditemukan".to_string()),
+    }
+}
+
+/// Menyusun leger satu rombel. Peringkat memakai rata-rata; rata-rata sama mendapat peringkat sama.
+async fn susun_leger(state: &State<'_, DbPool>, rombongan_belajar_id: SqlxUuid, aspek: &str) -> Result<LegerNilai, String> {
+    let (nama_rombel, semester_id, wali_kelas) = sqlx::query_as::<_, (String, String, Option<String>)>(
+        "SELECT rb.nama, rb.semester_id, ptk.nama
+         FROM rombongan_belajar rb
+         LEFT JOIN ptk ON ptk.ptk_id = rb.ptk_id
+         WHERE rb.rombongan_belajar_id = $1 AND rb.soft_delete = 0"
+    )
+    .bind(rombongan_belajar_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil data rombel: {}", e))?
+    .ok_or("Rombel tidak ditemukan")?;
+
+    let mata_pelajaran = sqlx::query_as::<_, MataPelajaranLeger>(QUERY_MAPEL_ROMBEL)
+        .bind(rombongan_belajar_id)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| format!("Gagal mengambil pembelajaran rombel: {}", e))?;
+
+    let rows = sqlx::query_as::<_, NilaiRapor>(QUERY_NILAI_RAPOR)
+        .bind(&semester_id)
+        .bind(rombongan_belajar_id)
+        .bind(None::<i32>)
+        .bind(None::<SqlxUuid>)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| format!("Gagal mengambil nilai rapor: {}", e))?;
+
+    let kolom: HashMap<SqlxUuid, usize> = mata_pelajaran.iter().enumerate().map(|(i, m)| (m.pembelajaran_id, i)).collect();
+    let mut siswa: Vec<BarisLeger> = Vec::new();
+    for r in rows {
+        if siswa.last().is_none_or(|s| s.peserta_didik_id != r.peserta_didik_id) {
+            siswa.push(BarisLeger {
+                peserta_didik_id: r.peserta_didik_id,
+                nama: r.nama.clone(),
+                nisn: r.nisn.clone(),
+                nilai: vec![None; mata_pelajaran.len()],
+                jumlah: 0.0,
+                rata_rata: None,
+                peringkat: None,
+            });
+        }
+        let nilai = if aspek == "keterampilan" { r.nilai_keterampilan } else { r.nilai_pengetahuan };
+        if let (Some(s), Some(&col)) = (siswa.last_mut(), kolom.get(&r.pembelajaran_id)) {
+            s.nilai[col] = nilai;
+        }
+    }
+
+    for s in &mut siswa {
+        s.jumlah = bulatkan(s.nilai.iter().flatten().sum());
+        s.rata_rata = rata_rata(s.nilai.iter().flatten().copied());
+    }
+
+    let mut urutan: Vec<(usize, f64)> = siswa.iter().enumerate().filter_map(|(i, s)| s.rata_rata.map(|r| (i, r))).collect();
+    urutan.sort_by(|a, b| b.1.total_cmp(&a.1));
+    for (posisi, &(idx, rata)) in urutan.iter().enumerate() {
+        let peringkat = match posisi.checked_sub(1).map(|p| urutan[p]) {
+            Some((sebelumnya, rata_sebelumnya)) if rata_sebelumnya == rata => siswa[sebelumnya].peringkat,
+            _ => Some(posisi + 1),
+        };
+        siswa[idx].peringkat = peringkat;
+    }
+
+    let rata_rata_mapel = (0..mata_pelajaran.len())
+        .map(|col| rata_rata(siswa.iter().filter_map(|s| s.nilai[col])))
+        .collect();
+
+    Ok(LegerNilai {
+        rombongan_belajar_id,
+        nama_rombel,
+        semester_id,
+        wali_kelas,
+        aspek: aspek.to_string(),
+        mata_pelajaran,
+        siswa,
+        rata_rata_mapel,
+    })
+}
+
+fn aspek_valid(aspek: Option<String>) -> Result<String, String> {
+    match aspek.as_deref().unwrap_or("pengetahuan") {
+        a @ ("pengetahuan" | "keterampilan") => Ok(a.to_string()),
+        a => Err(format!("Aspek nilai '{}' tidak dikenal, gunakan pengetahuan atau keterampilan", a)),
+    }
+}
+
+/// Nilai rapor per siswa dan mata pelajaran (bawaan: semester aktif), bisa disaring per rombel dan mata pelajaran
+#[tauri::command]
+pub async fn get_nilai_rapor(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    semester_id: Option<String>,
+    rombongan_belajar_id: Option<SqlxUuid>,
+    mata_pelajaran_id: Option<i32>,
+) -> Result<Vec<NilaiRapor>, String> {
+    crate::emit_log(&app, &format!("CMD: get_nilai_rapor - semester: {:?}, rombel: {:?}, mapel: {:?}", semester_id, rombongan_belajar_id, mata_pelajaran_id));
+
+    let semester_id = semester_atau_aktif(&state, semester_id).await?;
+    let data = sqlx::query_as::<_, NilaiRapor>(QUERY_NILAI_RAPOR)
+        .bind(&semester_id)
+        .bind(rombongan_belajar_id)
+        .bind(mata_pelajaran_id)
+        .bind(None::<SqlxUuid>)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| format!("Gagal mengambil nilai rapor: {}", e))?;
+
+    crate::emit_log(&app, &format!("CMD: get_nilai_rapor - {} baris nilai", data.len()));
+    Ok(data)
+}
+
+/// Mata pelajaran yang diajarkan di satu rombel, untuk pilihan filter nilai
+#[tauri::command]
+pub async fn get_mata_pelajaran_rombel(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    rombongan_belajar_id: SqlxUuid,
+) -> Result<Vec<MataPelajaranLeger>, String> {
+    crate::emit_log(&app, &format!("CMD: get_mata_pelajaran_rombel - rombel: {}", rombongan_belajar_id));
+
+    sqlx::query_as::<_, MataPelajaranLeger>(QUERY_MAPEL_ROMBEL)
+        .bind(rombongan_belajar_id)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| format!("Gagal mengambil pembelajaran rombel: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_leger_nilai(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    rombongan_belajar_id: SqlxUuid,
+    aspek: Option<String>,
+) -> Result<LegerNilai, String> {
+    crate::emit_log(&app, &format!("CMD: get_leger_nilai - rombel: {}, aspek: {:?}", rombongan_belajar_id, aspek));
+    let aspek = aspek_valid(aspek)?;
+    susun_leger(&state, rombongan_belajar_id, &aspek).await
+}
+
+/// Ekspor leger: satu baris per siswa, satu kolom per mata pelajaran, ditambah jumlah, rata-rata dan peringkat
+#[tauri::command]
+pub async fn export_leger_nilai(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    rombongan_belajar_id: SqlxUuid,
+    aspek: Option<String>,
+) -> Result<ExportResult, String> {
+    crate::emit_log(&app, &format!("CMD: export_leger_nilai - rombel: {}, aspek: {:?}", rombongan_belajar_id, aspek));
+
+    let aspek = aspek_valid(aspek)?;
+    let leger = susun_leger(&state, rombongan_belajar_id, &aspek).await?;
+    if leger.siswa.is_empty() {
+        return Err("Rombel tidak memiliki anggota atau pembelajaran untuk dibuat leger.".to_string());
+    }
+
+    // Buat nama file dengan timestamp
+    let now: DateTime<Utc> = Utc::now();
+    let timestamp = now.format("%Y%m%d_%H%M%S");
+    let nama_rombel_file: String = leger.nama_rombel.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+    let filename = format!("leger_{}_{}_{}.xlsx", nama_rombel_file, leger.semester_id, timestamp);
+
+    let desktop_path = dirs::desktop_dir()
+        .ok_or("Tidak dapat menemukan folder Desktop")?
+        .join(&filename);
+
+    let file_path = desktop_path.to_string_lossy().to_string();
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Leger")
+        .map_err(|e| format!("Gagal mengatur nama worksheet: {}", e))?;
+
+    let title_format = Format::new().set_bold();
+    let header_format = Format::new()
+        .set_bold()
+        .set_align(FormatAlign::Center)
+        .set_border(FormatBorder::Thin)
+        .set_text_wrap()
+        .set_background_color(rust_xlsxwriter::Color::Gray);
+    let data_format = Format::new()
+        .set_border(FormatBorder::Thin)
+        .set_align(FormatAlign::Left);
+    let nilai_format = Format::new()
+        .set_border(FormatBorder::Thin)
+        .set_align(FormatAlign::Center)
+        .set_num_format("0.##");
+    let ringkasan_format = Format::new()
+        .set_bold()
+        .set_border(FormatBorder::Thin)
+        .set_align(FormatAlign::Center)
+        .set_num_format("0.00");
+
+    let judul = format!("Leger Nilai {} Rombel {} Semester {}", if aspek == "keterampilan" { "Keterampilan" } else { "Pengetahuan" }, leger.nama_rombel, leger.semester_id);
+    worksheet.write_string_with_format(0, 0, judul, &title_format)
+        .map_err(|e| format!("Gagal menulis judul: {}", e))?;
+
+    let mut headers: Vec<&str> = vec!["No", "NISN", "Nama"];
+    headers.extend(leger.mata_pelajaran.iter().map(|m| m.nama_mata_pelajaran.as_str()));
+    headers.extend(["Jumlah", "Rata-rata", "Peringkat"]);
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(2, col as u16, *header, &header_format)
+            .map_err(|e| format!("Gagal menulis header: {}", e))?;
+    }
+
+    let kolom_jumlah = (3 + leger.mata_pelajaran.len()) as u16;
+    for (idx, s) in leger.siswa.iter().enumerate() {
+        let row_num = (idx + 3) as u32;
+        let err = |e: rust_xlsxwriter::XlsxError| format!("Gagal menulis baris {}: {}", idx + 1, e);
+        worksheet.write_number_with_format(row_num, 0, (idx + 1) as f64, &data_format).map_err(err)?;
+        worksheet.write_string_with_format(row_num, 1, s.nisn.as_deref().unwrap_or("-"), &data_format).map_err(err)?;
+        worksheet.write_string_with_format(row_num, 2, &s.nama, &data_format).map_err(err)?;
+        for (i, nilai) in s.nilai.iter().enumerate() {
+            let col = (3 + i) as u16;
+            match nilai {
+                Some(n) => worksheet.write_number_with_format(row_num, col, *n, &nilai_format),
+                None => worksheet.write_string_with_format(row_num, col, "-", &nilai_format),
+            }
+            .map_err(err)?;
+        }
+        worksheet.write_number_with_format(row_num, kolom_jumlah, s.jumlah, &nilai_format).map_err(err)?;
+        match s.rata_rata {
+            Some(r) => worksheet.write_number_with_format(row_num, kolom_jumlah + 1, r, &ringkasan_format),
+            None => worksheet.write_string_with_format(row_num, kolom_jumlah + 1, "-", &ringkasan_format),
+        }
+        .map_err(err)?;
+        match s.peringkat {
+            Some(p) => worksheet.write_number_with_format(row_num, kolom_jumlah + 2, p as f64, &nilai_format),
+            None => worksheet.write_string_with_format(row_num, kolom_jumlah + 2, "-", &nilai_format),
+        }
+        .map_err(err)?;
+    }
+
+    let baris_rata = (leger.siswa.len() + 3) as u32;
+    worksheet.write_string_with_format(baris_rata, 2, "Rata-rata Kelas", &header_format)
+        .map_err(|e| format!("Gagal menulis rata-rata kelas: {}", e))?;
+    for (i, rata) in leger.rata_rata_mapel.iter().enumerate() {
+        let col = (3 + i) as u16;
+        match rata {
+            Some(r) => worksheet.write_number_with_format(baris_rata, col, *r, &ringkasan_format),
+            None => worksheet.write_string_with_format(baris_rata, col, "-", &ringkasan_format),
+        }
+        .map_err(|e| format!("Gagal menulis rata-rata kelas: {}", e))?;
+    }
+
+    let baris_ttd = baris_rata + 3;
+    worksheet.write_string(baris_ttd, 2, "Mengetahui, Kepala Sekolah")
+        .map_err(|e| format!("Gagal menulis kolom tanda tangan: {}", e))?;
+    worksheet.write_string(baris_ttd + 4, 2, "(................................)")
+        .map_err(|e| format!("Gagal menulis kolom tanda tangan: {}", e))?;
+    worksheet.write_string(baris_ttd, kolom_jumlah, "Wali Kelas")
+        .map_err(|e| format!("Gagal menulis kolom tanda tangan: {}", e))?;
+    worksheet.write_string(baris_ttd + 4, kolom_jumlah, format!("({})", leger.wali_kelas.as_deref().unwrap_or("................................")))
+        .map_err(|e| format!("Gagal menulis kolom tanda tangan: {}", e))?;
+
+    worksheet.set_freeze_panes(3, 3)
+        .map_err(|e| format!("Gagal mengatur freeze panes: {}", e))?;
+    worksheet.autofit();
+
+    workbook.save(&file_path)
+        .map_err(|e| format!("Gagal menyimpan workbook: {}", e))?;
+
+    crate::emit_log(&app, &format!("CMD: export_leger_nilai - Exported {} siswa to {}", leger.siswa.len(), file_path));
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Berhasil mengekspor leger {} siswa ke file Excel: {}", leger.siswa.len(), filename),
+        file_path: Some(file_path),
+    })
+}
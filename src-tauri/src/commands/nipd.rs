@@ -0,0 +1,270 @@
+//! Generator NIPD (nomor induk peserta didik) berdasarkan pola yang dapat diatur.
+//!
+//! Placeholder yang didukung:
+//! - `{tahun_masuk}`: tahun masuk 4 digit, misalnya 2025
+//! - `{tahun}`: tahun masuk 2 digit, misalnya 25
+//! - `{jenjang}`: bentuk_pendidikan_id sekolah, misalnya 5 untuk SD
+//! - `{seq}` atau `{seq:N}`: nomor urut, diisi nol di depan sampai N digit (bawaan 3)
+//!
+//! Setiap pola wajib memuat tepat satu `{seq}`.
+
+use crate::app_state::DbPool;
+use crate::emit_log;
+use tauri::{AppHandle, State};
+use serde::Serialize;
+use chrono::{Datelike, NaiveDate};
+use sqlx::PgConnection;
+use sqlx::types::Uuid as SqlxUuid;
+use std::collections::{HashMap, HashSet};
+
+enum TokenPola {
+    Teks(String),
+    TahunMasuk,
+    Tahun,
+    Jenjang,
+    Seq(usize),
+}
+
+#[derive(Serialize)]
+pub struct HasilNipd {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nipd: String,
+}
+
+fn parse_pola(pola: &str) -> Result<Vec<TokenPola>, String> {
+    let mut tokens = Vec::new();
+    let mut teks = String::new();
+    let mut chars = pola.chars();
+    while let Some(c) = chars.next() {
+        if c != '{' {
+            teks.push(c);
+            continue;
+        }
+        let nama: String = chars.by_ref().take_while(|&c| c != '}').collect();
+        if !teks.is_empty() {
+            tokens.push(TokenPola::Teks(std::mem::take(&mut teks)));
+        }
+        let token = match nama.as_str() {
+            "tahun_masuk" => TokenPola::TahunMasuk,
+            "tahun" => TokenPola::Tahun,
+            "jenjang" => TokenPola::Jenjang,
+            "seq" => TokenPola::Seq(3),
+            lain => match lain.strip_prefix("seq:").map(str::parse::<usize>) {
+                Some(Ok(lebar)) if (1..=10).contains(&lebar) => TokenPola::Seq(lebar),
+                _ => return Err(format!("Placeholder '{{{}}}' tidak dikenal pada pola NIPD", nama)),
+            },
+        };
+        tokens.push(token);
+    }
+    if !teks.is_empty() {
+        tokens.push(TokenPola::Teks(teks));
+    }
+    let jumlah_seq = tokens.iter().filter(|t| matches!(t, TokenPola::Seq(_))).count();
+    if jumlah_seq != 1 {
+        return Err("Pola NIPD harus memuat tepat satu {seq}".to_string());
+    }
+    Ok(tokens)
+}
+
+pub fn validasi_pola_nipd(pola: &str) -> Result<(), String> {
+    parse_pola(pola).map(|_| ())
+}
+
+/// Penghasil NIPD untuk satu sekolah dan satu tahun masuk. Nomor urut berikutnya dihitung dari
+/// NIPD yang sudah ada dengan awalan dan akhiran yang sama.
+pub(crate) struct GeneratorNipd {
+    awalan: String,
+    lebar: usize,
+    akhiran: String,
+    terpakai: HashSet<String>,
+    berikutnya: u64,
+}
+
+impl GeneratorNipd {
+    pub(crate) async fn baru(conn: &mut PgConnection, sekolah_id: SqlxUuid, tahun_masuk: i32) -> Result<Self, String> {
+        let pola = crate::commands::pengaturan::muat_pengaturan().pola_nipd;
+        let tokens = parse_pola(&pola)?;
+
+        let jenjang: Option<String> = sqlx::query_scalar("SELECT bentuk_pendidikan_id::text FROM sekolah WHERE sekolah_id = $1")
+            .bind(sekolah_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| format!("Gagal mengambil data sekolah: {}", e))?;
+
+        let mut awalan = String::new();
+        let mut akhiran = String::new();
+        let mut lebar = 0;
+        for token in tokens {
+            let bagian = match token {
+                TokenPola::Seq(l) => {
+                    lebar = l;
+                    continue;
+                }
+                TokenPola::Teks(t) => t,
+                TokenPola::TahunMasuk => format!("{:04}", tahun_masuk),
+                TokenPola::Tahun => format!("{:02}", tahun_masuk.rem_euclid(100)),
+                TokenPola::Jenjang => jenjang.clone().ok_or("Sekolah tidak ditemukan untuk placeholder {jenjang}")?,
+            };
+            if lebar == 0 { awalan.push_str(&bagian) } else { akhiran.push_str(&bagian) }
+        }
+
+        // Keunikan dicek per sekolah, termasuk data di tempat sampah agar tidak bentrok saat dipulihkan
+        let terpakai: HashSet<String> = sqlx::query_scalar::<_, String>(
+            "SELECT TRIM(nipd) FROM registrasi_peserta_didik WHERE sekolah_id = $1 AND nipd IS NOT NULL AND TRIM(nipd) <> ''"
+        )
+        .bind(sekolah_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Gagal mengambil NIPD yang sudah ada: {}", e))?
+        .into_iter()
+        .collect();
+
+        let seq_terakhir = terpakai
+            .iter()
+            .filter_map(|nipd| nipd.strip_prefix(awalan.as_str())?.strip_suffix(akhiran.as_str()).map(str::to_string))
+            .filter(|tengah| !tengah.is_empty() && tengah.chars().all(|c| c.is_ascii_digit()))
+            .filter_map(|tengah| tengah.parse::<u64>().ok())
+            .max()
+            .unwrap_or(0);
+
+        Ok(Self { awalan, lebar, akhiran, terpakai, berikutnya: seq_terakhir + 1 })
+    }
+
+    pub(crate) fn ambil(&mut self) -> String {
+        loop {
+            let nipd = format!("{}{:0lebar$}{}", self.awalan, self.berikutnya, self.akhiran, lebar = self.lebar);
+            self.berikutnya += 1;
+            if self.terpakai.insert(nipd.clone()) {
+                return nipd;
+            }
+        }
+    }
+}
+
+/// Menolak NIPD isian operator yang sudah dipakai registrasi aktif lain di sekolah yang sama
+pub(crate) async fn pastikan_nipd_unik(conn: &mut PgConnection, nipd: &str, sekolah_id: SqlxUuid, registrasi_id: SqlxUuid) -> Result<(), String> {
+    let pemilik: Option<String> = sqlx::query_scalar(
+        "SELECT pd.nama FROM registrasi_peserta_didik rpd
+         JOIN peserta_didik pd ON pd.peserta_didik_id = rpd.peserta_didik_id
+         WHERE rpd.sekolah_id = $1 AND TRIM(rpd.nipd) = $2 AND rpd.soft_delete = 0 AND rpd.registrasi_id <> $3
+         LIMIT 1"
+    )
+    .bind(sekolah_id)
+    .bind(nipd)
+    .bind(registrasi_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Gagal memeriksa NIPD: {}", e))?;
+    match pemilik {
+        Some(nama) => Err(format!("NIPD {} sudah dipakai oleh {}", nipd, nama)),
+        None => Ok(()),
+    }
+}
+
+/// Mengisi NIPD otomatis bila operator membiarkannya kosong; NIPD isian operator harus unik
+pub(crate) async fn nipd_atau_otomatis(conn: &mut PgConnection, nipd: Option<&str>, sekolah_id: SqlxUuid, registrasi_id: SqlxUuid, tanggal_masuk: NaiveDate) -> Result<String, String> {
+    match nipd.map(str::trim) {
+        Some(n) if !n.is_empty() => {
+            pastikan_nipd_unik(conn, n, sekolah_id, registrasi_id).await?;
+            Ok(n.to_string())
+        }
+        _ => Ok(GeneratorNipd::baru(conn, sekolah_id, tanggal_masuk.year()).await?.ambil()),
+    }
+}
+
+/// Penomoran NIPD untuk banyak siswa dalam satu transaksi, misalnya import PPDB. Generator dibuat sekali
+/// per tahun masuk sehingga NIPD yang sudah ada tidak dibaca ulang untuk setiap baris.
+pub(crate) struct PenomoranNipd {
+    sekolah_id: SqlxUuid,
+    generator: HashMap<i32, GeneratorNipd>,
+}
+
+impl PenomoranNipd {
+    pub(crate) fn baru(sekolah_id: SqlxUuid) -> Self {
+        Self { sekolah_id, generator: HashMap::new() }
+    }
+
+    pub(crate) async fn nipd_atau_otomatis(&mut self, conn: &mut PgConnection, nipd: Option<&str>, registrasi_id: SqlxUuid, tanggal_masuk: NaiveDate) -> Result<String, String> {
+        if let Some(n) = nipd.map(str::trim).filter(|n| !n.is_empty()) {
+            pastikan_nipd_unik(conn, n, self.sekolah_id, registrasi_id).await?;
+            // NIPD isian tidak boleh diberikan lagi oleh generator yang sudah dibuat
+            for generator in self.generator.values_mut() {
+                generator.terpakai.insert(n.to_string());
+            }
+            return Ok(n.to_string());
+        }
+        let tahun = tanggal_masuk.year();
+        if !self.generator.contains_key(&tahun) {
+            let generator = GeneratorNipd::baru(conn, self.sekolah_id, tahun).await?;
+            self.generator.insert(tahun, generator);
+        }
+        Ok(self.generator.get_mut(&tahun).map(GeneratorNipd::ambil).unwrap_or_default())
+    }
+}
+
+/// Menampilkan NIPD berikutnya tanpa menyimpannya
+#[tauri::command]
+pub async fn preview_nipd(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    sekolah_id: SqlxUuid,
+    tanggal_masuk_sekolah: String,
+) -> Result<String, String> {
+    emit_log(&app, &format!("CMD: preview_nipd - sekolah: {}, tanggal masuk: {}", sekolah_id, tanggal_masuk_sekolah));
+    let tanggal_masuk = NaiveDate::parse_from_str(&tanggal_masuk_sekolah, "%Y-%m-%d")
+        .map_err(|e| format!("Format tanggal masuk sekolah salah: {}", e))?;
+    let mut conn = state.pool.acquire().await.map_err(|e| e.to_string())?;
+    let nipd = GeneratorNipd::baru(&mut conn, sekolah_id, tanggal_masuk.year()).await?.ambil();
+    Ok(nipd)
+}
+
+/// Memberi NIPD otomatis kepada semua siswa satu angkatan yang NIPD-nya masih kosong
+#[tauri::command]
+pub async fn generate_nipd_massal(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    sekolah_id: SqlxUuid,
+    tahun_masuk: i32,
+    pengguna_id: SqlxUuid,
+) -> Result<Vec<HasilNipd>, String> {
+    emit_log(&app, &format!("CMD: generate_nipd_massal - sekolah: {}, tahun masuk: {}", sekolah_id, tahun_masuk));
+
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+
+    let siswa = sqlx::query_as::<_, (SqlxUuid, SqlxUuid, String)>(
+        "SELECT rpd.registrasi_id, pd.peserta_didik_id, pd.nama
+         FROM registrasi_peserta_didik rpd
+         JOIN peserta_didik pd ON pd.peserta_didik_id = rpd.peserta_didik_id
+         WHERE rpd.sekolah_id = $1 AND rpd.soft_delete = 0 AND pd.soft_delete = 0
+         AND EXTRACT(YEAR FROM rpd.tanggal_masuk_sekolah) = $2
+         AND (rpd.nipd IS NULL OR TRIM(rpd.nipd) = '')
+         ORDER BY pd.nama
+         FOR UPDATE OF rpd"
+    )
+    .bind(sekolah_id)
+    .bind(tahun_masuk)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal mengambil siswa tanpa NIPD: {}", e))?;
+
+    let mut generator = GeneratorNipd::baru(&mut tx, sekolah_id, tahun_masuk).await?;
+    let mut hasil = Vec::new();
+
+    for (registrasi_id, peserta_didik_id, nama) in siswa {
+        let nipd = generator.ambil();
+        sqlx::query("UPDATE registrasi_peserta_didik SET nipd = $1, last_update = NOW(), updater_id = $2 WHERE registrasi_id = $3")
+            .bind(&nipd)
+            .bind(pengguna_id)
+            .bind(registrasi_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Gagal menyimpan NIPD untuk {}: {}", nama, e))?;
+        hasil.push(HasilNipd { peserta_didik_id, nama, nipd });
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    emit_log(&app, &format!("CMD: generate_nipd_massal - Berhasil memberi NIPD kepada {} siswa", hasil.len()));
+    Ok(hasil)
+}
@@ -0,0 +1,292 @@
+//! Aturan nomor ijazah (blanko) per jenis ijazah, pemeriksaan duplikat, dan alokasi nomor berurutan.
+//!
+//! Pola nomor dicocokkan karakter per karakter:
+//! - `#`: satu digit
+//! - `@`: satu huruf
+//! - `*`: satu huruf atau digit
+//! - `\`: karakter berikutnya dibaca apa adanya, misalnya `\#`
+//! - karakter lain harus sama persis (tanpa membedakan huruf besar/kecil)
+//!
+//! Contoh: `DN-## Dd/## #######` cocok dengan `DN-02 Dd/13 0012345`.
+
+use crate::app_state::DbPool;
+use crate::commands::pengaturan::{muat_pengaturan, AturanNomorIjazah};
+use crate::emit_log;
+use tauri::{AppHandle, State};
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use bigdecimal::BigDecimal;
+use sqlx::PgConnection;
+use sqlx::types::Uuid as SqlxUuid;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+pub fn validasi_pola_nomor_ijazah(pola: &str) -> Result<(), String> {
+    if pola.trim().is_empty() {
+        return Err("Pola nomor ijazah tidak boleh kosong".to_string());
+    }
+    let mut chars = pola.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.next().is_none() {
+            return Err(format!("Pola nomor ijazah '{}' diakhiri '\\' tanpa karakter", pola));
+        }
+    }
+    Ok(())
+}
+
+fn cocok_pola(pola: &str, nomor: &str) -> bool {
+    let mut pola = pola.chars();
+    let mut nomor = nomor.chars();
+    while let Some(p) = pola.next() {
+        let Some(n) = nomor.next() else {
+            return false;
+        };
+        let cocok = match p {
+            '#' => n.is_ascii_digit(),
+            '@' => n.is_alphabetic(),
+            '*' => n.is_alphanumeric(),
+            '\\' => pola.next().is_some_and(|lit| lit.eq_ignore_ascii_case(&n)),
+            lit => lit.eq_ignore_ascii_case(&n),
+        };
+        if !cocok {
+            return false;
+        }
+    }
+    nomor.next().is_none()
+}
+
+fn aturan_untuk<'a>(aturan: &'a [AturanNomorIjazah], jenis_ijazah_id: Option<&BigDecimal>) -> Option<&'a AturanNomorIjazah> {
+    let jenis = jenis_ijazah_id?;
+    aturan
+        .iter()
+        .find(|a| BigDecimal::from_str(a.jenis_ijazah_id.trim()).is_ok_and(|id| &id == jenis))
+}
+
+/// Memeriksa nomor ijazah satu siswa: kecocokan dengan pola jenis ijazahnya, duplikat dengan ijazah siswa lain,
+/// dan tanggal tanda tangan yang tidak boleh sebelum tanggal lulus
+pub(crate) async fn validasi_ijazah(
+    conn: &mut PgConnection,
+    registrasi_id: SqlxUuid,
+    jenis_ijazah_id: Option<&BigDecimal>,
+    nomor: Option<&str>,
+    tanggal_ttd: Option<NaiveDate>,
+) -> Result<(), String> {
+    if let Some(nomor) = nomor.map(str::trim).filter(|n| !n.is_empty()) {
+        let pengaturan = muat_pengaturan();
+        if let Some(aturan) = aturan_untuk(&pengaturan.aturan_nomor_ijazah, jenis_ijazah_id) {
+            if !cocok_pola(&aturan.pola, nomor) {
+                return Err(format!("Nomor ijazah '{}' tidak sesuai pola '{}'", nomor, aturan.pola));
+            }
+        }
+
+        let pemilik: Option<String> = sqlx::query_scalar(
+            "SELECT pd.nama FROM ijazah_pd ip
+             JOIN registrasi_peserta_didik rpd ON rpd.registrasi_id = ip.registrasi_id
+             JOIN peserta_didik pd ON pd.peserta_didik_id = rpd.peserta_didik_id
+             WHERE ip.soft_delete = 0 AND UPPER(TRIM(ip.nomor)) = UPPER($1) AND ip.registrasi_id <> $2
+             LIMIT 1"
+        )
+        .bind(nomor)
+        .bind(registrasi_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| format!("Gagal memeriksa duplikat nomor ijazah: {}", e))?;
+        if let Some(nama) = pemilik {
+            return Err(format!("Nomor ijazah '{}' sudah dipakai oleh {}", nomor, nama));
+        }
+    }
+
+    if let Some(tanggal_ttd) = tanggal_ttd {
+        let tanggal_lulus: Option<NaiveDate> = sqlx::query_scalar(
+            "SELECT tanggal_keluar FROM registrasi_peserta_didik WHERE registrasi_id = $1"
+        )
+        .bind(registrasi_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| format!("Gagal mengambil tanggal lulus: {}", e))?
+        .flatten();
+        if let Some(tanggal_lulus) = tanggal_lulus.filter(|t| tanggal_ttd < *t) {
+            return Err(format!(
+                "Tanggal tanda tangan ({}) tidak boleh sebelum tanggal lulus ({})",
+                tanggal_ttd.format("%d-%m-%Y"),
+                tanggal_lulus.format("%d-%m-%Y")
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct PemilikNomorIjazah {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nisn: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DuplikatNomorIjazah {
+    pub nomor: String,
+    pub pemilik: Vec<PemilikNomorIjazah>,
+}
+
+/// Daftar nomor ijazah yang dipakai lebih dari satu siswa
+#[tauri::command]
+pub async fn cek_duplikat_nomor_ijazah(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+) -> Result<Vec<DuplikatNomorIjazah>, String> {
+    emit_log(&app, "CMD: cek_duplikat_nomor_ijazah - Memeriksa nomor ijazah ganda");
+
+    let rows = sqlx::query_as::<_, (String, SqlxUuid, String, Option<String>)>(
+        "SELECT UPPER(TRIM(ip.nomor)) AS nomor, pd.peserta_didik_id, pd.nama, pd.nisn
+         FROM ijazah_pd ip
+         JOIN registrasi_peserta_didik rpd ON rpd.registrasi_id = ip.registrasi_id
+         JOIN peserta_didik pd ON pd.peserta_didik_id = rpd.peserta_didik_id
+         WHERE ip.soft_delete = 0 AND TRIM(COALESCE(ip.nomor, '')) <> ''
+         AND UPPER(TRIM(ip.nomor)) IN (
+             SELECT UPPER(TRIM(nomor)) FROM ijazah_pd
+             WHERE soft_delete = 0 AND TRIM(COALESCE(nomor, '')) <> ''
+             GROUP BY UPPER(TRIM(nomor)) HAVING COUNT(*) > 1
+         )
+         ORDER BY 1, pd.nama"
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal memeriksa nomor ijazah ganda: {}", e))?;
+
+    let mut duplikat: Vec<DuplikatNomorIjazah> = Vec::new();
+    for (nomor, peserta_didik_id, nama, nisn) in rows {
+        let pemilik = PemilikNomorIjazah { peserta_didik_id, nama, nisn };
+        match duplikat.last_mut() {
+            Some(d) if d.nomor == nomor => d.pemilik.push(pemilik),
+            _ => duplikat.push(DuplikatNomorIjazah { nomor, pemilik: vec![pemilik] }),
+        }
+    }
+
+    emit_log(&app, &format!("CMD: cek_duplikat_nomor_ijazah - {} nomor ganda", duplikat.len()));
+    Ok(duplikat)
+}
+
+#[derive(Deserialize)]
+pub struct AlokasiNomorIjazahPayload {
+    pub peserta_didik_ids: Vec<SqlxUuid>,
+    /// Nomor untuk siswa pertama; deretan digit terakhirnya dinaikkan satu per siswa
+    pub nomor_awal: String,
+    pub jenis_ijazah_id: Option<BigDecimal>,
+    /// false hanya menampilkan pratinjau tanpa menyimpan
+    pub simpan: bool,
+    pub pengguna_id: SqlxUuid,
+}
+
+#[derive(Serialize)]
+pub struct AlokasiNomorIjazah {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nomor: String,
+}
+
+/// Memecah nomor menjadi awalan, nilai deretan digit terakhir beserta lebarnya, dan akhiran
+fn urai_nomor_awal(nomor: &str) -> Option<(String, u64, usize, String)> {
+    let akhir_digit = nomor.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let awal_digit = nomor[..akhir_digit].trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let digit = &nomor[awal_digit..akhir_digit];
+    let nilai = digit.parse().ok()?;
+    Some((nomor[..awal_digit].to_string(), nilai, digit.len(), nomor[akhir_digit..].to_string()))
+}
+
+/// Membagikan nomor ijazah berurutan untuk satu angkatan lulusan, diurutkan menurut nama siswa.
+/// Saat disimpan, semua nomor divalidasi terhadap pola dan duplikat; satu kegagalan membatalkan semuanya.
+#[tauri::command]
+pub async fn alokasi_nomor_ijazah(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    payload: AlokasiNomorIjazahPayload,
+) -> Result<Vec<AlokasiNomorIjazah>, String> {
+    emit_log(&app, &format!(
+        "CMD: alokasi_nomor_ijazah - {} siswa mulai {}, simpan: {}",
+        payload.peserta_didik_ids.len(), payload.nomor_awal, payload.simpan
+    ));
+
+    let nomor_awal = payload.nomor_awal.trim();
+    let (awalan, mulai, lebar, akhiran) = urai_nomor_awal(nomor_awal)
+        .ok_or_else(|| format!("Nomor awal '{}' tidak memuat angka urut", nomor_awal))?;
+
+    let ids: Vec<SqlxUuid> = payload.peserta_didik_ids.iter().copied().collect::<HashSet<_>>().into_iter().collect();
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+
+    let siswa = sqlx::query_as::<_, (SqlxUuid, String, SqlxUuid)>(
+        "SELECT pd.peserta_didik_id, pd.nama, rpd.registrasi_id
+         FROM peserta_didik pd
+         JOIN registrasi_peserta_didik rpd ON rpd.peserta_didik_id = pd.peserta_didik_id AND rpd.soft_delete = 0
+         WHERE pd.peserta_didik_id = ANY($1) AND pd.soft_delete = 0 AND TRIM(rpd.jenis_keluar_id) = '1'
+         ORDER BY pd.nama"
+    )
+    .bind(&ids)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal mengambil data lulusan: {}", e))?;
+
+    if siswa.len() != ids.len() {
+        return Err(format!("{} siswa yang dipilih tidak tercatat lulus.", ids.len() - siswa.len()));
+    }
+
+    // Nomor lama siswa dalam batch ini akan ditimpa, jadi dikosongkan dulu agar tidak dianggap duplikat
+    // ketika nomor tersebut jatuh ke siswa lain di batch yang sama
+    if payload.simpan {
+        let registrasi_ids: Vec<SqlxUuid> = siswa.iter().map(|(_, _, registrasi_id)| *registrasi_id).collect();
+        sqlx::query("UPDATE ijazah_pd SET nomor = NULL WHERE registrasi_id = ANY($1) AND soft_delete = 0")
+            .bind(&registrasi_ids)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Gagal mengosongkan nomor ijazah lama: {}", e))?;
+    }
+
+    let mut alokasi = Vec::new();
+    for (urutan, (peserta_didik_id, nama, registrasi_id)) in siswa.into_iter().enumerate() {
+        let nomor = format!("{}{:0lebar$}{}", awalan, mulai + urutan as u64, akhiran, lebar = lebar);
+
+        if payload.simpan {
+            validasi_ijazah(&mut tx, registrasi_id, payload.jenis_ijazah_id.as_ref(), Some(&nomor), None)
+                .await
+                .map_err(|e| format!("{}: {}", nama, e))?;
+
+            let diupdate = sqlx::query(
+                "UPDATE ijazah_pd
+                 SET nomor = $1, jenis_ijazah_id = COALESCE($2, jenis_ijazah_id), last_update = NOW(), updater_id = $3
+                 WHERE registrasi_id = $4 AND soft_delete = 0"
+            )
+            .bind(&nomor)
+            .bind(&payload.jenis_ijazah_id)
+            .bind(payload.pengguna_id)
+            .bind(registrasi_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Gagal menyimpan nomor ijazah {}: {}", nama, e))?
+            .rows_affected();
+
+            if diupdate == 0 {
+                sqlx::query(
+                    "INSERT INTO ijazah_pd (registrasi_id, jenis_ijazah_id, nomor, create_date, last_update, soft_delete, updater_id)
+                     VALUES ($1, $2, $3, NOW(), NOW(), 0, $4)"
+                )
+                .bind(registrasi_id)
+                .bind(&payload.jenis_ijazah_id)
+                .bind(&nomor)
+                .bind(payload.pengguna_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Gagal menyimpan nomor ijazah {}: {}", nama, e))?;
+            }
+        }
+
+        alokasi.push(AlokasiNomorIjazah { peserta_didik_id, nama, nomor });
+    }
+
+    if payload.simpan {
+        tx.commit().await.map_err(|e| e.to_string())?;
+    }
+
+    emit_log(&app, &format!("CMD: alokasi_nomor_ijazah - {} nomor dialokasikan", alokasi.len()));
+    Ok(alokasi)
+}
@@ -0,0 +1,139 @@
+use tauri::AppHandle;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Pengaturan aplikasi yang dapat diubah operator, disimpan sebagai JSON di folder konfigurasi pengguna
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PengaturanAplikasi {
+    /// Batas jumlah anggota per rombel saat memindahkan siswa (None = tanpa batas)
+    pub kapasitas_rombel_maks: Option<i64>,
+    /// Pola NIPD otomatis, lihat `commands::nipd` untuk daftar placeholder
+    pub pola_nipd: String,
+    /// Rentang usia wajar per jenjang untuk laporan anomali usia
+    pub rentang_usia: Vec<RentangUsiaJenjang>,
+    /// Pengecualian rentang tingkat per bentuk pendidikan, atau per jurusan/kurikulum bila diisi,
+    /// misalnya jurusan SMK 4 tahun (tingkat akhir 13)
+    pub aturan_jenjang: Vec<AturanJenjang>,
+    /// Pola nomor ijazah per jenis ijazah, lihat `commands::nomor_ijazah`
+    pub aturan_nomor_ijazah: Vec<AturanNomorIjazah>,
+    /// Pola nomor SKL, lihat `commands::dokumen::format_nomor_surat`
+    pub pola_nomor_skl: String,
+    /// Lokasi template DOCX SKL milik sekolah (None = PDF bawaan)
+    pub template_skl: Option<String>,
+    /// Pola nomor surat keterangan pindah, sama dengan pola nomor SKL
+    pub pola_nomor_surat_pindah: String,
+    /// Lokasi template DOCX surat pindah (None = PDF bawaan)
+    pub template_surat_pindah: Option<String>,
+    /// Mengizinkan hapus permanen data siswa dari tempat sampah; mati secara bawaan
+    pub izinkan_hapus_permanen: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AturanNomorIjazah {
+    pub jenis_ijazah_id: String,
+    pub pola: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AturanJenjang {
+    pub bentuk_pendidikan: String,
+    pub tingkat_awal: i32,
+    pub tingkat_akhir: i32,
+    /// Hanya berlaku untuk rombel jurusan ini (None = semua jurusan)
+    #[serde(default)]
+    pub jurusan_sp_id: Option<Uuid>,
+    /// Hanya berlaku untuk rombel dengan kurikulum ini (None = semua kurikulum)
+    #[serde(default)]
+    pub kurikulum_id: Option<i32>,
+}
+
+/// Rentang usia untuk tingkat pertama sebuah jenjang; tingkat berikutnya bergeser satu tahun per tingkat
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RentangUsiaJenjang {
+    pub jenjang: String,
+    pub tingkat_awal: i32,
+    pub tingkat_akhir: i32,
+    pub usia_min: i32,
+    pub usia_maks: i32,
+}
+
+impl RentangUsiaJenjang {
+    fn baru(jenjang: &str, tingkat_awal: i32, tingkat_akhir: i32, usia_min: i32, usia_maks: i32) -> Self {
+        Self { jenjang: jenjang.to_string(), tingkat_awal, tingkat_akhir, usia_min, usia_maks }
+    }
+}
+
+impl Default for PengaturanAplikasi {
+    fn default() -> Self {
+        Self {
+            kapasitas_rombel_maks: None,
+            pola_nipd: "{tahun_masuk}{seq:4}".to_string(),
+            rentang_usia: vec![
+                RentangUsiaJenjang::baru("SD", 1, 6, 5, 8),
+                RentangUsiaJenjang::baru("SMP", 7, 9, 11, 15),
+                RentangUsiaJenjang::baru("SMA/SMK", 10, 13, 14, 18),
+            ],
+            aturan_jenjang: Vec::new(),
+            aturan_nomor_ijazah: Vec::new(),
+            pola_nomor_skl: "{urut:3}/SKL/{bulan_romawi}/{tahun}".to_string(),
+            template_skl: None,
+            pola_nomor_surat_pindah: "{urut:3}/SPD/{bulan_romawi}/{tahun}".to_string(),
+            template_surat_pindah: None,
+            izinkan_hapus_permanen: false,
+        }
+    }
+}
+
+/// Lokasi file data milik aplikasi di folder konfigurasi pengguna
+pub(crate) fn path_data_aplikasi(nama_file: &str) -> Result<PathBuf, String> {
+    let dir = dirs::config_dir()
+        .ok_or("Tidak dapat menemukan folder konfigurasi")?
+        .join("DapoMaster");
+    Ok(dir.join(nama_file))
+}
+
+fn path_pengaturan() -> Result<PathBuf, String> {
+    path_data_aplikasi("pengaturan.json")
+}
+
+/// Membaca pengaturan dari disk; jika file belum ada atau rusak, nilai bawaan yang dipakai
+pub fn muat_pengaturan() -> PengaturanAplikasi {
+    path_pengaturan()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|isi| serde_json::from_str(&isi).ok())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_pengaturan(app: AppHandle) -> Result<PengaturanAplikasi, String> {
+    crate::emit_log(&app, "CMD: get_pengaturan - Membaca pengaturan aplikasi");
+    Ok(muat_pengaturan())
+}
+
+#[tauri::command]
+pub async fn simpan_pengaturan(app: AppHandle, pengaturan: PengaturanAplikasi) -> Result<String, String> {
+    crate::emit_log(&app, "CMD: simpan_pengaturan - Menyimpan pengaturan aplikasi");
+    crate::commands::nipd::validasi_pola_nipd(&pengaturan.pola_nipd)?;
+    if let Some(r) = pengaturan.rentang_usia.iter().find(|r| r.tingkat_awal > r.tingkat_akhir || r.usia_min > r.usia_maks) {
+        return Err(format!("Rentang usia jenjang {} tidak valid", r.jenjang));
+    }
+    if let Some(a) = pengaturan.aturan_jenjang.iter().find(|a| a.tingkat_awal > a.tingkat_akhir) {
+        return Err(format!("Rentang tingkat {} tidak valid", a.bentuk_pendidikan));
+    }
+    for a in &pengaturan.aturan_nomor_ijazah {
+        crate::commands::nomor_ijazah::validasi_pola_nomor_ijazah(&a.pola)?;
+    }
+    let hari_ini = chrono::Local::now().date_naive();
+    crate::commands::dokumen::format_nomor_surat(&pengaturan.pola_nomor_skl, 1, hari_ini)?;
+    crate::commands::dokumen::format_nomor_surat(&pengaturan.pola_nomor_surat_pindah, 1, hari_ini)?;
+    let path = path_pengaturan()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Gagal membuat folder konfigurasi: {}", e))?;
+    }
+    let isi = serde_json::to_string_pretty(&pengaturan).map_err(|e| e.to_string())?;
+    std::fs::write(&path, isi).map_err(|e| format!("Gagal menyimpan pengaturan: {}", e))?;
+    Ok("Pengaturan berhasil disimpan.".to_string())
+}
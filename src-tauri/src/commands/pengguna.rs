@@ -1,5 +1,6 @@
 use crate::app_state::DbPool;
 use sqlx::types::Uuid as SqlxUuid;
+use sqlx::{Pool, Postgres};
 use tauri::{AppHandle, State};
 
 #[derive(sqlx::FromRow, serde::Serialize, Clone)]
@@ -27,4 +28,18 @@ pub async fn ambil_semua_pengguna(app: AppHandle, state: State<'_, DbPool>) -> R
             Err(err_msg)
         },
     }
-} 
\ No newline at end of file
+}
+
+/// Memastikan pengguna memiliki peran operator sekolah (peran_id = 10) sebelum menjalankan aksi berisiko
+pub async fn pastikan_operator(pool: &Pool<Postgres>, pengguna_id: SqlxUuid) -> Result<(), String> {
+    let is_operator: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM man_akses.role_pengguna WHERE pengguna_id = $1 AND peran_id = 10)")
+        .bind(pengguna_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Gagal memeriksa hak akses pengguna: {}", e))?;
+    if is_operator {
+        Ok(())
+    } else {
+        Err("Pengguna tidak memiliki hak akses operator untuk aksi ini.".to_string())
+    }
+}
@@ -0,0 +1,690 @@
+use crate::app_state::{DbPool, WilayahCache};
+use crate::commands::export::{
+    get_optional_date_value, get_optional_string_value, get_string_value, parse_tanggal_import, ExportResult, ImportError,
+    ImportResult,
+};
+use crate::commands::nipd::PenomoranNipd;
+use crate::commands::wilayah::{ambil_index_wilayah, LEVEL_DESA};
+use crate::commands::validasi::{cek_format_nik, cek_format_nisn, cek_format_no_kk, nik_sesuai_tanggal_lahir};
+use tauri::{AppHandle, State};
+use serde::Serialize;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{Postgres, Transaction};
+use sqlx::types::Uuid as SqlxUuid;
+use bigdecimal::BigDecimal;
+use uuid::Uuid;
+use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, Workbook};
+use calamine::{open_workbook_auto, DataType, Reader};
+use std::collections::{HashMap, HashSet};
+
+// Urutan kolom template PPDB, tanda * berarti wajib diisi
+const HEADER_PPDB: [&str; 26] = [
+    "No", "Nama*", "Jenis Kelamin (L/P)*", "NISN", "NIK", "No KK", "Tempat Lahir", "Tanggal Lahir*",
+    "Agama*", "Kewarganegaraan", "Alamat Jalan*", "RT", "RW", "Nama Dusun", "Desa/Kelurahan*",
+    "Kode Wilayah*", "Kode Pos", "Nama Ibu Kandung*", "NIK Ibu", "Nama Ayah", "NIK Ayah",
+    "Jenis Pendaftaran*", "Tanggal Masuk Sekolah*", "NIPD", "Sekolah Asal", "Rombel Tujuan",
+];
+
+// Kolom yang harus disimpan sebagai teks agar angka nol di depan tidak hilang
+const KOLOM_TEKS_PPDB: [u16; 8] = [3, 4, 5, 15, 16, 18, 20, 23];
+
+#[derive(Serialize)]
+pub struct PreviewSiswaPpdb {
+    pub row: i32,
+    pub nama: String,
+    pub nisn: Option<String>,
+    pub nik: Option<String>,
+    pub tanggal_lahir: Option<NaiveDate>,
+    pub nama_rombel: Option<String>,
+    pub status: String,
+    pub pesan: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct PreviewImportPpdb {
+    pub total_rows: i32,
+    pub valid_count: i32,
+    pub duplikat_count: i32,
+    pub error_count: i32,
+    pub rows: Vec<PreviewSiswaPpdb>,
+}
+
+struct BarisPpdb {
+    nama: String,
+    jenis_kelamin: String,
+    nisn: Option<String>,
+    nik: Option<String>,
+    no_kk: Option<String>,
+    tempat_lahir: Option<String>,
+    tanggal_lahir: NaiveDate,
+    agama_id: i16,
+    kewarganegaraan: String,
+    alamat_jalan: String,
+    rt: Option<BigDecimal>,
+    rw: Option<BigDecimal>,
+    nama_dusun: Option<String>,
+    desa_kelurahan: String,
+    kode_wilayah: String,
+    kode_pos: Option<String>,
+    nama_ibu_kandung: String,
+    nik_ibu: Option<String>,
+    nama_ayah: Option<String>,
+    nik_ayah: Option<String>,
+    jenis_pendaftaran_id: BigDecimal,
+    tanggal_masuk_sekolah: NaiveDate,
+    nipd: Option<String>,
+    sekolah_asal: Option<String>,
+    rombongan_belajar_id: Option<SqlxUuid>,
+    nama_rombel: Option<String>,
+}
+
+struct HasilBarisPpdb {
+    row: usize,
+    nama: String,
+    data: Option<BarisPpdb>,
+    errors: Vec<ImportError>,
+    duplikat: Vec<String>,
+}
+
+struct ReferensiPpdb {
+    agama: HashMap<String, i16>,
+    jenis_pendaftaran: HashMap<String, BigDecimal>,
+    rombel: HashMap<String, (SqlxUuid, String)>,
+    nisn_terdaftar: HashMap<String, String>,
+    nik_terdaftar: HashMap<String, String>,
+    identitas_terdaftar: HashSet<(String, NaiveDate, String)>,
+}
+
+async fn muat_referensi_ppdb(state: &State<'_, DbPool>) -> Result<ReferensiPpdb, String> {
+    let agama = sqlx::query_as::<_, (i16, String)>("SELECT agama_id, nama FROM ref.agama")
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| format!("Gagal mengambil referensi agama: {}", e))?
+        .into_iter()
+        .map(|(id, nama)| (nama.trim().to_lowercase(), id))
+        .collect();
+
+    let jenis_pendaftaran = sqlx::query_as::<_, (BigDecimal, String)>("SELECT jenis_pendaftaran_id, nama FROM ref.jenis_pendaftaran")
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| format!("Gagal mengambil referensi jenis pendaftaran: {}", e))?
+        .into_iter()
+        .map(|(id, nama)| (nama.trim().to_lowercase(), id))
+        .collect();
+
+    let rombel = sqlx::query_as::<_, (SqlxUuid, String)>(
+        "SELECT rb.rombongan_belajar_id, rb.nama FROM rombongan_belajar rb
+         JOIN ref.semester s ON rb.semester_id = s.semester_id
+         WHERE rb.soft_delete = 0 AND s.periode_aktif = 1"
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil rombel semester aktif: {}", e))?
+    .into_iter()
+    .map(|(id, nama)| (nama.trim().to_lowercase(), (id, nama)))
+    .collect();
+
+    let siswa_terdaftar = sqlx::query_as::<_, (String, Option<String>, Option<String>, NaiveDate, Option<String>)>(
+        "SELECT nama, nisn, nik, tanggal_lahir, nama_ibu_kandung FROM peserta_didik WHERE soft_delete = 0"
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil data siswa terdaftar: {}", e))?;
+
+    let mut nisn_terdaftar = HashMap::new();
+    let mut nik_terdaftar = HashMap::new();
+    let mut identitas_terdaftar = HashSet::new();
+    for (nama, nisn, nik, tanggal_lahir, nama_ibu) in siswa_terdaftar {
+        if let Some(nisn) = nisn.filter(|n| !n.trim().is_empty()) {
+            nisn_terdaftar.insert(nisn.trim().to_string(), nama.clone());
+        }
+        if let Some(nik) = nik.filter(|n| !n.trim().is_empty()) {
+            nik_terdaftar.insert(nik.trim().to_string(), nama.clone());
+        }
+        identitas_terdaftar.insert((
+            nama.trim().to_lowercase(),
+            tanggal_lahir,
+            nama_ibu.unwrap_or_default().trim().to_lowercase(),
+        ));
+    }
+
+    Ok(ReferensiPpdb { agama, jenis_pendaftaran, rombel, nisn_terdaftar, nik_terdaftar, identitas_terdaftar })
+}
+
+fn push_error(errors: &mut Vec<ImportError>, row: usize, field: &str, message: String) {
+    errors.push(ImportError { row: row as i32, field: field.to_string(), message });
+}
+
+fn parse_baris_ppdb(row: &[DataType], excel_row: usize, refs: &ReferensiPpdb, errors: &mut Vec<ImportError>) -> Option<BarisPpdb> {
+    let kosong = DataType::Empty;
+    let cell = |idx: usize| row.get(idx).unwrap_or(&kosong);
+    let jumlah_error_awal = errors.len();
+
+    let nama = get_string_value(cell(1), "Nama", excel_row, errors);
+    let jenis_kelamin_raw = get_string_value(cell(2), "Jenis Kelamin", excel_row, errors);
+    let nisn = get_optional_string_value(cell(3), "NISN", excel_row, errors);
+    let nik = get_optional_string_value(cell(4), "NIK", excel_row, errors);
+    let no_kk = get_optional_string_value(cell(5), "No KK", excel_row, errors);
+    let tempat_lahir = get_optional_string_value(cell(6), "Tempat Lahir", excel_row, errors);
+    let tanggal_lahir_raw = get_optional_date_value(cell(7), "Tanggal Lahir", excel_row, errors);
+    let agama_raw = get_string_value(cell(8), "Agama", excel_row, errors);
+    let kewarganegaraan = get_optional_string_value(cell(9), "Kewarganegaraan", excel_row, errors).unwrap_or_else(|| "ID".to_string());
+    let alamat_jalan = get_string_value(cell(10), "Alamat Jalan", excel_row, errors);
+    let rt_raw = get_optional_string_value(cell(11), "RT", excel_row, errors);
+    let rw_raw = get_optional_string_value(cell(12), "RW", excel_row, errors);
+    let nama_dusun = get_optional_string_value(cell(13), "Nama Dusun", excel_row, errors);
+    let desa_kelurahan = get_string_value(cell(14), "Desa/Kelurahan", excel_row, errors);
+    let kode_wilayah = get_string_value(cell(15), "Kode Wilayah", excel_row, errors);
+    let kode_pos = get_optional_string_value(cell(16), "Kode Pos", excel_row, errors);
+    let nama_ibu_kandung = get_string_value(cell(17), "Nama Ibu Kandung", excel_row, errors);
+    let nik_ibu = get_optional_string_value(cell(18), "NIK Ibu", excel_row, errors);
+    let nama_ayah = get_optional_string_value(cell(19), "Nama Ayah", excel_row, errors);
+    let nik_ayah = get_optional_string_value(cell(20), "NIK Ayah", excel_row, errors);
+    let jenis_pendaftaran_raw = get_string_value(cell(21), "Jenis Pendaftaran", excel_row, errors);
+    let tanggal_masuk_raw = get_optional_date_value(cell(22), "Tanggal Masuk Sekolah", excel_row, errors);
+    let nipd = get_optional_string_value(cell(23), "NIPD", excel_row, errors);
+    let sekolah_asal = get_optional_string_value(cell(24), "Sekolah Asal", excel_row, errors);
+    let rombel_raw = get_optional_string_value(cell(25), "Rombel Tujuan", excel_row, errors);
+
+    let jenis_kelamin = jenis_kelamin_raw.and_then(|jk| match jk.to_uppercase().as_str() {
+        "L" | "LAKI-LAKI" | "LAKI LAKI" => Some("L".to_string()),
+        "P" | "PEREMPUAN" => Some("P".to_string()),
+        _ => {
+            push_error(errors, excel_row, "Jenis Kelamin", format!("Jenis kelamin '{}' tidak dikenal (gunakan L atau P)", jk));
+            None
+        }
+    });
+
+    let tanggal_lahir = match tanggal_lahir_raw.as_deref().map(parse_tanggal_import) {
+        Some(Some(tgl)) => Some(tgl),
+        Some(None) => None,
+        None => {
+            push_error(errors, excel_row, "Tanggal Lahir", "Data wajib tidak boleh kosong".to_string());
+            None
+        }
+    };
+
+    let tanggal_masuk_sekolah = match tanggal_masuk_raw.as_deref().map(parse_tanggal_import) {
+        Some(Some(tgl)) => Some(tgl),
+        Some(None) => None,
+        None => {
+            push_error(errors, excel_row, "Tanggal Masuk Sekolah", "Data wajib tidak boleh kosong".to_string());
+            None
+        }
+    };
+
+    let agama_id = agama_raw.and_then(|agama| match refs.agama.get(&agama.to_lowercase()) {
+        Some(id) => Some(*id),
+        None => {
+            push_error(errors, excel_row, "Agama", format!("Agama '{}' tidak ditemukan dalam referensi", agama));
+            None
+        }
+    });
+
+    let jenis_pendaftaran_id = jenis_pendaftaran_raw.and_then(|jenis| match refs.jenis_pendaftaran.get(&jenis.to_lowercase()) {
+        Some(id) => Some(id.clone()),
+        None => {
+            push_error(errors, excel_row, "Jenis Pendaftaran", format!("Jenis pendaftaran '{}' tidak ditemukan dalam referensi", jenis));
+            None
+        }
+    });
+
+    let rombel = match rombel_raw {
+        Some(nama_rombel) => match refs.rombel.get(&nama_rombel.to_lowercase()) {
+            Some((id, nama)) => Some((*id, nama.clone())),
+            None => {
+                push_error(errors, excel_row, "Rombel Tujuan", format!("Rombel '{}' tidak ditemukan di semester aktif", nama_rombel));
+                None
+            }
+        },
+        None => None,
+    };
+
+    if let Some(ref nisn) = nisn {
+        if let Err(e) = cek_format_nisn(nisn) {
+            push_error(errors, excel_row, "NISN", e);
+        }
+    }
+    if let Some(ref nik) = nik {
+        match cek_format_nik(nik) {
+            Ok(perempuan) => {
+                if let Some(lahir) = tanggal_lahir {
+                    if !nik_sesuai_tanggal_lahir(nik, lahir) {
+                        push_error(errors, excel_row, "NIK", "Tanggal lahir pada NIK tidak sesuai dengan Tanggal Lahir".to_string());
+                    }
+                }
+                if let Some(ref jk) = jenis_kelamin {
+                    if perempuan != (jk == "P") {
+                        push_error(errors, excel_row, "NIK", "Jenis kelamin pada NIK (tanggal ditambah 40 untuk perempuan) tidak sesuai dengan Jenis Kelamin".to_string());
+                    }
+                }
+            }
+            Err(e) => push_error(errors, excel_row, "NIK", e),
+        }
+    }
+    if let Some(ref no_kk) = no_kk {
+        if let Err(e) = cek_format_no_kk(no_kk) {
+            push_error(errors, excel_row, "No KK", e);
+        }
+    }
+    for (field, nilai) in [("NIK Ibu", &nik_ibu), ("NIK Ayah", &nik_ayah)] {
+        if let Some(nilai) = nilai {
+            if let Err(e) = cek_format_nik(nilai) {
+                push_error(errors, excel_row, field, e);
+            }
+        }
+    }
+
+    if let (Some(lahir), Some(masuk)) = (tanggal_lahir, tanggal_masuk_sekolah) {
+        if masuk <= lahir {
+            push_error(errors, excel_row, "Tanggal Masuk Sekolah", "Tanggal masuk sekolah harus setelah tanggal lahir".to_string());
+        }
+    }
+
+    let mut parse_rt_rw = |field: &str, nilai: Option<String>| {
+        let nilai = nilai?;
+        match nilai.trim().parse::<BigDecimal>() {
+            Ok(angka) => Some(angka),
+            Err(_) => {
+                push_error(errors, excel_row, field, format!("{} '{}' harus berupa angka", field, nilai));
+                None
+            }
+        }
+    };
+    let rt = parse_rt_rw("RT", rt_raw);
+    let rw = parse_rt_rw("RW", rw_raw);
+
+    if errors.len() > jumlah_error_awal {
+        return None;
+    }
+
+    let (rombongan_belajar_id, nama_rombel) = match rombel {
+        Some((id, nama)) => (Some(id), Some(nama)),
+        None => (None, None),
+    };
+
+    Some(BarisPpdb {
+        nama: nama?,
+        jenis_kelamin: jenis_kelamin?,
+        nisn,
+        nik,
+        no_kk,
+        tempat_lahir,
+        tanggal_lahir: tanggal_lahir?,
+        agama_id: agama_id?,
+        kewarganegaraan,
+        alamat_jalan: alamat_jalan?,
+        rt,
+        rw,
+        nama_dusun,
+        desa_kelurahan: desa_kelurahan?,
+        kode_wilayah: kode_wilayah?,
+        kode_pos,
+        nama_ibu_kandung: nama_ibu_kandung?,
+        nik_ibu,
+        nama_ayah,
+        nik_ayah,
+        jenis_pendaftaran_id: jenis_pendaftaran_id?,
+        tanggal_masuk_sekolah: tanggal_masuk_sekolah?,
+        nipd,
+        sekolah_asal,
+        rombongan_belajar_id,
+        nama_rombel,
+    })
+}
+
+/// Membaca file PPDB dan memvalidasi setiap baris terhadap referensi, aturan nomor identitas, dan data siswa yang sudah ada
+async fn baca_file_ppdb(state: &State<'_, DbPool>, cache: &WilayahCache, file_path: &str) -> Result<Vec<HasilBarisPpdb>, String> {
+    let mut workbook = open_workbook_auto(file_path)
+        .map_err(|e| format!("Gagal membuka file Excel: {}", e))?;
+
+    let range = workbook.worksheet_range_at(0)
+        .ok_or("Tidak dapat menemukan worksheet")?
+        .map_err(|e| format!("Gagal membaca worksheet: {}", e))?;
+
+    let header_cocok = range.rows().next().map(|header| {
+        HEADER_PPDB.iter().enumerate().all(|(idx, expected)| {
+            header.get(idx).map(|cell| cell.to_string().trim().to_lowercase()) == Some(expected.to_lowercase())
+        })
+    });
+    if header_cocok != Some(true) {
+        return Err("Format file tidak sesuai template PPDB. Gunakan template dari menu Export Template PPDB.".to_string());
+    }
+
+    let refs = muat_referensi_ppdb(state).await?;
+    let mut hasil = Vec::new();
+
+    for (row_idx, row) in range.rows().skip(1).enumerate() {
+        let excel_row = row_idx + 2;
+        // Lewati baris yang benar-benar kosong di akhir template
+        if row.iter().skip(1).all(|cell| matches!(cell, DataType::Empty) || cell.to_string().trim().is_empty()) {
+            continue;
+        }
+        let mut errors = Vec::new();
+        let nama = row.get(1).map(|c| c.to_string().trim().to_string()).unwrap_or_default();
+        let data = parse_baris_ppdb(row, excel_row, &refs, &mut errors);
+        hasil.push(HasilBarisPpdb { row: excel_row, nama, data, errors, duplikat: Vec::new() });
+    }
+
+    // Kode wilayah harus kode desa/kelurahan di indeks wilayah bersama
+    let index_wilayah = ambil_index_wilayah(&state.pool, cache).await?;
+
+    let mut nisn_di_file: HashMap<String, usize> = HashMap::new();
+    let mut nik_di_file: HashMap<String, usize> = HashMap::new();
+    for h in hasil.iter_mut() {
+        let data = match h.data.as_ref() {
+            Some(d) => d,
+            None => continue,
+        };
+        if !index_wilayah.valid(&data.kode_wilayah, Some(LEVEL_DESA)) {
+            push_error(&mut h.errors, h.row, "Kode Wilayah", format!("Kode wilayah '{}' bukan kode desa/kelurahan di referensi wilayah", data.kode_wilayah));
+        }
+        if let Some(ref nisn) = data.nisn {
+            if let Some(nama) = refs.nisn_terdaftar.get(nisn) {
+                h.duplikat.push(format!("NISN {} sudah dipakai oleh {}", nisn, nama));
+            }
+            if let Some(baris_lain) = nisn_di_file.insert(nisn.clone(), h.row) {
+                h.duplikat.push(format!("NISN {} sama dengan baris {}", nisn, baris_lain));
+            }
+        }
+        if let Some(ref nik) = data.nik {
+            if let Some(nama) = refs.nik_terdaftar.get(nik) {
+                h.duplikat.push(format!("NIK {} sudah dipakai oleh {}", nik, nama));
+            }
+            if let Some(baris_lain) = nik_di_file.insert(nik.clone(), h.row) {
+                h.duplikat.push(format!("NIK {} sama dengan baris {}", nik, baris_lain));
+            }
+        }
+        let identitas = (data.nama.to_lowercase(), data.tanggal_lahir, data.nama_ibu_kandung.to_lowercase());
+        if refs.identitas_terdaftar.contains(&identitas) {
+            h.duplikat.push("Nama, tanggal lahir, dan nama ibu sama dengan siswa yang sudah terdaftar".to_string());
+        }
+    }
+
+    Ok(hasil)
+}
+
+async fn insert_siswa_ppdb(
+    tx: &mut Transaction<'_, Postgres>,
+    data: &BarisPpdb,
+    sekolah_id: SqlxUuid,
+    pengguna_id: SqlxUuid,
+    penomoran: &mut PenomoranNipd,
+) -> Result<(), String> {
+    let peserta_didik_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO peserta_didik (peserta_didik_id, nama, jenis_kelamin, tanggal_lahir, agama_id, kebutuhan_khusus_id, alamat_jalan, desa_kelurahan, kode_wilayah, penerima_kps, layak_pip, penerima_kip, kebutuhan_khusus_id_ayah, nama_ibu_kandung, kebutuhan_khusus_id_ibu, kewarganegaraan, create_date, last_update, soft_delete, updater_id, nisn, tempat_lahir, nik, no_kk, rt, rw, nama_dusun, kode_pos, nik_ayah, nik_ibu, nama_ayah) VALUES ($1, $2, $3, $4, $5, 0, $6, $7, $8, 0, 0, 0, 0, $9, 0, $10, NOW(), NOW(), 0, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22)")
+        .bind(peserta_didik_id)
+        .bind(&data.nama)
+        .bind(&data.jenis_kelamin)
+        .bind(data.tanggal_lahir)
+        .bind(data.agama_id)
+        .bind(&data.alamat_jalan)
+        .bind(&data.desa_kelurahan)
+        .bind(&data.kode_wilayah)
+        .bind(&data.nama_ibu_kandung)
+        .bind(&data.kewarganegaraan)
+        .bind(pengguna_id)
+        .bind(&data.nisn)
+        .bind(&data.tempat_lahir)
+        .bind(&data.nik)
+        .bind(&data.no_kk)
+        .bind(&data.rt)
+        .bind(&data.rw)
+        .bind(&data.nama_dusun)
+        .bind(&data.kode_pos)
+        .bind(&data.nik_ayah)
+        .bind(&data.nik_ibu)
+        .bind(&data.nama_ayah)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Gagal insert ke peserta_didik: {}", e))?;
+
+    let registrasi_id = Uuid::new_v4();
+    let nipd = penomoran.nipd_atau_otomatis(tx, data.nipd.as_deref(), registrasi_id, data.tanggal_masuk_sekolah).await?;
+    sqlx::query("INSERT INTO registrasi_peserta_didik (registrasi_id, peserta_didik_id, sekolah_id, jenis_pendaftaran_id, nipd, tanggal_masuk_sekolah, sekolah_asal, a_pernah_paud, a_pernah_tk, create_date, last_update, soft_delete, updater_id) VALUES ($1, $2, $3, $4, $5, $6, $7, 0, 0, NOW(), NOW(), 0, $8)")
+        .bind(registrasi_id)
+        .bind(peserta_didik_id)
+        .bind(sekolah_id)
+        .bind(&data.jenis_pendaftaran_id)
+        .bind(&nipd)
+        .bind(data.tanggal_masuk_sekolah)
+        .bind(&data.sekolah_asal)
+        .bind(pengguna_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Gagal insert ke registrasi_peserta_didik: {}", e))?;
+
+    if let Some(rombongan_belajar_id) = data.rombongan_belajar_id {
+        sqlx::query("INSERT INTO anggota_rombel (anggota_rombel_id, rombongan_belajar_id, peserta_didik_id, jenis_pendaftaran_id, create_date, last_update, soft_delete, updater_id) VALUES ($1, $2, $3, $4, NOW(), NOW(), 0, $5)")
+            .bind(Uuid::new_v4())
+            .bind(rombongan_belajar_id)
+            .bind(peserta_didik_id)
+            .bind(&data.jenis_pendaftaran_id)
+            .bind(pengguna_id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Gagal insert ke anggota_rombel: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Menampilkan pratinjau file PPDB tanpa menyimpan apa pun ke database
+#[tauri::command]
+pub async fn preview_import_ppdb(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, WilayahCache>,
+    file_path: String,
+) -> Result<PreviewImportPpdb, String> {
+    crate::emit_log(&app, &format!("CMD: preview_import_ppdb - Reading {}", file_path));
+
+    let hasil = baca_file_ppdb(&state, &cache, &file_path).await?;
+    let mut valid_count = 0;
+    let mut duplikat_count = 0;
+    let mut error_count = 0;
+
+    let rows: Vec<PreviewSiswaPpdb> = hasil
+        .into_iter()
+        .map(|h| {
+            let status = if !h.errors.is_empty() {
+                error_count += 1;
+                "error"
+            } else if !h.duplikat.is_empty() {
+                duplikat_count += 1;
+                "duplikat"
+            } else {
+                valid_count += 1;
+                "valid"
+            };
+            let mut pesan: Vec<String> = h.errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+            pesan.extend(h.duplikat);
+            PreviewSiswaPpdb {
+                row: h.row as i32,
+                nama: h.nama,
+                nisn: h.data.as_ref().and_then(|d| d.nisn.clone()),
+                nik: h.data.as_ref().and_then(|d| d.nik.clone()),
+                tanggal_lahir: h.data.as_ref().map(|d| d.tanggal_lahir),
+                nama_rombel: h.data.as_ref().and_then(|d| d.nama_rombel.clone()),
+                status: status.to_string(),
+                pesan,
+            }
+        })
+        .collect();
+
+    crate::emit_log(&app, &format!("CMD: preview_import_ppdb - Valid: {}, Duplikat: {}, Error: {}", valid_count, duplikat_count, error_count));
+
+    Ok(PreviewImportPpdb {
+        total_rows: rows.len() as i32,
+        valid_count,
+        duplikat_count,
+        error_count,
+        rows,
+    })
+}
+
+/// Import siswa baru dari template PPDB dalam satu transaksi.
+/// Jika `lewati_baris_bermasalah` false, satu baris error atau duplikat membatalkan seluruh import.
+#[tauri::command]
+pub async fn import_ppdb_from_excel(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, WilayahCache>,
+    file_path: String,
+    sekolah_id: SqlxUuid,
+    pengguna_id: SqlxUuid,
+    lewati_baris_bermasalah: bool,
+) -> Result<ImportResult, String> {
+    crate::emit_log(&app, "CMD: import_ppdb_from_excel - Starting import process.");
+
+    let hasil = baca_file_ppdb(&state, &cache, &file_path).await?;
+    let total_rows = hasil.len() as i32;
+    let mut errors = Vec::new();
+    let mut siap_import = Vec::new();
+
+    for h in hasil {
+        let bermasalah = !h.errors.is_empty() || !h.duplikat.is_empty();
+        errors.extend(h.errors);
+        for pesan in h.duplikat {
+            push_error(&mut errors, h.row, "Duplikat", pesan);
+        }
+        if !bermasalah {
+            if let Some(data) = h.data {
+                siap_import.push((h.row, data));
+            }
+        }
+    }
+
+    let baris_bermasalah = total_rows - siap_import.len() as i32;
+    if baris_bermasalah > 0 && !lewati_baris_bermasalah {
+        crate::emit_log(&app, &format!("CMD: import_ppdb_from_excel - Dibatalkan, {} baris bermasalah", baris_bermasalah));
+        return Ok(ImportResult {
+            success: false,
+            message: format!("Import dibatalkan: {} baris bermasalah. Perbaiki file atau pilih lewati baris bermasalah.", baris_bermasalah),
+            total_rows,
+            success_count: 0,
+            error_count: baris_bermasalah,
+            errors,
+        });
+    }
+
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    let mut penomoran = PenomoranNipd::baru(sekolah_id);
+    for (row, data) in &siap_import {
+        insert_siswa_ppdb(&mut tx, data, sekolah_id, pengguna_id, &mut penomoran)
+            .await
+            .map_err(|e| format!("Baris {} ({}): {}", row, data.nama, e))?;
+        crate::emit_log(&app, &format!("CMD: import_ppdb_from_excel - Inserted row {}: {}", row, data.nama));
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    let success_count = siap_import.len() as i32;
+    let message = if baris_bermasalah == 0 {
+        format!("Berhasil mengimport semua {} siswa baru", success_count)
+    } else {
+        format!("Berhasil mengimport {} siswa baru, {} baris dilewati", success_count, baris_bermasalah)
+    };
+
+    crate::emit_log(&app, &format!("CMD: import_ppdb_from_excel - Import completed. Success: {}, Skipped: {}", success_count, baris_bermasalah));
+
+    Ok(ImportResult {
+        success: baris_bermasalah == 0,
+        message,
+        total_rows,
+        success_count,
+        error_count: baris_bermasalah,
+        errors,
+    })
+}
+
+#[tauri::command]
+pub async fn export_template_ppdb(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+) -> Result<ExportResult, String> {
+    crate::emit_log(&app, "CMD: export_template_ppdb - Creating PPDB template.");
+
+    let agama = sqlx::query_scalar::<_, String>("SELECT nama FROM ref.agama ORDER BY agama_id")
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let jenis_pendaftaran = sqlx::query_scalar::<_, String>("SELECT nama FROM ref.jenis_pendaftaran ORDER BY jenis_pendaftaran_id")
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let rombel = sqlx::query_scalar::<_, String>(
+        "SELECT rb.nama FROM rombongan_belajar rb
+         JOIN ref.semester s ON rb.semester_id = s.semester_id
+         WHERE rb.soft_delete = 0 AND s.periode_aktif = 1
+         ORDER BY rb.tingkat_pendidikan_id, rb.nama"
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Buat nama file dengan timestamp
+    let now: DateTime<Utc> = Utc::now();
+    let timestamp = now.format("%Y%m%d_%H%M%S");
+    let filename = format!("template_ppdb_{}.xlsx", timestamp);
+
+    let desktop_path = dirs::desktop_dir()
+        .ok_or("Tidak dapat menemukan folder Desktop")?
+        .join(&filename);
+
+    let file_path = desktop_path.to_string_lossy().to_string();
+
+    let mut workbook = Workbook::new();
+
+    let header_format = Format::new()
+        .set_bold()
+        .set_align(FormatAlign::Center)
+        .set_border(FormatBorder::Thin)
+        .set_background_color(rust_xlsxwriter::Color::Green)
+        .set_font_color(rust_xlsxwriter::Color::White);
+
+    let text_format = Format::new().set_num_format("@");
+
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Data PPDB")
+        .map_err(|e| format!("Gagal mengatur nama worksheet: {}", e))?;
+
+    for col in KOLOM_TEKS_PPDB {
+        worksheet.set_column_format(col, &text_format)
+            .map_err(|e| format!("Gagal mengatur format kolom: {}", e))?;
+    }
+
+    for (col, header) in HEADER_PPDB.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *header, &header_format)
+            .map_err(|e| format!("Gagal menulis header: {}", e))?;
+        worksheet.set_column_width(col as u16, 18)
+            .map_err(|e| format!("Gagal mengatur lebar kolom: {}", e))?;
+    }
+    worksheet.set_freeze_panes(1, 2)
+        .map_err(|e| format!("Gagal mengatur freeze panes: {}", e))?;
+
+    // Sheet kedua berisi nilai referensi yang boleh dipakai pada kolom Agama, Jenis Pendaftaran, dan Rombel Tujuan
+    let referensi = workbook.add_worksheet();
+    referensi.set_name("Referensi")
+        .map_err(|e| format!("Gagal mengatur nama worksheet: {}", e))?;
+
+    for (col, (judul, nilai)) in [("Agama", &agama), ("Jenis Pendaftaran", &jenis_pendaftaran), ("Rombel Tujuan", &rombel)].iter().enumerate() {
+        referensi.write_string_with_format(0, col as u16, *judul, &header_format)
+            .map_err(|e| format!("Gagal menulis header referensi: {}", e))?;
+        for (row_idx, isi) in nilai.iter().enumerate() {
+            referensi.write_string((row_idx + 1) as u32, col as u16, isi)
+                .map_err(|e| format!("Gagal menulis referensi: {}", e))?;
+        }
+    }
+    referensi.autofit();
+
+    workbook.save(&file_path)
+        .map_err(|e| format!("Gagal menyimpan workbook: {}", e))?;
+
+    crate::emit_log(&app, &format!("CMD: export_template_ppdb - Template saved to {}", file_path));
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Template PPDB berhasil dibuat: {}", filename),
+        file_path: Some(file_path),
+    })
+}
@@ -1,7 +1,13 @@
-use crate::app_state::DbPool;
+use crate::app_state::{DbPool, ReferensiCache, WilayahCache};
+use crate::commands::lulusan::JenisIjazah;
+use crate::commands::siswa::{AlatTransportasi, JenisKeluar, JenisTinggal};
+use crate::commands::wilayah::ambil_index_wilayah;
 use bigdecimal::BigDecimal;
 use sqlx::types::Uuid as SqlxUuid;
 use tauri::{AppHandle, State};
+use sqlx::{Pool, Postgres};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[derive(sqlx::FromRow, serde::Serialize, Clone)]
 pub struct RombonganBelajar {
@@ -62,45 +68,37 @@ pub async fn get_all_rombels(app: AppHandle, state: State<'_, DbPool>) -> Result
 }
 
 #[tauri::command]
-pub async fn get_all_agama(app: AppHandle, state: State<'_, DbPool>) -> Result<Vec<Agama>, String> {
+pub async fn get_all_agama(app: AppHandle, state: State<'_, DbPool>, cache: State<'_, ReferensiCache>) -> Result<Vec<Agama>, String> {
     crate::emit_log(&app, "CMD: get_all_agama - Fetching...");
-    sqlx::query_as("SELECT agama_id, nama FROM ref.agama ORDER BY agama_id")
-        .fetch_all(&state.pool).await.map_err(|e| e.to_string())
+    Ok(ambil_referensi(&state.pool, &cache).await?.agama.clone())
 }
 
 #[tauri::command]
-pub async fn get_all_jenis_pendaftaran(app: AppHandle, state: State<'_, DbPool>) -> Result<Vec<JenisPendaftaran>, String> {
+pub async fn get_all_jenis_pendaftaran(app: AppHandle, state: State<'_, DbPool>, cache: State<'_, ReferensiCache>) -> Result<Vec<JenisPendaftaran>, String> {
     crate::emit_log(&app, "CMD: get_all_jenis_pendaftaran - Fetching...");
-    sqlx::query_as("SELECT jenis_pendaftaran_id, nama FROM ref.jenis_pendaftaran")
-        .fetch_all(&state.pool).await.map_err(|e| e.to_string())
+    Ok(ambil_referensi(&state.pool, &cache).await?.jenis_pendaftaran.clone())
 }
 
 #[tauri::command]
-pub async fn get_all_hobby(app: AppHandle, state: State<'_, DbPool>) -> Result<Vec<Hobby>, String> {
+pub async fn get_all_hobby(app: AppHandle, state: State<'_, DbPool>, cache: State<'_, ReferensiCache>) -> Result<Vec<Hobby>, String> {
     crate::emit_log(&app, "CMD: get_all_hobby - Fetching...");
-    sqlx::query_as("SELECT id_hobby, nm_hobby FROM ref.jenis_hobby")
-        .fetch_all(&state.pool).await.map_err(|e| e.to_string())
+    Ok(ambil_referensi(&state.pool, &cache).await?.hobby.clone())
 }
 
 #[tauri::command]
-pub async fn get_all_cita(app: AppHandle, state: State<'_, DbPool>) -> Result<Vec<Cita>, String> {
+pub async fn get_all_cita(app: AppHandle, state: State<'_, DbPool>, cache: State<'_, ReferensiCache>) -> Result<Vec<Cita>, String> {
     crate::emit_log(&app, "CMD: get_all_cita - Fetching...");
-    sqlx::query_as("SELECT id_cita, nm_cita FROM ref.jenis_cita")
-        .fetch_all(&state.pool).await.map_err(|e| e.to_string())
+    Ok(ambil_referensi(&state.pool, &cache).await?.cita.clone())
 }
 
 #[tauri::command]
-pub async fn get_wilayah_by_level_and_parent(app: AppHandle, level: i16, parent: Option<String>, state: State<'_, DbPool>) -> Result<Vec<WilayahReferensi>, String> {
+pub async fn get_wilayah_by_level_and_parent(app: AppHandle, level: i16, parent: Option<String>, state: State<'_, DbPool>, cache: State<'_, WilayahCache>) -> Result<Vec<WilayahReferensi>, String> {
     crate::emit_log(&app, &format!("CMD: get_wilayah_by_level_and_parent - level: {}, parent: {:?}", level, parent));
-    let query = if let Some(parent_kode) = parent {
-        sqlx::query_as::<_, WilayahReferensi>("SELECT kode_wilayah, nama, id_level_wilayah, mst_kode_wilayah FROM ref.mst_wilayah WHERE id_level_wilayah = $1 AND mst_kode_wilayah = $2 ORDER BY nama")
-            .bind(level)
-            .bind(parent_kode)
-    } else {
-        sqlx::query_as::<_, WilayahReferensi>("SELECT kode_wilayah, nama, id_level_wilayah, mst_kode_wilayah FROM ref.mst_wilayah WHERE id_level_wilayah = $1 ORDER BY nama")
-            .bind(level)
+    let index = ambil_index_wilayah(&state.pool, &cache).await?;
+    let wilayah = match parent {
+        Some(parent_kode) => index.anak(&parent_kode).into_iter().filter(|w| w.id_level_wilayah == level).cloned().collect(),
+        None => index.per_level(level).into_iter().cloned().collect(),
     };
-    let wilayah = query.fetch_all(&state.pool).await.map_err(|e| e.to_string())?;
     Ok(wilayah)
 }
 
@@ -116,4 +114,107 @@ pub async fn get_all_tahun_ajaran(app: AppHandle, state: State<'_, DbPool>) -> R
     crate::emit_log(&app, "CMD: get_all_tahun_ajaran - Fetching...");
     sqlx::query_as("SELECT tahun_ajaran_id, nama FROM ref.tahun_ajaran ORDER BY nama DESC")
         .fetch_all(&state.pool).await.map_err(|e| e.to_string())
-} 
\ No newline at end of file
+}
+
+/// Semua tabel ref.* yang dipakai form siswa, lulusan, dan ekspor
+#[derive(serde::Serialize, Clone)]
+pub struct ReferensiBundle {
+    pub versi_db: Option<String>,
+    pub agama: Vec<Agama>,
+    pub jenis_pendaftaran: Vec<JenisPendaftaran>,
+    pub hobby: Vec<Hobby>,
+    pub cita: Vec<Cita>,
+    pub jenis_keluar: Vec<JenisKeluar>,
+    pub jenis_tinggal: Vec<JenisTinggal>,
+    pub alat_transportasi: Vec<AlatTransportasi>,
+    pub jenis_ijazah: Vec<JenisIjazah>,
+}
+
+impl ReferensiBundle {
+    async fn muat(pool: &Pool<Postgres>) -> Result<Self, String> {
+        let versi_db = ambil_versi_db(pool).await;
+        let agama = sqlx::query_as("SELECT agama_id, nama FROM ref.agama ORDER BY agama_id")
+            .fetch_all(pool).await.map_err(|e| format!("Gagal memuat ref.agama: {}", e))?;
+        let jenis_pendaftaran = sqlx::query_as("SELECT jenis_pendaftaran_id, nama FROM ref.jenis_pendaftaran")
+            .fetch_all(pool).await.map_err(|e| format!("Gagal memuat ref.jenis_pendaftaran: {}", e))?;
+        let hobby = sqlx::query_as("SELECT id_hobby, nm_hobby FROM ref.jenis_hobby")
+            .fetch_all(pool).await.map_err(|e| format!("Gagal memuat ref.jenis_hobby: {}", e))?;
+        let cita = sqlx::query_as("SELECT id_cita, nm_cita FROM ref.jenis_cita")
+            .fetch_all(pool).await.map_err(|e| format!("Gagal memuat ref.jenis_cita: {}", e))?;
+        let jenis_keluar = sqlx::query_as("SELECT jenis_keluar_id, ket_keluar FROM ref.jenis_keluar ORDER BY ket_keluar")
+            .fetch_all(pool).await.map_err(|e| format!("Gagal memuat ref.jenis_keluar: {}", e))?;
+        let jenis_tinggal = sqlx::query_as("SELECT jenis_tinggal_id, nama FROM ref.jenis_tinggal ORDER BY nama")
+            .fetch_all(pool).await.map_err(|e| format!("Gagal memuat ref.jenis_tinggal: {}", e))?;
+        let alat_transportasi = sqlx::query_as("SELECT alat_transportasi_id, nama FROM ref.alat_transportasi ORDER BY nama")
+            .fetch_all(pool).await.map_err(|e| format!("Gagal memuat ref.alat_transportasi: {}", e))?;
+        let jenis_ijazah = sqlx::query_as::<_, (BigDecimal, String)>("SELECT jenis_ijazah_id, nama FROM ref.jenis_ijazah ORDER BY nama")
+            .fetch_all(pool).await.map_err(|e| format!("Gagal memuat ref.jenis_ijazah: {}", e))?
+            .into_iter()
+            .map(|(jenis_ijazah_id, nama)| JenisIjazah { jenis_ijazah_id, nama })
+            .collect();
+
+        Ok(Self { versi_db, agama, jenis_pendaftaran, hobby, cita, jenis_keluar, jenis_tinggal, alat_transportasi, jenis_ijazah })
+    }
+
+    pub fn nama_jenis_keluar(&self, jenis_keluar_id: &str) -> Option<&str> {
+        self.jenis_keluar.iter().find(|j| j.jenis_keluar_id.trim() == jenis_keluar_id.trim()).map(|j| j.ket_keluar.as_str())
+    }
+
+    pub fn nama_jenis_ijazah(&self, jenis_ijazah_id: &BigDecimal) -> Option<&str> {
+        self.jenis_ijazah.iter().find(|j| &j.jenis_ijazah_id == jenis_ijazah_id).map(|j| j.nama.as_str())
+    }
+}
+
+// Tanda versi database Dapodik. Isi tabel versi_db berubah setiap kali Dapodik diperbarui,
+// termasuk saat tabel ref.* ikut diganti. Bila tabel tidak terbaca, cache tetap dipakai.
+async fn ambil_versi_db(pool: &Pool<Postgres>) -> Option<String> {
+    sqlx::query_scalar::<_, Option<String>>("SELECT string_agg(v::text, ';' ORDER BY v::text) FROM versi_db v")
+        .fetch_one(pool)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Selang minimal antar pemeriksaan versi_db; di antaranya referensi dilayani langsung dari memori
+const SELANG_CEK_VERSI_DB: Duration = Duration::from_secs(300);
+
+/// Mengambil referensi dari cache. Bila cache belum terisi, atau versi_db yang diperiksa setelah selang
+/// `SELANG_CEK_VERSI_DB` sudah berubah, referensi dimuat ulang.
+pub(crate) async fn ambil_referensi(pool: &Pool<Postgres>, cache: &ReferensiCache) -> Result<Arc<ReferensiBundle>, String> {
+    let tersimpan = cache.bundle.read().map_err(|e| e.to_string())?.clone();
+    if let Some(bundle) = tersimpan {
+        let perlu_cek = cache
+            .versi_diperiksa_pada
+            .read()
+            .map_err(|e| e.to_string())?
+            .is_none_or(|waktu| waktu.elapsed() >= SELANG_CEK_VERSI_DB);
+        if !perlu_cek || bundle.versi_db.is_none() {
+            return Ok(bundle);
+        }
+        *cache.versi_diperiksa_pada.write().map_err(|e| e.to_string())? = Some(Instant::now());
+        if bundle.versi_db == ambil_versi_db(pool).await {
+            return Ok(bundle);
+        }
+    }
+    muat_ulang_referensi_cache(pool, cache).await
+}
+
+pub(crate) async fn muat_ulang_referensi_cache(pool: &Pool<Postgres>, cache: &ReferensiCache) -> Result<Arc<ReferensiBundle>, String> {
+    let bundle = Arc::new(ReferensiBundle::muat(pool).await?);
+    *cache.bundle.write().map_err(|e| e.to_string())? = Some(bundle.clone());
+    *cache.versi_diperiksa_pada.write().map_err(|e| e.to_string())? = Some(Instant::now());
+    Ok(bundle)
+}
+
+#[tauri::command]
+pub async fn get_referensi_bundle(app: AppHandle, state: State<'_, DbPool>, cache: State<'_, ReferensiCache>) -> Result<ReferensiBundle, String> {
+    crate::emit_log(&app, "CMD: get_referensi_bundle - Fetching...");
+    Ok(ambil_referensi(&state.pool, &cache).await?.as_ref().clone())
+}
+
+#[tauri::command]
+pub async fn muat_ulang_referensi(app: AppHandle, state: State<'_, DbPool>, cache: State<'_, ReferensiCache>) -> Result<String, String> {
+    crate::emit_log(&app, "CMD: muat_ulang_referensi - Memuat ulang data referensi");
+    let bundle = muat_ulang_referensi_cache(&state.pool, &cache).await?;
+    Ok(format!("Data referensi dimuat ulang (versi database: {}).", bundle.versi_db.as_deref().unwrap_or("tidak diketahui")))
+}
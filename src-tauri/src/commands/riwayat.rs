@@ -0,0 +1,212 @@
+//! Riwayat perubahan data siswa yang dibaca dari tabel audit.logged_actions milik Dapodik.
+//! Modul ini hanya membaca, tidak pernah mengubah atau menghapus jejak audit.
+
+use crate::app_state::DbPool;
+use crate::commands::export::ExportResult;
+use tauri::{AppHandle, State};
+use serde::Serialize;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::types::Uuid as SqlxUuid;
+use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, Workbook};
+use std::collections::{BTreeMap, HashMap};
+
+// Kolom teknis yang selalu berubah di setiap update sehingga tidak ditampilkan di timeline
+const KOLOM_DIABAIKAN: [&str; 4] = ["last_update", "last_sync", "updater_id", "create_date"];
+
+#[derive(Serialize)]
+pub struct RiwayatPerubahan {
+    pub waktu: NaiveDateTime,
+    pub tabel: String,
+    pub aksi: String,
+    pub kolom: String,
+    pub nilai_lama: Option<String>,
+    pub nilai_baru: Option<String>,
+    pub pengguna_db: String,
+    pub diubah_oleh: Option<String>,
+}
+
+type KolomHstore = BTreeMap<String, Option<String>>;
+
+fn decode_hstore(json: Option<String>) -> Result<KolomHstore, String> {
+    match json {
+        Some(j) => serde_json::from_str(&j).map_err(|e| format!("Gagal membaca data audit: {}", e)),
+        None => Ok(KolomHstore::new()),
+    }
+}
+
+/// Mengubah satu baris audit menjadi perubahan per kolom. Untuk UPDATE, row_data berisi nilai lama
+/// dan changed_fields berisi nilai baru; untuk INSERT dan DELETE hanya row_data yang terisi.
+fn uraikan_aksi(aksi: &str, row_data: &KolomHstore, changed_fields: &KolomHstore) -> Vec<(String, String, Option<String>, Option<String>)> {
+    let tampil = |kolom: &String| !KOLOM_DIABAIKAN.contains(&kolom.as_str());
+    match aksi {
+        "I" => row_data
+            .iter()
+            .filter(|(k, v)| tampil(k) && v.is_some())
+            .map(|(k, v)| ("Tambah".to_string(), k.clone(), None, v.clone()))
+            .collect(),
+        "U" => changed_fields
+            .iter()
+            .filter(|(k, _)| tampil(k))
+            .map(|(k, v)| ("Ubah".to_string(), k.clone(), row_data.get(k).cloned().flatten(), v.clone()))
+            .collect(),
+        "D" => vec![("Hapus".to_string(), "*".to_string(), None, None)],
+        _ => Vec::new(),
+    }
+}
+
+async fn ambil_riwayat(state: &State<'_, DbPool>, peserta_didik_id: SqlxUuid, kolom: Option<&str>) -> Result<Vec<RiwayatPerubahan>, String> {
+    let rows = sqlx::query_as::<_, (NaiveDateTime, String, String, String, Option<String>, Option<String>)>(
+        "SELECT action_tstamp_tx::timestamp, table_name, action, session_user_name,
+                hstore_to_json(row_data)::text, hstore_to_json(changed_fields)::text
+         FROM audit.logged_actions
+         WHERE table_name IN ('peserta_didik', 'registrasi_peserta_didik')
+         AND row_data ? 'peserta_didik_id' AND (row_data -> 'peserta_didik_id') = $1::text
+         ORDER BY action_tstamp_tx, event_id"
+    )
+    .bind(peserta_didik_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil riwayat perubahan: {}", e))?;
+
+    let pengguna: HashMap<String, String> = sqlx::query_as::<_, (SqlxUuid, String)>("SELECT pengguna_id, username FROM man_akses.pengguna")
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| format!("Gagal mengambil data pengguna: {}", e))?
+        .into_iter()
+        .map(|(id, username)| (id.to_string(), username))
+        .collect();
+
+    let mut riwayat = Vec::new();
+    for (waktu, tabel, aksi, pengguna_db, row_data, changed_fields) in rows {
+        let row_data = decode_hstore(row_data)?;
+        let changed_fields = decode_hstore(changed_fields)?;
+
+        // updater_id terbaru ada di changed_fields bila ikut diubah, selain itu di row_data
+        let diubah_oleh = changed_fields
+            .get("updater_id")
+            .or_else(|| row_data.get("updater_id"))
+            .cloned()
+            .flatten()
+            .map(|id| pengguna.get(&id).cloned().unwrap_or(id));
+
+        for (label_aksi, nama_kolom, nilai_lama, nilai_baru) in uraikan_aksi(&aksi, &row_data, &changed_fields) {
+            if kolom.is_some_and(|k| k != nama_kolom) {
+                continue;
+            }
+            riwayat.push(RiwayatPerubahan {
+                waktu,
+                tabel: tabel.clone(),
+                aksi: label_aksi,
+                kolom: nama_kolom,
+                nilai_lama,
+                nilai_baru,
+                pengguna_db: pengguna_db.clone(),
+                diubah_oleh: diubah_oleh.clone(),
+            });
+        }
+    }
+
+    Ok(riwayat)
+}
+
+/// Timeline perubahan per kolom untuk satu siswa, dari yang paling lama. Bila `kolom` diisi
+/// (misalnya "tanggal_lahir"), hanya perubahan kolom tersebut yang dikembalikan.
+#[tauri::command]
+pub async fn get_riwayat_perubahan_siswa(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    peserta_didik_id: SqlxUuid,
+    kolom: Option<String>,
+) -> Result<Vec<RiwayatPerubahan>, String> {
+    crate::emit_log(&app, &format!("CMD: get_riwayat_perubahan_siswa - siswa: {}, kolom: {:?}", peserta_didik_id, kolom));
+    let riwayat = ambil_riwayat(&state, peserta_didik_id, kolom.as_deref()).await?;
+    crate::emit_log(&app, &format!("CMD: get_riwayat_perubahan_siswa - Ditemukan {} perubahan", riwayat.len()));
+    Ok(riwayat)
+}
+
+#[tauri::command]
+pub async fn export_riwayat_perubahan_siswa(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    peserta_didik_id: SqlxUuid,
+    kolom: Option<String>,
+) -> Result<ExportResult, String> {
+    crate::emit_log(&app, &format!("CMD: export_riwayat_perubahan_siswa - siswa: {}", peserta_didik_id));
+
+    let nama: String = sqlx::query_scalar("SELECT nama FROM peserta_didik WHERE peserta_didik_id = $1")
+        .bind(peserta_didik_id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Siswa tidak ditemukan")?;
+
+    let riwayat = ambil_riwayat(&state, peserta_didik_id, kolom.as_deref()).await?;
+
+    // Buat nama file dengan timestamp
+    let now: DateTime<Utc> = Utc::now();
+    let timestamp = now.format("%Y%m%d_%H%M%S");
+    let nama_file_siswa: String = nama.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+    let filename = format!("riwayat_perubahan_{}_{}.xlsx", nama_file_siswa, timestamp);
+
+    let desktop_path = dirs::desktop_dir()
+        .ok_or("Tidak dapat menemukan folder Desktop")?
+        .join(&filename);
+
+    let file_path = desktop_path.to_string_lossy().to_string();
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Riwayat Perubahan")
+        .map_err(|e| format!("Gagal mengatur nama worksheet: {}", e))?;
+
+    let title_format = Format::new().set_bold();
+    let header_format = Format::new()
+        .set_bold()
+        .set_align(FormatAlign::Center)
+        .set_border(FormatBorder::Thin)
+        .set_background_color(rust_xlsxwriter::Color::Gray);
+    let data_format = Format::new()
+        .set_border(FormatBorder::Thin)
+        .set_align(FormatAlign::Left);
+
+    worksheet.write_string_with_format(0, 0, format!("Riwayat Perubahan Data: {}", nama), &title_format)
+        .map_err(|e| format!("Gagal menulis judul: {}", e))?;
+
+    let headers = ["No", "Waktu", "Tabel", "Aksi", "Kolom", "Nilai Lama", "Nilai Baru", "Pengguna DB", "Diubah Oleh"];
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(2, col as u16, *header, &header_format)
+            .map_err(|e| format!("Gagal menulis header: {}", e))?;
+    }
+
+    for (idx, r) in riwayat.iter().enumerate() {
+        let row_num = (idx + 3) as u32;
+        let nilai = [
+            (idx + 1).to_string(),
+            r.waktu.format("%d/%m/%Y %H:%M:%S").to_string(),
+            r.tabel.clone(),
+            r.aksi.clone(),
+            r.kolom.clone(),
+            r.nilai_lama.clone().unwrap_or_else(|| "-".to_string()),
+            r.nilai_baru.clone().unwrap_or_else(|| "-".to_string()),
+            r.pengguna_db.clone(),
+            r.diubah_oleh.clone().unwrap_or_else(|| "-".to_string()),
+        ];
+        for (col, isi) in nilai.iter().enumerate() {
+            worksheet.write_string_with_format(row_num, col as u16, isi, &data_format)
+                .map_err(|e| format!("Gagal menulis baris {}: {}", idx + 1, e))?;
+        }
+    }
+
+    worksheet.autofit();
+
+    workbook.save(&file_path)
+        .map_err(|e| format!("Gagal menyimpan workbook: {}", e))?;
+
+    crate::emit_log(&app, &format!("CMD: export_riwayat_perubahan_siswa - Exported {} entries to {}", riwayat.len(), file_path));
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Berhasil mengekspor {} riwayat perubahan ke file Excel: {}", riwayat.len(), filename),
+        file_path: Some(file_path),
+    })
+}
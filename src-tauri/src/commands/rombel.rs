@@ -0,0 +1,175 @@
+use crate::app_state::DbPool;
+use crate::emit_log;
+use tauri::{AppHandle, State};
+use serde::Deserialize;
+use sqlx::{Postgres, Transaction};
+use sqlx::types::Uuid as SqlxUuid;
+use bigdecimal::BigDecimal;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct PindahRombelMassalPayload {
+    pub peserta_didik_ids: Vec<SqlxUuid>,
+    pub rombongan_belajar_id_tujuan: SqlxUuid,
+    pub pengguna_id: SqlxUuid,
+}
+
+struct RombelTujuan {
+    nama: String,
+    semester_id: String,
+    tingkat_pendidikan_id: BigDecimal,
+}
+
+async fn ambil_rombel_tujuan(tx: &mut Transaction<'_, Postgres>, rombongan_belajar_id: SqlxUuid) -> Result<RombelTujuan, String> {
+    let rombel = sqlx::query_as::<_, (String, String, BigDecimal)>(
+        "SELECT rb.nama, rb.semester_id, rb.tingkat_pendidikan_id
+         FROM rombongan_belajar rb
+         JOIN ref.semester s ON rb.semester_id = s.semester_id
+         WHERE rb.rombongan_belajar_id = $1 AND rb.soft_delete = 0 AND s.periode_aktif = 1"
+    )
+    .bind(rombongan_belajar_id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| format!("Gagal mengambil rombel tujuan: {}", e))?;
+
+    match rombel {
+        Some((nama, semester_id, tingkat_pendidikan_id)) => Ok(RombelTujuan { nama, semester_id, tingkat_pendidikan_id }),
+        None => Err("Rombel tujuan tidak ditemukan di semester aktif.".to_string()),
+    }
+}
+
+/// Memindahkan satu siswa: keanggotaan lama ditutup lalu dibuat keanggotaan baru di rombel tujuan
+async fn pindahkan_siswa(
+    tx: &mut Transaction<'_, Postgres>,
+    peserta_didik_id: SqlxUuid,
+    rombongan_belajar_id_tujuan: SqlxUuid,
+    tujuan: &RombelTujuan,
+    pengguna_id: SqlxUuid,
+) -> Result<String, String> {
+    let asal = sqlx::query_as::<_, (SqlxUuid, SqlxUuid, String, String, BigDecimal, Option<BigDecimal>, String)>(
+        "SELECT ar.anggota_rombel_id, ar.rombongan_belajar_id, rb.nama, rb.semester_id, rb.tingkat_pendidikan_id,
+                ar.jenis_pendaftaran_id, pd.nama
+         FROM anggota_rombel ar
+         JOIN rombongan_belajar rb ON ar.rombongan_belajar_id = rb.rombongan_belajar_id
+         JOIN ref.semester s ON rb.semester_id = s.semester_id
+         JOIN peserta_didik pd ON ar.peserta_didik_id = pd.peserta_didik_id
+         WHERE ar.peserta_didik_id = $1 AND ar.soft_delete = 0 AND rb.soft_delete = 0
+         AND pd.soft_delete = 0 AND s.periode_aktif = 1
+         FOR UPDATE OF ar"
+    )
+    .bind(peserta_didik_id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| format!("Gagal mengambil rombel asal: {}", e))?;
+
+    let (anggota_rombel_id, rombel_asal_id, nama_rombel_asal, semester_asal, tingkat_asal, jenis_pendaftaran_id, nama_siswa) = match asal {
+        Some(a) => a,
+        None => return Err(format!("Siswa {} tidak terdaftar di rombel semester aktif.", peserta_didik_id)),
+    };
+
+    if rombel_asal_id == rombongan_belajar_id_tujuan {
+        return Err(format!("{} sudah berada di rombel {}.", nama_siswa, tujuan.nama));
+    }
+    if semester_asal != tujuan.semester_id {
+        return Err(format!("Rombel {} dan {} berada di semester berbeda.", nama_rombel_asal, tujuan.nama));
+    }
+    if tingkat_asal != tujuan.tingkat_pendidikan_id {
+        return Err(format!(
+            "{}: tingkat rombel {} ({}) berbeda dengan rombel {} ({}).",
+            nama_siswa, nama_rombel_asal, tingkat_asal, tujuan.nama, tujuan.tingkat_pendidikan_id
+        ));
+    }
+
+    sqlx::query("UPDATE anggota_rombel SET soft_delete = 1, last_update = NOW(), updater_id = $1 WHERE anggota_rombel_id = $2")
+        .bind(pengguna_id)
+        .bind(anggota_rombel_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Gagal menutup anggota rombel lama: {}", e))?;
+
+    sqlx::query(
+        "INSERT INTO anggota_rombel (anggota_rombel_id, rombongan_belajar_id, peserta_didik_id, jenis_pendaftaran_id, create_date, last_update, soft_delete, updater_id)
+         VALUES ($1, $2, $3, $4, NOW(), NOW(), 0, $5)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(rombongan_belajar_id_tujuan)
+    .bind(peserta_didik_id)
+    .bind(jenis_pendaftaran_id.unwrap_or_else(|| BigDecimal::from(1)))
+    .bind(pengguna_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Gagal menambahkan anggota rombel baru: {}", e))?;
+
+    Ok(format!("{} dari {}", nama_siswa, nama_rombel_asal))
+}
+
+/// Memastikan jumlah anggota rombel tujuan tidak melebihi kapasitas di pengaturan
+pub(crate) async fn cek_kapasitas(tx: &mut Transaction<'_, Postgres>, rombongan_belajar_id: SqlxUuid, nama_rombel: &str) -> Result<(), String> {
+    let kapasitas = match crate::commands::pengaturan::muat_pengaturan().kapasitas_rombel_maks {
+        Some(k) if k > 0 => k,
+        _ => return Ok(()),
+    };
+    let jumlah: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM anggota_rombel ar
+         JOIN peserta_didik pd ON ar.peserta_didik_id = pd.peserta_didik_id
+         WHERE ar.rombongan_belajar_id = $1 AND ar.soft_delete = 0 AND pd.soft_delete = 0"
+    )
+    .bind(rombongan_belajar_id)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| format!("Gagal menghitung anggota rombel: {}", e))?;
+
+    if jumlah > kapasitas {
+        return Err(format!("Rombel {} melebihi kapasitas: {} siswa dari maksimal {}.", nama_rombel, jumlah, kapasitas));
+    }
+    Ok(())
+}
+
+/// Memindahkan satu siswa ke rombel lain dengan tingkat yang sama di semester aktif
+#[tauri::command]
+pub async fn pindah_rombel(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    peserta_didik_id: SqlxUuid,
+    rombongan_belajar_id_tujuan: SqlxUuid,
+    pengguna_id: SqlxUuid,
+) -> Result<String, String> {
+    emit_log(&app, &format!("CMD: pindah_rombel - Memindahkan {} ke rombel {}", peserta_didik_id, rombongan_belajar_id_tujuan));
+
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    let tujuan = ambil_rombel_tujuan(&mut tx, rombongan_belajar_id_tujuan).await?;
+    let keterangan = pindahkan_siswa(&mut tx, peserta_didik_id, rombongan_belajar_id_tujuan, &tujuan, pengguna_id).await?;
+    cek_kapasitas(&mut tx, rombongan_belajar_id_tujuan, &tujuan.nama).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    emit_log(&app, &format!("CMD: pindah_rombel - Berhasil memindahkan {} ke {}", keterangan, tujuan.nama));
+    Ok(format!("Berhasil memindahkan {} ke rombel {}.", keterangan, tujuan.nama))
+}
+
+/// Memindahkan sekelompok siswa ke satu rombel tujuan; jika satu siswa gagal, semua dibatalkan
+#[tauri::command]
+pub async fn pindah_rombel_massal(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    payload: PindahRombelMassalPayload,
+) -> Result<String, String> {
+    emit_log(&app, &format!("CMD: pindah_rombel_massal - Memindahkan {} siswa ke rombel {}", payload.peserta_didik_ids.len(), payload.rombongan_belajar_id_tujuan));
+
+    if payload.peserta_didik_ids.is_empty() {
+        return Err("Tidak ada siswa yang dipilih.".to_string());
+    }
+
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    let tujuan = ambil_rombel_tujuan(&mut tx, payload.rombongan_belajar_id_tujuan).await?;
+
+    for peserta_didik_id in &payload.peserta_didik_ids {
+        let keterangan = pindahkan_siswa(&mut tx, *peserta_didik_id, payload.rombongan_belajar_id_tujuan, &tujuan, payload.pengguna_id).await?;
+        emit_log(&app, &format!("CMD: pindah_rombel_massal - {} -> {}", keterangan, tujuan.nama));
+    }
+
+    cek_kapasitas(&mut tx, payload.rombongan_belajar_id_tujuan, &tujuan.nama).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    emit_log(&app, &format!("CMD: pindah_rombel_massal - Berhasil memindahkan {} siswa ke {}", payload.peserta_didik_ids.len(), tujuan.nama));
+    Ok(format!("Berhasil memindahkan {} siswa ke rombel {}.", payload.peserta_didik_ids.len(), tujuan.nama))
+}
@@ -1,7 +1,9 @@
-use crate::app_state::DbPool;
+use crate::app_state::{DbPool, ReferensiCache};
+use crate::commands::referensi::ambil_referensi;
 use tauri::{AppHandle, State};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid as SqlxUuid;
+use sqlx::{Postgres, Transaction};
 use bigdecimal::BigDecimal;
 use uuid::Uuid;
 use rand::seq::SliceRandom;
@@ -99,19 +101,19 @@ pub struct RegistrasiSiswaPayload {
 }
 
 // Struct untuk data referensi
-#[derive(Serialize, sqlx::FromRow)]
+#[derive(Serialize, sqlx::FromRow, Clone)]
 pub struct JenisKeluar {
     pub jenis_keluar_id: String,
     pub ket_keluar: String,
 }
 
-#[derive(Serialize, sqlx::FromRow)]
+#[derive(Serialize, sqlx::FromRow, Clone)]
 pub struct JenisTinggal {
     pub jenis_tinggal_id: BigDecimal,
     pub nama: String,
 }
 
-#[derive(Serialize, sqlx::FromRow)]
+#[derive(Serialize, sqlx::FromRow, Clone)]
 pub struct AlatTransportasi {
     pub alat_transportasi_id: BigDecimal,
     pub nama: String,
@@ -179,62 +181,47 @@ pub async fn get_daftar_siswa(app: AppHandle, page: usize, page_size: usize, sea
 
 // Command untuk mengambil data referensi baru
 #[tauri::command]
-pub async fn get_all_jenis_keluar(app: AppHandle, state: State<'_, DbPool>) -> Result<Vec<JenisKeluar>, String> {
+pub async fn get_all_jenis_keluar(app: AppHandle, state: State<'_, DbPool>, cache: State<'_, ReferensiCache>) -> Result<Vec<JenisKeluar>, String> {
     crate::emit_log(&app, "CMD: get_all_jenis_keluar - Fetching all jenis keluar");
-    sqlx::query_as::<_, JenisKeluar>("SELECT jenis_keluar_id, ket_keluar FROM ref.jenis_keluar ORDER BY ket_keluar")
-        .fetch_all(&state.pool)
-        .await
-        .map_err(|e| e.to_string())
+    Ok(ambil_referensi(&state.pool, &cache).await?.jenis_keluar.clone())
 }
 
 #[tauri::command]
-pub async fn get_all_jenis_tinggal(app: AppHandle, state: State<'_, DbPool>) -> Result<Vec<JenisTinggal>, String> {
+pub async fn get_all_jenis_tinggal(app: AppHandle, state: State<'_, DbPool>, cache: State<'_, ReferensiCache>) -> Result<Vec<JenisTinggal>, String> {
     crate::emit_log(&app, "CMD: get_all_jenis_tinggal - Fetching all jenis tinggal");
-    sqlx::query_as::<_, JenisTinggal>("SELECT jenis_tinggal_id, nama FROM ref.jenis_tinggal ORDER BY nama")
-        .fetch_all(&state.pool)
-        .await
-        .map_err(|e| e.to_string())
+    Ok(ambil_referensi(&state.pool, &cache).await?.jenis_tinggal.clone())
 }
 
 #[tauri::command]
-pub async fn get_all_alat_transportasi(app: AppHandle, state: State<'_, DbPool>) -> Result<Vec<AlatTransportasi>, String> {
+pub async fn get_all_alat_transportasi(app: AppHandle, state: State<'_, DbPool>, cache: State<'_, ReferensiCache>) -> Result<Vec<AlatTransportasi>, String> {
     crate::emit_log(&app, "CMD: get_all_alat_transportasi - Fetching all alat transportasi");
-    sqlx::query_as::<_, AlatTransportasi>("SELECT alat_transportasi_id, nama FROM ref.alat_transportasi ORDER BY nama")
-        .fetch_all(&state.pool)
-        .await
-        .map_err(|e| e.to_string())
+    Ok(ambil_referensi(&state.pool, &cache).await?.alat_transportasi.clone())
 }
 
-#[tauri::command]
-pub async fn registrasi_siswa_baru(app: AppHandle, payload: RegistrasiSiswaPayload, state: State<'_, DbPool>) -> Result<String, String> {
-    crate::emit_log(&app, "CMD: registrasi_siswa_baru - Starting registration process.");
-    let mut tx = match state.pool.begin().await {
-        Ok(tx) => tx,
-        Err(e) => {
-            crate::emit_log(&app, &format!("CMD: registrasi_siswa_baru - ERROR: Gagal mulai transaksi: {}", e));
-            return Err(e.to_string());
-        }
-    };
+/// Menyimpan peserta_didik dan registrasi_peserta_didik untuk siswa baru di dalam transaksi yang diberikan.
+/// Mengembalikan peserta_didik_id yang dibuat; transaksi di-commit oleh pemanggil.
+async fn simpan_registrasi_siswa(app: &AppHandle, tx: &mut Transaction<'_, Postgres>, payload: &RegistrasiSiswaPayload, log_prefix: &str) -> Result<Uuid, String> {
     let peserta_didik_id = Uuid::new_v4();
     let tanggal_lahir_naive = match chrono::NaiveDate::parse_from_str(&payload.tanggal_lahir, "%Y-%m-%d") {
         Ok(tgl) => tgl,
         Err(e) => {
-            crate::emit_log(&app, &format!("CMD: registrasi_siswa_baru - ERROR: Format tanggal_lahir salah: {}", e));
+            crate::emit_log(app, &format!("CMD: {} - ERROR: Format tanggal_lahir salah: {}", log_prefix, e));
             return Err(format!("Format tanggal salah: {}", e));
         }
     };
-    let id_cita_final = match payload.id_cita {
+    let tanggal_masuk_sekolah_naive = chrono::NaiveDate::parse_from_str(&payload.tanggal_masuk_sekolah, "%Y-%m-%d").map_err(|e| format!("Format tanggal masuk sekolah salah: {}", e))?;
+    let id_cita_final = match payload.id_cita.clone() {
         Some(id) => id,
         None => {
-            let all_citas: Vec<(BigDecimal,)> = match sqlx::query_as("SELECT id_cita FROM ref.jenis_cita").fetch_all(&mut *tx).await {
+            let all_citas: Vec<(BigDecimal,)> = match sqlx::query_as("SELECT id_cita FROM ref.jenis_cita").fetch_all(&mut **tx).await {
                 Ok(citas) => citas,
                 Err(e) => {
-                    crate::emit_log(&app, &format!("CMD: registrasi_siswa_baru - ERROR: Gagal mengambil data cita-cita: {}", e));
+                    crate::emit_log(app, &format!("CMD: {} - ERROR: Gagal mengambil data cita-cita: {}", log_prefix, e));
                     return Err(e.to_string());
                 }
             };
             if all_citas.is_empty() {
-                crate::emit_log(&app, "CMD: registrasi_siswa_baru - ERROR: Tidak ada data referensi cita-cita untuk dipilih secara acak.");
+                crate::emit_log(app, &format!("CMD: {} - ERROR: Tidak ada data referensi cita-cita untuk dipilih secara acak.", log_prefix));
                 return Err("Tidak ada data referensi cita-cita untuk dipilih secara acak.".to_string());
             }
             all_citas.choose(&mut rand::thread_rng()).unwrap().0.clone()
@@ -282,35 +269,48 @@ pub async fn registrasi_siswa_baru(app: AppHandle, payload: RegistrasiSiswaPaylo
         .bind(&payload.nomor_telepon_rumah)
         .bind(&payload.nomor_telepon_seluler)
         .bind(&payload.email)
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await;
     if let Err(e) = insert_pd_result {
-        crate::emit_log(&app, &format!("CMD: registrasi_siswa_baru - ERROR: Gagal insert ke peserta_didik: {}", e));
-        tx.rollback().await.ok();
+        crate::emit_log(app, &format!("CMD: {} - ERROR: Gagal insert ke peserta_didik: {}", log_prefix, e));
         return Err(format!("Gagal insert ke peserta_didik: {}", e));
     }
-    let sekolah_id = Uuid::parse_str(&payload.sekolah_id).unwrap();
+    let sekolah_id = Uuid::parse_str(&payload.sekolah_id).map_err(|e| format!("sekolah_id tidak valid: {}", e))?;
     let registrasi_id = Uuid::new_v4();
+    let nipd = crate::commands::nipd::nipd_atau_otomatis(tx, payload.nipd.as_deref(), sekolah_id, registrasi_id, tanggal_masuk_sekolah_naive).await?;
     let insert_reg_pd_result = sqlx::query("INSERT INTO registrasi_peserta_didik (registrasi_id, peserta_didik_id, sekolah_id, jenis_pendaftaran_id, nipd, tanggal_masuk_sekolah, sekolah_asal, id_hobby, id_cita, a_pernah_paud, a_pernah_tk, create_date, last_update, soft_delete, updater_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NOW(), NOW(), 0, $12)")
         .bind(registrasi_id)
         .bind(peserta_didik_id)
         .bind(sekolah_id)
         .bind(&payload.jenis_pendaftaran_id)
-        .bind(&payload.nipd)
-        .bind(chrono::NaiveDate::parse_from_str(&payload.tanggal_masuk_sekolah, "%Y-%m-%d").unwrap())
+        .bind(&nipd)
+        .bind(tanggal_masuk_sekolah_naive)
         .bind(&payload.sekolah_asal)
         .bind(&payload.id_hobby)
         .bind(id_cita_final)
         .bind(&a_pernah_paud_bigdecimal)
         .bind(&a_pernah_tk_bigdecimal)
         .bind(payload.pengguna_id)
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await;
     if let Err(e) = insert_reg_pd_result {
-        crate::emit_log(&app, &format!("CMD: registrasi_siswa_baru - ERROR: Gagal insert ke registrasi_peserta_didik: {}", e));
-        tx.rollback().await.ok();
+        crate::emit_log(app, &format!("CMD: {} - ERROR: Gagal insert ke registrasi_peserta_didik: {}", log_prefix, e));
         return Err(format!("Gagal insert ke registrasi_peserta_didik: {}", e));
     }
+    Ok(peserta_didik_id)
+}
+
+#[tauri::command]
+pub async fn registrasi_siswa_baru(app: AppHandle, payload: RegistrasiSiswaPayload, state: State<'_, DbPool>) -> Result<String, String> {
+    crate::emit_log(&app, "CMD: registrasi_siswa_baru - Starting registration process.");
+    let mut tx = match state.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            crate::emit_log(&app, &format!("CMD: registrasi_siswa_baru - ERROR: Gagal mulai transaksi: {}", e));
+            return Err(e.to_string());
+        }
+    };
+    simpan_registrasi_siswa(&app, &mut tx, &payload, "registrasi_siswa_baru").await?;
     if let Err(e) = tx.commit().await {
         crate::emit_log(&app, &format!("CMD: registrasi_siswa_baru - ERROR: Gagal commit transaksi: {}", e));
         return Err(e.to_string());
@@ -319,6 +319,100 @@ pub async fn registrasi_siswa_baru(app: AppHandle, payload: RegistrasiSiswaPaylo
     Ok(format!("Siswa {} berhasil diregistrasi.", payload.nama))
 }
 
+// ref.jenis_pendaftaran: 2 = Pindahan
+const JENIS_PENDAFTARAN_PINDAHAN: i32 = 2;
+
+#[derive(Deserialize)]
+pub struct RegistrasiPindahanPayload {
+    /// Data siswa; `tanggal_masuk_sekolah` dipakai sebagai tanggal pindah masuk
+    pub siswa: RegistrasiSiswaPayload,
+    pub rombongan_belajar_id: SqlxUuid,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct SiswaMutasiMasuk {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nisn: Option<String>,
+    pub sekolah_asal: Option<String>,
+    pub tanggal_masuk_sekolah: Option<chrono::NaiveDate>,
+    pub nama_rombel: Option<String>,
+}
+
+/// Registrasi siswa pindahan (mutasi masuk) langsung ke rombel semester aktif
+#[tauri::command]
+pub async fn registrasi_siswa_pindahan(app: AppHandle, payload: RegistrasiPindahanPayload, state: State<'_, DbPool>) -> Result<String, String> {
+    crate::emit_log(&app, "CMD: registrasi_siswa_pindahan - Starting transfer-in registration.");
+    let mut siswa = payload.siswa;
+
+    let sekolah_asal = siswa.sekolah_asal.as_deref().map(str::trim).unwrap_or_default();
+    if sekolah_asal.is_empty() {
+        return Err("Sekolah asal wajib diisi untuk siswa pindahan.".to_string());
+    }
+    let nisn = siswa.nisn.as_deref().map(str::trim).unwrap_or_default().to_string();
+    crate::commands::validasi::cek_format_nisn(&nisn)?;
+    let tanggal_pindah = chrono::NaiveDate::parse_from_str(&siswa.tanggal_masuk_sekolah, "%Y-%m-%d").map_err(|e| format!("Format tanggal pindah salah: {}", e))?;
+    if tanggal_pindah > chrono::Local::now().date_naive() {
+        return Err("Tanggal pindah tidak boleh di masa depan.".to_string());
+    }
+    siswa.nisn = Some(nisn.clone());
+    siswa.jenis_pendaftaran_id = BigDecimal::from(JENIS_PENDAFTARAN_PINDAHAN);
+
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+
+    let nisn_dipakai: Option<(String,)> = sqlx::query_as("SELECT nama FROM peserta_didik WHERE nisn = $1 AND soft_delete = 0")
+        .bind(&nisn).fetch_optional(&mut *tx).await.map_err(|e| e.to_string())?;
+    if let Some((nama,)) = nisn_dipakai {
+        return Err(format!("NISN {} sudah terdaftar atas nama {}.", nisn, nama));
+    }
+
+    let rombel: Option<(String,)> = sqlx::query_as("SELECT rb.nama FROM rombongan_belajar rb JOIN ref.semester s ON rb.semester_id = s.semester_id WHERE rb.rombongan_belajar_id = $1 AND rb.soft_delete = 0 AND s.periode_aktif = 1")
+        .bind(payload.rombongan_belajar_id).fetch_optional(&mut *tx).await.map_err(|e| e.to_string())?;
+    let (nama_rombel,) = rombel.ok_or("Rombel tujuan tidak ditemukan di semester aktif.")?;
+
+    let peserta_didik_id = simpan_registrasi_siswa(&app, &mut tx, &siswa, "registrasi_siswa_pindahan").await?;
+
+    sqlx::query("INSERT INTO anggota_rombel (anggota_rombel_id, rombongan_belajar_id, peserta_didik_id, jenis_pendaftaran_id, create_date, last_update, soft_delete, updater_id) VALUES ($1, $2, $3, $4, NOW(), NOW(), 0, $5)")
+        .bind(Uuid::new_v4())
+        .bind(payload.rombongan_belajar_id)
+        .bind(peserta_didik_id)
+        .bind(&siswa.jenis_pendaftaran_id)
+        .bind(siswa.pengguna_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Gagal insert ke anggota_rombel: {}", e))?;
+
+    crate::commands::rombel::cek_kapasitas(&mut tx, payload.rombongan_belajar_id, &nama_rombel).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    crate::emit_log(&app, &format!("CMD: registrasi_siswa_pindahan - Registered {} from {} into {}", siswa.nama, sekolah_asal, nama_rombel));
+    Ok(format!("Siswa pindahan {} berhasil diregistrasi ke rombel {}.", siswa.nama, nama_rombel))
+}
+
+/// Daftar siswa pindahan yang masuk pada bulan tertentu (laporan mutasi masuk)
+#[tauri::command]
+pub async fn get_daftar_mutasi_masuk(app: AppHandle, tahun: i32, bulan: u32, state: State<'_, DbPool>) -> Result<Vec<SiswaMutasiMasuk>, String> {
+    crate::emit_log(&app, &format!("CMD: get_daftar_mutasi_masuk - Fetching transfers for {}-{:02}", tahun, bulan));
+    let awal = chrono::NaiveDate::from_ymd_opt(tahun, bulan, 1).ok_or("Bulan atau tahun tidak valid")?;
+    let akhir = awal.checked_add_months(chrono::Months::new(1)).ok_or("Bulan atau tahun tidak valid")?;
+    sqlx::query_as::<_, SiswaMutasiMasuk>("SELECT pd.peserta_didik_id, pd.nama, pd.nisn, rpd.sekolah_asal, rpd.tanggal_masuk_sekolah,
+                (SELECT rb.nama FROM anggota_rombel ar
+                 JOIN rombongan_belajar rb ON ar.rombongan_belajar_id = rb.rombongan_belajar_id
+                 WHERE ar.peserta_didik_id = pd.peserta_didik_id AND ar.soft_delete = 0 AND rb.soft_delete = 0
+                 ORDER BY rb.semester_id DESC LIMIT 1) as nama_rombel
+         FROM peserta_didik pd
+         JOIN registrasi_peserta_didik rpd ON pd.peserta_didik_id = rpd.peserta_didik_id
+         WHERE pd.soft_delete = 0 AND rpd.soft_delete = 0 AND rpd.jenis_pendaftaran_id = $1
+         AND rpd.tanggal_masuk_sekolah >= $2 AND rpd.tanggal_masuk_sekolah < $3
+         ORDER BY rpd.tanggal_masuk_sekolah, pd.nama")
+        .bind(JENIS_PENDAFTARAN_PINDAHAN)
+        .bind(awal)
+        .bind(akhir)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_siswa_by_id(app: AppHandle, peserta_didik_id: SqlxUuid, state: State<'_, DbPool>) -> Result<PesertaDidik, String> {
     crate::emit_log(&app, &format!("CMD: get_siswa_by_id - Fetching student with ID: {}", peserta_didik_id));
@@ -633,40 +727,117 @@ pub async fn update_siswa_ghost(app: AppHandle, peserta_didik_id: SqlxUuid, payl
 }
 
 #[tauri::command]
-pub async fn delete_siswa(app: AppHandle, peserta_didik_id: SqlxUuid, state: State<'_, DbPool>) -> Result<String, String> {
-    crate::emit_log(&app, &format!("CMD: delete_siswa - Deleting student with ID: {}", peserta_didik_id));
-    sqlx::query("DELETE FROM anggota_rombel WHERE peserta_didik_id = $1").bind(peserta_didik_id).execute(&state.pool).await.map_err(|e| format!("Gagal menghapus dari anggota_rombel: {}", e))?;
-    sqlx::query("DELETE FROM registrasi_peserta_didik WHERE peserta_didik_id = $1").bind(peserta_didik_id).execute(&state.pool).await.map_err(|e| format!("Gagal menghapus dari registrasi_peserta_didik: {}", e))?;
-    sqlx::query("DELETE FROM peserta_didik WHERE peserta_didik_id = $1").bind(peserta_didik_id).execute(&state.pool).await.map_err(|e| e.to_string())?;
-    crate::emit_log(&app, &format!("CMD: delete_siswa - Successfully deleted student: {}", peserta_didik_id));
-    Ok("Data siswa berhasil dihapus.".to_string())
+pub async fn delete_siswa(app: AppHandle, peserta_didik_id: SqlxUuid, pengguna_id: Option<SqlxUuid>, state: State<'_, DbPool>) -> Result<String, String> {
+    crate::emit_log(&app, &format!("CMD: delete_siswa - Soft delete student with ID: {}", peserta_didik_id));
+    state.pastikan_skema_dapomaster()?;
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    let result = sqlx::query("UPDATE peserta_didik SET soft_delete = 1, last_update = NOW(), updater_id = COALESCE($2, updater_id) WHERE peserta_didik_id = $1 AND soft_delete = 0")
+        .bind(peserta_didik_id).bind(pengguna_id).execute(&mut *tx).await.map_err(|e| format!("Gagal menghapus dari peserta_didik: {}", e))?;
+    if result.rows_affected() == 0 {
+        return Err("Data siswa tidak ditemukan atau sudah dihapus.".to_string());
+    }
+    let registrasi_ids: Vec<SqlxUuid> = sqlx::query_scalar("UPDATE registrasi_peserta_didik SET soft_delete = 1, last_update = NOW(), updater_id = COALESCE($2, updater_id) WHERE peserta_didik_id = $1 AND soft_delete = 0 RETURNING registrasi_id")
+        .bind(peserta_didik_id).bind(pengguna_id).fetch_all(&mut *tx).await.map_err(|e| format!("Gagal menghapus dari registrasi_peserta_didik: {}", e))?;
+    let anggota_rombel_ids: Vec<SqlxUuid> = sqlx::query_scalar("UPDATE anggota_rombel SET soft_delete = 1, last_update = NOW(), updater_id = COALESCE($2, updater_id) WHERE peserta_didik_id = $1 AND soft_delete = 0 RETURNING anggota_rombel_id")
+        .bind(peserta_didik_id).bind(pengguna_id).fetch_all(&mut *tx).await.map_err(|e| format!("Gagal menghapus dari anggota_rombel: {}", e))?;
+    // Id baris yang ikut terhapus dicatat agar pulihkan_siswa hanya mengaktifkan kembali baris-baris ini,
+    // bukan yang memang sudah nonaktif sebelumnya (misalnya rombel yang ditutup saat siswa keluar)
+    sqlx::query("INSERT INTO dapomaster.siswa_terhapus (peserta_didik_id, registrasi_ids, anggota_rombel_ids, dihapus_pada) VALUES ($1, $2, $3, NOW())
+         ON CONFLICT (peserta_didik_id) DO UPDATE SET registrasi_ids = EXCLUDED.registrasi_ids, anggota_rombel_ids = EXCLUDED.anggota_rombel_ids, dihapus_pada = EXCLUDED.dihapus_pada")
+        .bind(peserta_didik_id).bind(&registrasi_ids).bind(&anggota_rombel_ids).execute(&mut *tx).await.map_err(|e| format!("Gagal mencatat data yang dihapus: {}", e))?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    crate::emit_log(&app, &format!("CMD: delete_siswa - Successfully moved student to recycle bin: {}", peserta_didik_id));
+    Ok("Data siswa dipindahkan ke tempat sampah dan masih dapat dipulihkan.".to_string())
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct SiswaTerhapus {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nisn: Option<String>,
+    pub tanggal_lahir: chrono::NaiveDate,
+    pub nama_ibu_kandung: Option<String>,
+    pub tanggal_dihapus: Option<chrono::NaiveDateTime>,
 }
 
 #[tauri::command]
-pub async fn fix_desa_kelurahan_format(app: AppHandle, state: State<'_, DbPool>) -> Result<String, String> {
-    crate::emit_log(&app, "CMD: fix_desa_kelurahan_format - Memperbaiki format desa_kelurahan (STEALTH MODE)");
-    
-    // LANGKAH 1: Nonaktifkan trigger audit untuk tabel peserta_didik
-    sqlx::query("ALTER TABLE peserta_didik DISABLE TRIGGER audit_trigger_row")
-        .execute(&state.pool)
-        .await
-        .map_err(|e| format!("Gagal menonaktifkan trigger audit: {}", e))?;
-    
-    // LANGKAH 2: Update format desa_kelurahan yang salah
-    let result = sqlx::query("UPDATE peserta_didik SET desa_kelurahan = 'Desa/Kel. Panawa' WHERE desa_kelurahan = 'Panawa' OR desa_kelurahan = 'PANAWA'")
-        .execute(&state.pool)
-        .await
-        .map_err(|e| format!("Gagal update format desa_kelurahan: {}", e))?;
-    
-    // LANGKAH 3: Aktifkan kembali trigger audit
-    sqlx::query("ALTER TABLE peserta_didik ENABLE TRIGGER audit_trigger_row")
-        .execute(&state.pool)
+pub async fn list_siswa_terhapus(app: AppHandle, page: usize, page_size: usize, search: Option<String>, state: State<'_, DbPool>) -> Result<Vec<SiswaTerhapus>, String> {
+    crate::emit_log(&app, &format!("CMD: list_siswa_terhapus - Fetching page {} with search: {:?}", page, search));
+    state.pastikan_skema_dapomaster()?;
+    let offset = (page - 1) * page_size;
+    let search_term = format!("%{}%", search.unwrap_or_default());
+    // Hanya siswa yang dihapus lewat aplikasi ini; siswa yang dihapus oleh Dapodik sendiri tidak ditampilkan
+    sqlx::query_as::<_, SiswaTerhapus>("SELECT pd.peserta_didik_id, pd.nama, pd.nisn, pd.tanggal_lahir, pd.nama_ibu_kandung, st.dihapus_pada as tanggal_dihapus
+         FROM peserta_didik pd
+         JOIN dapomaster.siswa_terhapus st ON st.peserta_didik_id = pd.peserta_didik_id AND st.dihapus_permanen_pada IS NULL
+         WHERE pd.soft_delete = 1 AND (pd.nama ILIKE $1 OR pd.nisn ILIKE $1)
+         ORDER BY st.dihapus_pada DESC, pd.nama LIMIT $2 OFFSET $3")
+        .bind(&search_term)
+        .bind(page_size as i64)
+        .bind(offset as i64)
+        .fetch_all(&state.pool)
         .await
-        .map_err(|e| format!("Gagal mengaktifkan trigger audit: {}", e))?;
-    
-    let rows_affected = result.rows_affected();
-    crate::emit_log(&app, &format!("CMD: fix_desa_kelurahan_format - Berhasil memperbaiki {} data siswa (STEALTH MODE)", rows_affected));
-    Ok(format!("Berhasil memperbaiki format desa_kelurahan untuk {} data siswa (tanpa jejak audit).", rows_affected))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn pulihkan_siswa(app: AppHandle, peserta_didik_id: SqlxUuid, pengguna_id: Option<SqlxUuid>, state: State<'_, DbPool>) -> Result<String, String> {
+    crate::emit_log(&app, &format!("CMD: pulihkan_siswa - Restoring student with ID: {}", peserta_didik_id));
+    state.pastikan_skema_dapomaster()?;
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    let terhapus: Option<(String,)> = sqlx::query_as("SELECT nama FROM peserta_didik WHERE peserta_didik_id = $1 AND soft_delete = 1 FOR UPDATE")
+        .bind(peserta_didik_id).fetch_optional(&mut *tx).await.map_err(|e| e.to_string())?;
+    let (nama,) = terhapus.ok_or("Data siswa tidak ada di tempat sampah.")?;
+    // Hanya baris yang tercatat terhapus bersamaan dengan data siswa yang dipulihkan, bukan yang memang sudah
+    // nonaktif sebelumnya atau dihapus oleh Dapodik
+    let catatan: Option<(Vec<SqlxUuid>, Vec<SqlxUuid>)> = sqlx::query_as("DELETE FROM dapomaster.siswa_terhapus WHERE peserta_didik_id = $1 AND dihapus_permanen_pada IS NULL RETURNING registrasi_ids, anggota_rombel_ids")
+        .bind(peserta_didik_id).fetch_optional(&mut *tx).await.map_err(|e| format!("Gagal mengambil catatan data yang dihapus: {}", e))?;
+    let (registrasi_ids, anggota_rombel_ids) = catatan.ok_or_else(|| format!("Siswa {} tidak dihapus melalui DapoMaster, sehingga tidak dapat dipulihkan dari sini.", nama))?;
+    let registrasi = sqlx::query("UPDATE registrasi_peserta_didik SET soft_delete = 0, last_update = NOW(), updater_id = COALESCE($2, updater_id) WHERE registrasi_id = ANY($1) AND soft_delete = 1")
+        .bind(&registrasi_ids).bind(pengguna_id).execute(&mut *tx).await.map_err(|e| format!("Gagal memulihkan registrasi_peserta_didik: {}", e))?;
+    sqlx::query("UPDATE anggota_rombel SET soft_delete = 0, last_update = NOW(), updater_id = COALESCE($2, updater_id) WHERE anggota_rombel_id = ANY($1) AND soft_delete = 1")
+        .bind(&anggota_rombel_ids).bind(pengguna_id).execute(&mut *tx).await.map_err(|e| format!("Gagal memulihkan anggota_rombel: {}", e))?;
+    // Siswa tanpa registrasi aktif tidak muncul di daftar mana pun, jadi pemulihan dibatalkan seluruhnya
+    if registrasi.rows_affected() == 0 && !registrasi_ids.is_empty() {
+        return Err(format!("Data registrasi siswa {} yang terhapus tidak ditemukan, data siswa tidak dipulihkan.", nama));
+    }
+    sqlx::query("UPDATE peserta_didik SET soft_delete = 0, last_update = NOW(), updater_id = COALESCE($2, updater_id) WHERE peserta_didik_id = $1")
+        .bind(peserta_didik_id).bind(pengguna_id).execute(&mut *tx).await.map_err(|e| format!("Gagal memulihkan peserta_didik: {}", e))?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    crate::emit_log(&app, &format!("CMD: pulihkan_siswa - Successfully restored student: {}", nama));
+    Ok(format!("Data siswa {} berhasil dipulihkan.", nama))
+}
+
+/// Menghapus permanen siswa yang sudah ada di tempat sampah. Hanya boleh bila diizinkan di pengaturan,
+/// oleh operator sekolah, dan nama siswa harus diketik ulang sebagai konfirmasi. Penghapusan dicatat di
+/// `dapomaster.siswa_terhapus` sebelum baris Dapodik dihapus.
+#[tauri::command]
+pub async fn hapus_permanen(app: AppHandle, peserta_didik_id: SqlxUuid, pengguna_id: SqlxUuid, konfirmasi_nama: String, state: State<'_, DbPool>) -> Result<String, String> {
+    crate::emit_log(&app, &format!("CMD: hapus_permanen - Permanently deleting student with ID: {} by {}", peserta_didik_id, pengguna_id));
+    state.pastikan_skema_dapomaster()?;
+    if !crate::commands::pengaturan::muat_pengaturan().izinkan_hapus_permanen {
+        return Err("Hapus permanen tidak diizinkan. Aktifkan terlebih dahulu di pengaturan aplikasi.".to_string());
+    }
+    crate::commands::pengguna::pastikan_operator(&state.pool, pengguna_id).await?;
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    let nama: Option<(String, Option<String>)> = sqlx::query_as("SELECT pd.nama, pd.nisn FROM peserta_didik pd
+         JOIN dapomaster.siswa_terhapus st ON st.peserta_didik_id = pd.peserta_didik_id AND st.dihapus_permanen_pada IS NULL
+         WHERE pd.peserta_didik_id = $1 AND pd.soft_delete = 1 FOR UPDATE OF pd")
+        .bind(peserta_didik_id).fetch_optional(&mut *tx).await.map_err(|e| e.to_string())?;
+    let (nama, nisn) = nama.ok_or("Hanya data siswa di tempat sampah yang dapat dihapus permanen.")?;
+    if konfirmasi_nama.trim().to_lowercase() != nama.trim().to_lowercase() {
+        return Err("Konfirmasi nama tidak sesuai, data tidak dihapus.".to_string());
+    }
+    // Catatan penghapusan tetap disimpan setelah baris Dapodik hilang, sebagai jejak siapa yang menghapus
+    sqlx::query("UPDATE dapomaster.siswa_terhapus SET nama = $2, nisn = $3, dihapus_permanen_oleh = $4, dihapus_permanen_pada = NOW()
+         WHERE peserta_didik_id = $1")
+        .bind(peserta_didik_id).bind(&nama).bind(&nisn).bind(pengguna_id).execute(&mut *tx).await.map_err(|e| format!("Gagal mencatat penghapusan permanen: {}", e))?;
+    sqlx::query("DELETE FROM anggota_rombel WHERE peserta_didik_id = $1").bind(peserta_didik_id).execute(&mut *tx).await.map_err(|e| format!("Gagal menghapus dari anggota_rombel: {}", e))?;
+    sqlx::query("DELETE FROM registrasi_peserta_didik WHERE peserta_didik_id = $1").bind(peserta_didik_id).execute(&mut *tx).await.map_err(|e| format!("Gagal menghapus dari registrasi_peserta_didik: {}", e))?;
+    sqlx::query("DELETE FROM peserta_didik WHERE peserta_didik_id = $1").bind(peserta_didik_id).execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    crate::emit_log(&app, &format!("CMD: hapus_permanen - Successfully deleted student permanently: {}", nama));
+    Ok(format!("Data siswa {} dihapus permanen.", nama))
 }
 
 #[tauri::command]
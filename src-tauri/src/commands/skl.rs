@@ -0,0 +1,290 @@
+//! Surat Keterangan Lulus (SKL). Dokumen dibuat dari template DOCX milik sekolah (placeholder `{{kunci}}`,
+//! lihat `get_placeholder_skl`) atau langsung sebagai PDF dengan tata letak bawaan.
+
+use crate::app_state::{DbPool, ReferensiCache};
+use crate::commands::dokumen::{self, tanggal_atau_kosong, teks_atau_kosong, DokumenPdf, IdentitasSurat, Rata, LEBAR_A4};
+use crate::commands::export::ExportResult;
+use crate::commands::pengaturan::muat_pengaturan;
+use crate::commands::referensi::ambil_referensi;
+use tauri::{AppHandle, State};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use sqlx::types::Uuid as SqlxUuid;
+use bigdecimal::BigDecimal;
+use std::collections::{HashMap, HashSet};
+
+const PLACEHOLDER_SKL: [(&str, &str); 24] = [
+    ("nomor_surat", "Nomor surat, disusun dari pola nomor SKL di pengaturan"),
+    ("tanggal_surat", "Tanggal surat, misalnya 2 Juni 2025"),
+    ("nama", "Nama siswa"),
+    ("nisn", "NISN"),
+    ("nipd", "NIPD / nomor induk sekolah"),
+    ("nik", "NIK siswa"),
+    ("tempat_lahir", "Tempat lahir"),
+    ("tanggal_lahir", "Tanggal lahir"),
+    ("tempat_tanggal_lahir", "Tempat, tanggal lahir"),
+    ("jenis_kelamin", "Laki-laki atau Perempuan"),
+    ("nama_ayah", "Nama ayah"),
+    ("nama_ibu", "Nama ibu kandung"),
+    ("nama_rombel", "Rombel terakhir"),
+    ("tanggal_lulus", "Tanggal lulus"),
+    ("tahun_ajaran", "Tahun ajaran kelulusan, misalnya 2024/2025"),
+    ("jenis_ijazah", "Jenis ijazah"),
+    ("nomor_ijazah", "Nomor ijazah"),
+    ("tanggal_ijazah", "Tanggal tanda tangan ijazah"),
+    ("nama_sekolah", "Nama sekolah"),
+    ("npsn", "NPSN sekolah"),
+    ("alamat_sekolah", "Alamat sekolah"),
+    ("kepala_sekolah", "Nama kepala sekolah"),
+    ("nip_kepala_sekolah", "NIP kepala sekolah"),
+    ("tempat_surat", "Tempat penerbitan surat"),
+];
+
+#[derive(Serialize)]
+pub struct PlaceholderDokumen {
+    pub kunci: String,
+    pub keterangan: String,
+}
+
+#[derive(Deserialize)]
+pub struct GenerateSklPayload {
+    pub peserta_didik_ids: Vec<SqlxUuid>,
+    /// Nomor urut surat pertama; siswa berikutnya mendapat nomor urut selanjutnya. Harus setelah nomor terakhir
+    /// yang sudah dipakai; kosong berarti melanjutkan nomor terakhir untuk pola SKL dan tahun surat.
+    pub nomor_awal: Option<u32>,
+    pub tanggal_surat: String,
+    /// Kota/tempat penerbitan surat
+    pub tempat_surat: Option<String>,
+    pub kepala_sekolah: Option<String>,
+    pub nip_kepala_sekolah: Option<String>,
+    /// pdf: satu PDF untuk semua siswa; docx: satu DOCX per siswa (dikemas ZIP bila lebih dari satu)
+    pub format: String,
+    /// Lokasi template DOCX; kosong berarti memakai template di pengaturan
+    pub template_path: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct DataSkl {
+    peserta_didik_id: SqlxUuid,
+    #[sqlx(flatten)]
+    identitas: IdentitasSurat,
+    nama_rombel: Option<String>,
+    tanggal_lulus: Option<NaiveDate>,
+    jenis_ijazah_id: Option<BigDecimal>,
+    /// Diisi dari cache referensi setelah query
+    #[sqlx(default)]
+    jenis_ijazah: Option<String>,
+    nomor_ijazah: Option<String>,
+    tanggal_ijazah: Option<NaiveDate>,
+    penandatangan: Option<String>,
+}
+
+fn nilai_placeholder(d: &DataSkl, payload: &GenerateSklPayload, nomor_surat: &str, tanggal_surat: NaiveDate) -> HashMap<String, String> {
+    let tahun_ajaran = d.tanggal_lulus.map(|t| {
+        // Kelulusan Januari-Juni termasuk tahun ajaran yang dimulai tahun sebelumnya
+        let awal = if t.month() <= 6 { t.year() - 1 } else { t.year() };
+        format!("{}/{}", awal, awal + 1)
+    });
+
+    let mut pasangan = dokumen::placeholder_identitas(&d.identitas, nomor_surat, tanggal_surat);
+    pasangan.extend([
+        ("nama_rombel", teks_atau_kosong(&d.nama_rombel)),
+        ("tanggal_lulus", tanggal_atau_kosong(d.tanggal_lulus)),
+        ("tahun_ajaran", tahun_ajaran.unwrap_or_default()),
+        ("jenis_ijazah", teks_atau_kosong(&d.jenis_ijazah)),
+        ("nomor_ijazah", teks_atau_kosong(&d.nomor_ijazah)),
+        ("tanggal_ijazah", tanggal_atau_kosong(d.tanggal_ijazah)),
+        ("kepala_sekolah", payload.kepala_sekolah.clone().or_else(|| d.penandatangan.clone()).unwrap_or_default()),
+        ("nip_kepala_sekolah", teks_atau_kosong(&payload.nip_kepala_sekolah)),
+        ("tempat_surat", teks_atau_kosong(&payload.tempat_surat)),
+    ]);
+    pasangan.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+/// Tata letak SKL bawaan untuk satu halaman PDF
+fn tulis_halaman_skl(pdf: &mut DokumenPdf, n: &HashMap<String, String>) {
+    let v = |k: &str| n.get(k).map(String::as_str).unwrap_or_default();
+    let kiri = 70.0;
+    let lebar = LEBAR_A4 - 2.0 * kiri;
+    let tengah = LEBAR_A4 / 2.0;
+
+    pdf.halaman_baru();
+    let mut y = dokumen::kop_surat(pdf, n, kiri);
+
+    y -= 36.0;
+    pdf.teks(tengah, y, 13.0, true, Rata::Tengah, "SURAT KETERANGAN LULUS");
+    y -= 16.0;
+    pdf.teks(tengah, y, 11.0, false, Rata::Tengah, &format!("Nomor: {}", v("nomor_surat")));
+
+    y -= 36.0;
+    y = pdf.paragraf(kiri, y, lebar, 11.0, &format!(
+        "Yang bertanda tangan di bawah ini, Kepala {} menerangkan bahwa:", v("nama_sekolah")
+    ));
+
+    y -= 6.0;
+    y = dokumen::tabel_isian(pdf, kiri, y, &[
+        ("Nama", v("nama")),
+        ("Tempat, Tanggal Lahir", v("tempat_tanggal_lahir")),
+        ("Jenis Kelamin", v("jenis_kelamin")),
+        ("NISN", v("nisn")),
+        ("NIPD", v("nipd")),
+        ("Nama Orang Tua", v("nama_ayah")),
+        ("Nama Ibu Kandung", v("nama_ibu")),
+    ]);
+
+    y -= 10.0;
+    y = pdf.paragraf(kiri, y, lebar, 11.0, &format!(
+        "dinyatakan LULUS dari {} pada tahun ajaran {} terhitung tanggal {}.",
+        v("nama_sekolah"), v("tahun_ajaran"), v("tanggal_lulus")
+    ));
+    y -= 6.0;
+    y = pdf.paragraf(kiri, y, lebar, 11.0,
+        "Surat keterangan ini berlaku sementara sampai ijazah diterbitkan, dan dibuat untuk dipergunakan sebagaimana mestinya."
+    );
+    y -= 30.0;
+    dokumen::tanda_tangan(pdf, n, kiri, y);
+}
+
+/// Nomor urut SKL berikutnya untuk tanggal surat, sebagai nilai awal isian nomor di form SKL
+#[tauri::command]
+pub async fn get_nomor_awal_skl(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    tanggal_surat: String,
+) -> Result<u32, String> {
+    crate::emit_log(&app, &format!("CMD: get_nomor_awal_skl - tanggal surat: {}", tanggal_surat));
+    state.pastikan_skema_dapomaster()?;
+    let tanggal_surat = NaiveDate::parse_from_str(&tanggal_surat, "%Y-%m-%d")
+        .map_err(|e| format!("Format tanggal surat salah: {}", e))?;
+    Ok(dokumen::nomor_surat_terakhir(&state.pool, &muat_pengaturan().pola_nomor_skl, tanggal_surat.year()).await? + 1)
+}
+
+/// Daftar placeholder yang dapat dipakai di template DOCX SKL
+#[tauri::command]
+pub async fn get_placeholder_skl() -> Result<Vec<PlaceholderDokumen>, String> {
+    Ok(PLACEHOLDER_SKL
+        .iter()
+        .map(|(kunci, keterangan)| PlaceholderDokumen { kunci: format!("{{{{{}}}}}", kunci), keterangan: keterangan.to_string() })
+        .collect())
+}
+
+/// Membuat SKL untuk lulusan terpilih dengan nomor surat berurutan, diurutkan menurut nama siswa
+#[tauri::command]
+pub async fn generate_skl(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, ReferensiCache>,
+    payload: GenerateSklPayload,
+) -> Result<ExportResult, String> {
+    crate::emit_log(&app, &format!("CMD: generate_skl - {} siswa, format {}", payload.peserta_didik_ids.len(), payload.format));
+    state.pastikan_skema_dapomaster()?;
+
+    if payload.peserta_didik_ids.is_empty() {
+        return Err("Belum ada siswa yang dipilih.".to_string());
+    }
+    let tanggal_surat = NaiveDate::parse_from_str(&payload.tanggal_surat, "%Y-%m-%d")
+        .map_err(|e| format!("Format tanggal surat salah: {}", e))?;
+    let pengaturan = muat_pengaturan();
+
+    let template = match payload.format.as_str() {
+        "pdf" => None,
+        "docx" => {
+            let path = payload.template_path.clone().or(pengaturan.template_skl.clone())
+                .filter(|p| !p.trim().is_empty())
+                .ok_or("Template DOCX SKL belum dipilih")?;
+            Some(std::fs::read(&path).map_err(|e| format!("Gagal membaca template {}: {}", path, e))?)
+        }
+        lain => return Err(format!("Format '{}' tidak didukung", lain)),
+    };
+
+    let referensi = ambil_referensi(&state.pool, &cache).await?;
+    let mut siswa = sqlx::query_as::<_, DataSkl>(
+        "SELECT pd.peserta_didik_id, pd.nama, pd.nisn, rpd.nipd, pd.nik, pd.tempat_lahir, pd.tanggal_lahir, pd.jenis_kelamin::text AS jenis_kelamin,
+                pd.nama_ayah, pd.nama_ibu_kandung,
+                (SELECT rb.nama FROM anggota_rombel ar
+                 JOIN rombongan_belajar rb ON rb.rombongan_belajar_id = ar.rombongan_belajar_id
+                 WHERE ar.peserta_didik_id = pd.peserta_didik_id AND rb.soft_delete = 0
+                 ORDER BY rb.semester_id DESC, ar.soft_delete LIMIT 1) AS nama_rombel,
+                rpd.tanggal_keluar AS tanggal_lulus, ip.jenis_ijazah_id, ip.nomor AS nomor_ijazah,
+                ip.tanggal_ttd AS tanggal_ijazah, ip.penandatangan,
+                s.nama AS nama_sekolah, s.npsn,
+                CONCAT_WS(', ', NULLIF(TRIM(s.alamat_jalan), ''), NULLIF(TRIM(s.desa_kelurahan), '')) AS alamat_sekolah
+         FROM peserta_didik pd
+         JOIN registrasi_peserta_didik rpd ON rpd.peserta_didik_id = pd.peserta_didik_id AND rpd.soft_delete = 0
+         JOIN sekolah s ON s.sekolah_id = rpd.sekolah_id
+         LEFT JOIN ijazah_pd ip ON ip.registrasi_id = rpd.registrasi_id AND ip.soft_delete = 0
+         WHERE pd.peserta_didik_id = ANY($1) AND pd.soft_delete = 0 AND TRIM(rpd.jenis_keluar_id) = '1'
+         ORDER BY pd.nama"
+    )
+    .bind(&payload.peserta_didik_ids)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil data lulusan: {}", e))?;
+    for d in &mut siswa {
+        d.jenis_ijazah = d.jenis_ijazah_id.as_ref().and_then(|id| referensi.nama_jenis_ijazah(id)).map(str::to_string);
+    }
+
+    // Siswa terpilih yang bukan lulusan tidak boleh hilang diam-diam dari urutan nomor surat
+    let lulusan: HashSet<SqlxUuid> = siswa.iter().map(|d| d.peserta_didik_id).collect();
+    let bukan_lulusan: Vec<SqlxUuid> = payload.peserta_didik_ids.iter().filter(|id| !lulusan.contains(id)).copied().collect();
+    if !bukan_lulusan.is_empty() {
+        let nama: Vec<String> = sqlx::query_scalar("SELECT nama FROM peserta_didik WHERE peserta_didik_id = ANY($1) ORDER BY nama")
+            .bind(&bukan_lulusan)
+            .fetch_all(&state.pool)
+            .await
+            .map_err(|e| format!("Gagal mengambil nama siswa: {}", e))?;
+        let daftar = if nama.len() == bukan_lulusan.len() {
+            nama.join(", ")
+        } else {
+            bukan_lulusan.iter().map(SqlxUuid::to_string).collect::<Vec<_>>().join(", ")
+        };
+        return Err(format!("{} siswa terpilih belum tercatat lulus: {}", bukan_lulusan.len(), daftar));
+    }
+
+    // Nomor dipesan sebelum dokumen dibuat sehingga tidak bisa dipakai ulang oleh pembuatan SKL lain
+    let nomor_awal = dokumen::pesan_nomor_surat(
+        &state.pool, "SKL", &pengaturan.pola_nomor_skl, tanggal_surat.year(), payload.nomor_awal, siswa.len() as u32,
+    ).await?;
+
+    let mut pdf = DokumenPdf::default();
+    let mut berkas_docx = Vec::new();
+    let mut nomor_terakhir = String::new();
+    for (urutan, d) in siswa.iter().enumerate() {
+        let nomor_surat = dokumen::format_nomor_surat(&pengaturan.pola_nomor_skl, nomor_awal + urutan as u32, tanggal_surat)?;
+        let nilai = nilai_placeholder(d, &payload, &nomor_surat, tanggal_surat);
+        match &template {
+            Some(template) => {
+                let isi = dokumen::isi_template_docx(template, &nilai)?;
+                berkas_docx.push((format!("SKL_{:03}_{}.docx", urutan + 1, dokumen::nama_berkas_aman(&d.identitas.nama)), isi));
+            }
+            None => tulis_halaman_skl(&mut pdf, &nilai),
+        }
+        nomor_terakhir = nomor_surat;
+    }
+
+    // Buat nama file dengan timestamp
+    let now: DateTime<Utc> = Utc::now();
+    let timestamp = now.format("%Y%m%d_%H%M%S");
+    let (filename, isi) = match template {
+        None => (format!("skl_{}.pdf", timestamp), pdf.simpan()),
+        Some(_) if berkas_docx.len() == 1 => {
+            let (nama, isi) = berkas_docx.remove(0);
+            (nama, isi)
+        }
+        Some(_) => (format!("skl_{}.zip", timestamp), dokumen::buat_zip(&berkas_docx)?),
+    };
+
+    let desktop_path = dirs::desktop_dir()
+        .ok_or("Tidak dapat menemukan folder Desktop")?
+        .join(&filename);
+    let file_path = desktop_path.to_string_lossy().to_string();
+    std::fs::write(&desktop_path, isi).map_err(|e| format!("Gagal menyimpan dokumen: {}", e))?;
+
+    crate::emit_log(&app, &format!("CMD: generate_skl - {} SKL disimpan ke {}", siswa.len(), file_path));
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Berhasil membuat {} SKL (nomor terakhir {}): {}", siswa.len(), nomor_terakhir, filename),
+        file_path: Some(file_path),
+    })
+}
@@ -0,0 +1,368 @@
+//! Surat keterangan pindah untuk siswa yang keluar karena mutasi. Seperti SKL, surat dibuat dari template DOCX
+//! sekolah (placeholder `{{kunci}}`, lihat `get_placeholder_surat_pindah`) atau PDF bawaan dengan lampiran
+//! riwayat rombel dan, bila diminta, ringkasan nilai rapor rombel terakhir.
+
+use crate::app_state::{DbPool, ReferensiCache};
+use crate::commands::dokumen::{self, tanggal_atau_kosong, teks_atau_kosong, DokumenPdf, IdentitasSurat, Rata, LEBAR_A4, TINGGI_A4};
+use crate::commands::export::ExportResult;
+use crate::commands::nilai::{nilai_rapor_siswa, NilaiRapor};
+use crate::commands::pengaturan::muat_pengaturan;
+use crate::commands::referensi::ambil_referensi;
+use crate::commands::skl::PlaceholderDokumen;
+use tauri::{AppHandle, State};
+use serde::Deserialize;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use sqlx::types::Uuid as SqlxUuid;
+use std::collections::HashMap;
+
+/// Kode jenis keluar mutasi (pindah sekolah) di ref.jenis_keluar
+const JENIS_KELUAR_MUTASI: &str = "2";
+
+const PLACEHOLDER_SURAT_PINDAH: [(&str, &str); 28] = [
+    ("nomor_surat", "Nomor surat, disusun dari pola nomor surat pindah di pengaturan"),
+    ("tanggal_surat", "Tanggal surat, misalnya 2 Juni 2025"),
+    ("nama", "Nama siswa"),
+    ("nisn", "NISN"),
+    ("nipd", "NIPD / nomor induk sekolah"),
+    ("nik", "NIK siswa"),
+    ("tempat_lahir", "Tempat lahir"),
+    ("tanggal_lahir", "Tanggal lahir"),
+    ("tempat_tanggal_lahir", "Tempat, tanggal lahir"),
+    ("jenis_kelamin", "Laki-laki atau Perempuan"),
+    ("nama_ayah", "Nama ayah"),
+    ("nama_ibu", "Nama ibu kandung"),
+    ("nama_rombel", "Rombel terakhir"),
+    ("tingkat", "Tingkat kelas terakhir"),
+    ("semester_terakhir", "Semester rombel terakhir"),
+    ("tanggal_masuk", "Tanggal masuk sekolah"),
+    ("tanggal_keluar", "Tanggal keluar"),
+    ("alasan_keluar", "Alasan keluar, bawaan dari jenis keluar"),
+    ("sekolah_tujuan", "Nama sekolah tujuan"),
+    ("npsn_sekolah_tujuan", "NPSN sekolah tujuan"),
+    ("riwayat_rombel", "Riwayat rombel per semester dalam satu baris"),
+    ("ringkasan_nilai", "Rata-rata nilai rapor per mata pelajaran rombel terakhir (bila diminta)"),
+    ("nama_sekolah", "Nama sekolah"),
+    ("npsn", "NPSN sekolah"),
+    ("alamat_sekolah", "Alamat sekolah"),
+    ("kepala_sekolah", "Nama kepala sekolah"),
+    ("nip_kepala_sekolah", "NIP kepala sekolah"),
+    ("tempat_surat", "Tempat penerbitan surat"),
+];
+
+#[derive(Deserialize)]
+pub struct GenerateSuratPindahPayload {
+    pub peserta_didik_id: SqlxUuid,
+    /// Nomor urut surat; kosong berarti melanjutkan nomor terakhir untuk pola surat pindah dan tahun surat
+    pub nomor_urut: Option<u32>,
+    pub tanggal_surat: String,
+    pub tempat_surat: Option<String>,
+    /// Nama sekolah tujuan; kosong berarti memakai keterangan saat siswa diproses keluar
+    pub sekolah_tujuan: Option<String>,
+    pub npsn_sekolah_tujuan: Option<String>,
+    /// Alasan pindah; kosong berarti memakai nama jenis keluar
+    pub alasan: Option<String>,
+    #[serde(default)]
+    pub sertakan_nilai: bool,
+    pub kepala_sekolah: Option<String>,
+    pub nip_kepala_sekolah: Option<String>,
+    /// pdf atau docx
+    pub format: String,
+    /// Lokasi template DOCX; kosong berarti memakai template di pengaturan
+    pub template_path: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct DataSuratPindah {
+    #[sqlx(flatten)]
+    identitas: IdentitasSurat,
+    tanggal_masuk: Option<NaiveDate>,
+    tanggal_keluar: Option<NaiveDate>,
+    jenis_keluar_id: String,
+    /// Diisi dari cache referensi setelah query
+    #[sqlx(default)]
+    ket_keluar: Option<String>,
+    keterangan: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct RiwayatRombel {
+    rombongan_belajar_id: SqlxUuid,
+    nama_rombel: String,
+    tingkat_pendidikan_id: i32,
+    semester_id: String,
+    nama_semester: String,
+}
+
+/// Rata-rata nilai pengetahuan dan keterampilan satu mata pelajaran
+fn rata_nilai(n: &NilaiRapor) -> Option<f64> {
+    let nilai: Vec<f64> = [n.nilai_pengetahuan, n.nilai_keterampilan].into_iter().flatten().collect();
+    (!nilai.is_empty()).then(|| (nilai.iter().sum::<f64>() / nilai.len() as f64 * 100.0).round() / 100.0)
+}
+
+fn teks_nilai(nilai: Option<f64>) -> String {
+    nilai.map(|n| format!("{}", n)).unwrap_or_else(|| "-".to_string())
+}
+
+fn nilai_placeholder(
+    d: &DataSuratPindah,
+    riwayat: &[RiwayatRombel],
+    nilai: &[NilaiRapor],
+    payload: &GenerateSuratPindahPayload,
+    nomor_surat: &str,
+    tanggal_surat: NaiveDate,
+) -> HashMap<String, String> {
+    let isi = |t: &Option<String>| t.as_deref().map(str::trim).filter(|t| !t.is_empty()).map(str::to_string);
+    let terakhir = riwayat.last();
+
+    let mut pasangan = dokumen::placeholder_identitas(&d.identitas, nomor_surat, tanggal_surat);
+    pasangan.extend([
+        ("nama_rombel", terakhir.map(|r| r.nama_rombel.clone()).unwrap_or_default()),
+        ("tingkat", terakhir.map(|r| r.tingkat_pendidikan_id.to_string()).unwrap_or_default()),
+        ("semester_terakhir", terakhir.map(|r| r.nama_semester.clone()).unwrap_or_default()),
+        ("tanggal_masuk", tanggal_atau_kosong(d.tanggal_masuk)),
+        ("tanggal_keluar", tanggal_atau_kosong(d.tanggal_keluar)),
+        ("alasan_keluar", isi(&payload.alasan).or_else(|| d.ket_keluar.clone()).unwrap_or_default()),
+        ("sekolah_tujuan", isi(&payload.sekolah_tujuan).or_else(|| isi(&d.keterangan)).unwrap_or_default()),
+        ("npsn_sekolah_tujuan", teks_atau_kosong(&payload.npsn_sekolah_tujuan)),
+        ("riwayat_rombel", riwayat.iter().map(|r| format!("{} ({})", r.nama_rombel, r.nama_semester)).collect::<Vec<_>>().join("; ")),
+        ("ringkasan_nilai", nilai.iter().map(|n| format!("{}: {}", n.nama_mata_pelajaran, teks_nilai(rata_nilai(n)))).collect::<Vec<_>>().join("; ")),
+        ("kepala_sekolah", teks_atau_kosong(&payload.kepala_sekolah)),
+        ("nip_kepala_sekolah", teks_atau_kosong(&payload.nip_kepala_sekolah)),
+        ("tempat_surat", teks_atau_kosong(&payload.tempat_surat)),
+    ]);
+    pasangan.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+}
+
+/// Tata letak surat pindah bawaan: halaman surat dan halaman lampiran riwayat rombel serta nilai
+fn tulis_surat_pindah(pdf: &mut DokumenPdf, n: &HashMap<String, String>, riwayat: &[RiwayatRombel], nilai: &[NilaiRapor]) {
+    let v = |k: &str| n.get(k).map(String::as_str).unwrap_or_default();
+    let kiri = 70.0;
+    let lebar = LEBAR_A4 - 2.0 * kiri;
+    let tengah = LEBAR_A4 / 2.0;
+
+    pdf.halaman_baru();
+    let mut y = dokumen::kop_surat(pdf, n, kiri);
+    y -= 36.0;
+    pdf.teks(tengah, y, 13.0, true, Rata::Tengah, "SURAT KETERANGAN PINDAH");
+    y -= 16.0;
+    pdf.teks(tengah, y, 11.0, false, Rata::Tengah, &format!("Nomor: {}", v("nomor_surat")));
+
+    y -= 36.0;
+    y = pdf.paragraf(kiri, y, lebar, 11.0, &format!(
+        "Yang bertanda tangan di bawah ini, Kepala {} menerangkan bahwa:", v("nama_sekolah")
+    ));
+
+    y -= 6.0;
+    y = dokumen::tabel_isian(pdf, kiri, y, &[
+        ("Nama", v("nama")),
+        ("Tempat, Tanggal Lahir", v("tempat_tanggal_lahir")),
+        ("Jenis Kelamin", v("jenis_kelamin")),
+        ("NISN", v("nisn")),
+        ("NIPD", v("nipd")),
+        ("Nama Orang Tua", v("nama_ayah")),
+        ("Nama Ibu Kandung", v("nama_ibu")),
+        ("Kelas Terakhir", v("nama_rombel")),
+        ("Tanggal Masuk", v("tanggal_masuk")),
+        ("Tanggal Keluar", v("tanggal_keluar")),
+        ("Sekolah Tujuan", v("sekolah_tujuan")),
+        ("NPSN Sekolah Tujuan", v("npsn_sekolah_tujuan")),
+        ("Alasan Pindah", v("alasan_keluar")),
+    ]);
+
+    y -= 10.0;
+    y = pdf.paragraf(kiri, y, lebar, 11.0, &format!(
+        "telah pindah dari {} terhitung tanggal {}. Segala hak dan kewajiban siswa tersebut di sekolah kami telah diselesaikan.",
+        v("nama_sekolah"), v("tanggal_keluar")
+    ));
+    y -= 6.0;
+    y = pdf.paragraf(kiri, y, lebar, 11.0,
+        "Demikian surat keterangan ini dibuat untuk dipergunakan sebagaimana mestinya."
+    );
+    y -= 30.0;
+    dokumen::tanda_tangan(pdf, n, kiri, y);
+
+    // Lampiran: riwayat rombel dan ringkasan nilai
+    pdf.halaman_baru();
+    let mut y = dokumen::kop_surat(pdf, n, kiri);
+    y -= 30.0;
+    pdf.teks(tengah, y, 12.0, true, Rata::Tengah, "LAMPIRAN SURAT KETERANGAN PINDAH");
+    y -= 16.0;
+    pdf.teks(tengah, y, 10.0, false, Rata::Tengah, &format!("Nomor: {}", v("nomor_surat")));
+    y -= 24.0;
+    pdf.teks(kiri, y, 11.0, false, Rata::Kiri, &format!("Nama: {}    NISN: {}", v("nama"), v("nisn")));
+
+    y -= 26.0;
+    pdf.teks(kiri, y, 11.0, true, Rata::Kiri, "Riwayat Rombongan Belajar");
+    y -= 18.0;
+    pdf.teks(kiri + 10.0, y, 10.0, true, Rata::Kiri, "Semester");
+    pdf.teks(kiri + 220.0, y, 10.0, true, Rata::Kiri, "Rombel");
+    pdf.teks(kiri + 380.0, y, 10.0, true, Rata::Kiri, "Tingkat");
+    y -= 4.0;
+    pdf.garis(kiri, y, LEBAR_A4 - kiri, y);
+    y -= 14.0;
+    for r in riwayat {
+        if y < 60.0 {
+            pdf.halaman_baru();
+            y = TINGGI_A4 - 70.0;
+        }
+        pdf.teks(kiri + 10.0, y, 10.0, false, Rata::Kiri, &r.nama_semester);
+        pdf.teks(kiri + 220.0, y, 10.0, false, Rata::Kiri, &r.nama_rombel);
+        pdf.teks(kiri + 380.0, y, 10.0, false, Rata::Kiri, &r.tingkat_pendidikan_id.to_string());
+        y -= 15.0;
+    }
+
+    if !nilai.is_empty() {
+        y -= 20.0;
+        pdf.teks(kiri, y, 11.0, true, Rata::Kiri, &format!("Ringkasan Nilai Rapor {} ({})", v("nama_rombel"), v("semester_terakhir")));
+        y -= 18.0;
+        pdf.teks(kiri + 10.0, y, 10.0, true, Rata::Kiri, "Mata Pelajaran");
+        pdf.teks(kiri + 280.0, y, 10.0, true, Rata::Kiri, "Pengetahuan");
+        pdf.teks(kiri + 370.0, y, 10.0, true, Rata::Kiri, "Keterampilan");
+        y -= 4.0;
+        pdf.garis(kiri, y, LEBAR_A4 - kiri, y);
+        y -= 14.0;
+        for n in nilai {
+            // Mata pelajaran yang tidak muat dilanjutkan di halaman berikutnya
+            if y < 60.0 {
+                pdf.halaman_baru();
+                y = TINGGI_A4 - 70.0;
+            }
+            pdf.teks(kiri + 10.0, y, 10.0, false, Rata::Kiri, &n.nama_mata_pelajaran);
+            pdf.teks(kiri + 280.0, y, 10.0, false, Rata::Kiri, &teks_nilai(n.nilai_pengetahuan));
+            pdf.teks(kiri + 370.0, y, 10.0, false, Rata::Kiri, &teks_nilai(n.nilai_keterampilan));
+            y -= 15.0;
+        }
+    }
+}
+
+/// Daftar placeholder yang dapat dipakai di template DOCX surat pindah
+#[tauri::command]
+pub async fn get_placeholder_surat_pindah() -> Result<Vec<PlaceholderDokumen>, String> {
+    Ok(PLACEHOLDER_SURAT_PINDAH
+        .iter()
+        .map(|(kunci, keterangan)| PlaceholderDokumen { kunci: format!("{{{{{}}}}}", kunci), keterangan: keterangan.to_string() })
+        .collect())
+}
+
+/// Nomor urut surat pindah berikutnya untuk tanggal surat, sebagai nilai awal isian nomor di form surat pindah
+#[tauri::command]
+pub async fn get_nomor_awal_surat_pindah(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    tanggal_surat: String,
+) -> Result<u32, String> {
+    crate::emit_log(&app, &format!("CMD: get_nomor_awal_surat_pindah - tanggal surat: {}", tanggal_surat));
+    state.pastikan_skema_dapomaster()?;
+    let tanggal_surat = NaiveDate::parse_from_str(&tanggal_surat, "%Y-%m-%d")
+        .map_err(|e| format!("Format tanggal surat salah: {}", e))?;
+    Ok(dokumen::nomor_surat_terakhir(&state.pool, &muat_pengaturan().pola_nomor_surat_pindah, tanggal_surat.year()).await? + 1)
+}
+
+/// Membuat surat keterangan pindah untuk satu siswa yang sudah diproses keluar karena mutasi
+#[tauri::command]
+pub async fn generate_surat_pindah(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, ReferensiCache>,
+    payload: GenerateSuratPindahPayload,
+) -> Result<ExportResult, String> {
+    crate::emit_log(&app, &format!("CMD: generate_surat_pindah - siswa: {}, format {}", payload.peserta_didik_id, payload.format));
+    state.pastikan_skema_dapomaster()?;
+
+    let tanggal_surat = NaiveDate::parse_from_str(&payload.tanggal_surat, "%Y-%m-%d")
+        .map_err(|e| format!("Format tanggal surat salah: {}", e))?;
+    let pengaturan = muat_pengaturan();
+
+    let template = match payload.format.as_str() {
+        "pdf" => None,
+        "docx" => {
+            let path = payload.template_path.clone().or(pengaturan.template_surat_pindah.clone())
+                .filter(|p| !p.trim().is_empty())
+                .ok_or("Template DOCX surat pindah belum dipilih")?;
+            Some(std::fs::read(&path).map_err(|e| format!("Gagal membaca template {}: {}", path, e))?)
+        }
+        lain => return Err(format!("Format '{}' tidak didukung", lain)),
+    };
+
+    let referensi = ambil_referensi(&state.pool, &cache).await?;
+    let mut data = sqlx::query_as::<_, DataSuratPindah>(
+        "SELECT pd.nama, pd.nisn, rpd.nipd, pd.nik, pd.tempat_lahir, pd.tanggal_lahir, pd.jenis_kelamin::text AS jenis_kelamin,
+                pd.nama_ayah, pd.nama_ibu_kandung, rpd.tanggal_masuk_sekolah AS tanggal_masuk, rpd.tanggal_keluar,
+                TRIM(rpd.jenis_keluar_id) AS jenis_keluar_id, rpd.keterangan,
+                s.nama AS nama_sekolah, s.npsn,
+                CONCAT_WS(', ', NULLIF(TRIM(s.alamat_jalan), ''), NULLIF(TRIM(s.desa_kelurahan), '')) AS alamat_sekolah
+         FROM peserta_didik pd
+         JOIN registrasi_peserta_didik rpd ON rpd.peserta_didik_id = pd.peserta_didik_id AND rpd.soft_delete = 0
+         JOIN sekolah s ON s.sekolah_id = rpd.sekolah_id
+         WHERE pd.peserta_didik_id = $1 AND pd.soft_delete = 0 AND rpd.jenis_keluar_id IS NOT NULL"
+    )
+    .bind(payload.peserta_didik_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil data siswa keluar: {}", e))?
+    .ok_or("Siswa belum diproses keluar. Catat siswa keluar terlebih dahulu.")?;
+    data.ket_keluar = referensi.nama_jenis_keluar(&data.jenis_keluar_id).map(str::to_string);
+
+    match data.jenis_keluar_id.as_str() {
+        JENIS_KELUAR_MUTASI => {}
+        "1" => return Err(format!("Siswa {} tercatat lulus; gunakan SKL, bukan surat pindah.", data.identitas.nama)),
+        _ => return Err(format!(
+            "Surat pindah hanya untuk siswa yang keluar karena mutasi, sedangkan {} tercatat keluar karena {}.",
+            data.identitas.nama, data.ket_keluar.as_deref().unwrap_or("alasan lain")
+        )),
+    }
+
+    // Anggota rombel yang ditutup saat siswa keluar tetap termasuk riwayat
+    let riwayat = sqlx::query_as::<_, RiwayatRombel>(
+        "SELECT DISTINCT ON (rb.semester_id) rb.rombongan_belajar_id, rb.nama AS nama_rombel,
+                rb.tingkat_pendidikan_id::int AS tingkat_pendidikan_id, rb.semester_id, sm.nama AS nama_semester
+         FROM anggota_rombel ar
+         JOIN rombongan_belajar rb ON rb.rombongan_belajar_id = ar.rombongan_belajar_id AND rb.soft_delete = 0
+         JOIN ref.semester sm ON sm.semester_id = rb.semester_id
+         WHERE ar.peserta_didik_id = $1
+         ORDER BY rb.semester_id, ar.last_update DESC"
+    )
+    .bind(payload.peserta_didik_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil riwayat rombel: {}", e))?;
+
+    let nilai = match (payload.sertakan_nilai, riwayat.last()) {
+        (true, Some(r)) => nilai_rapor_siswa(&state.pool, payload.peserta_didik_id, r.rombongan_belajar_id, &r.semester_id).await?,
+        _ => Vec::new(),
+    };
+
+    // Setiap surat pindah memakai nomor baru yang dipesan sebelum dokumen dibuat; nomor yang sudah terpakai ditolak
+    let nomor_urut = dokumen::pesan_nomor_surat(
+        &state.pool, "surat pindah", &pengaturan.pola_nomor_surat_pindah, tanggal_surat.year(), payload.nomor_urut, 1,
+    ).await?;
+    let nomor_surat = dokumen::format_nomor_surat(&pengaturan.pola_nomor_surat_pindah, nomor_urut, tanggal_surat)?;
+    let isian = nilai_placeholder(&data, &riwayat, &nilai, &payload, &nomor_surat, tanggal_surat);
+
+    // Buat nama file dengan timestamp
+    let now: DateTime<Utc> = Utc::now();
+    let timestamp = now.format("%Y%m%d_%H%M%S");
+    let nama_berkas = dokumen::nama_berkas_aman(&data.identitas.nama);
+    let (filename, isi) = match template {
+        Some(template) => (format!("surat_pindah_{}_{}.docx", nama_berkas, timestamp), dokumen::isi_template_docx(&template, &isian)?),
+        None => {
+            let mut pdf = DokumenPdf::default();
+            tulis_surat_pindah(&mut pdf, &isian, &riwayat, &nilai);
+            (format!("surat_pindah_{}_{}.pdf", nama_berkas, timestamp), pdf.simpan())
+        }
+    };
+
+    let desktop_path = dirs::desktop_dir()
+        .ok_or("Tidak dapat menemukan folder Desktop")?
+        .join(&filename);
+    let file_path = desktop_path.to_string_lossy().to_string();
+    std::fs::write(&desktop_path, isi).map_err(|e| format!("Gagal menyimpan dokumen: {}", e))?;
+
+    crate::emit_log(&app, &format!("CMD: generate_surat_pindah - {} disimpan ke {}", data.identitas.nama, file_path));
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Berhasil membuat surat pindah {} nomor {}: {}", data.identitas.nama, nomor_surat, filename),
+        file_path: Some(file_path),
+    })
+}
@@ -0,0 +1,294 @@
+//! Keputusan tinggal kelas per siswa. Dapodik tidak punya kolom untuk alasan dan persetujuan tinggal kelas,
+//! sehingga catatannya disimpan di tabel milik aplikasi `dapomaster.tinggal_kelas` pada database yang sama,
+//! dikelompokkan per semester asal. Tabel dibuat saat aplikasi dimulai bila belum ada (lihat `dapomaster.rs`).
+
+use crate::app_state::DbPool;
+use crate::commands::export::ExportResult;
+use tauri::{AppHandle, State};
+use serde::Serialize;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::types::Uuid as SqlxUuid;
+use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, Workbook};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Serialize, Clone, sqlx::FromRow)]
+pub struct CatatanTinggalKelas {
+    pub peserta_didik_id: SqlxUuid,
+    pub semester_id: String,
+    pub alasan: String,
+    pub disetujui_oleh: Option<String>,
+    pub dicatat_oleh: SqlxUuid,
+    pub dicatat_pada: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct SiswaTinggalKelas {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nisn: Option<String>,
+    pub rombongan_belajar_id: Option<SqlxUuid>,
+    pub nama_rombel: Option<String>,
+    pub semester_id: String,
+    pub alasan: String,
+    pub disetujui_oleh: Option<String>,
+    pub dicatat_pada: NaiveDateTime,
+}
+
+/// Catatan tinggal kelas untuk satu semester asal, per peserta_didik_id. Menerima koneksi agar dapat
+/// dibaca di dalam transaksi naik kelas atau kelulusan.
+pub(crate) async fn tinggal_kelas_semester(
+    conn: &mut sqlx::PgConnection,
+    semester_id: &str,
+) -> Result<HashMap<SqlxUuid, CatatanTinggalKelas>, String> {
+    let catatan = sqlx::query_as::<_, CatatanTinggalKelas>(
+        "SELECT peserta_didik_id, semester_id, alasan, disetujui_oleh, dicatat_oleh, dicatat_pada
+         FROM dapomaster.tinggal_kelas WHERE semester_id = $1 AND soft_delete = 0"
+    )
+    .bind(semester_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Gagal mengambil catatan tinggal kelas: {}", e))?;
+
+    Ok(catatan.into_iter().map(|c| (c.peserta_didik_id, c)).collect())
+}
+
+async fn daftar_dengan_rombel(state: &State<'_, DbPool>, semester_id: &str, rombongan_belajar_id: Option<SqlxUuid>) -> Result<Vec<SiswaTinggalKelas>, String> {
+    let mut conn = state.pool.acquire().await.map_err(|e| e.to_string())?;
+    let catatan = tinggal_kelas_semester(&mut conn, semester_id).await?;
+    if catatan.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ids: Vec<SqlxUuid> = catatan.keys().copied().collect();
+
+    let siswa = sqlx::query_as::<_, (SqlxUuid, String, Option<String>, Option<SqlxUuid>, Option<String>)>(
+        "SELECT pd.peserta_didik_id, pd.nama, pd.nisn, rb.rombongan_belajar_id, rb.nama
+         FROM peserta_didik pd
+         LEFT JOIN LATERAL (
+             SELECT rb.rombongan_belajar_id, rb.nama FROM anggota_rombel ar
+             JOIN rombongan_belajar rb ON rb.rombongan_belajar_id = ar.rombongan_belajar_id
+             WHERE ar.peserta_didik_id = pd.peserta_didik_id AND ar.soft_delete = 0 AND rb.soft_delete = 0
+             AND rb.semester_id = $2
+             ORDER BY rb.tingkat_pendidikan_id DESC LIMIT 1
+         ) rb ON TRUE
+         WHERE pd.peserta_didik_id = ANY($1)
+         ORDER BY rb.nama, pd.nama"
+    )
+    .bind(&ids)
+    .bind(semester_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Gagal mengambil data siswa tinggal kelas: {}", e))?;
+
+    Ok(siswa
+        .into_iter()
+        .filter(|(_, _, _, rombel_id, _)| rombongan_belajar_id.is_none() || *rombel_id == rombongan_belajar_id)
+        .filter_map(|(peserta_didik_id, nama, nisn, rombel_id, nama_rombel)| {
+            let c = catatan.get(&peserta_didik_id)?;
+            Some(SiswaTinggalKelas {
+                peserta_didik_id,
+                nama,
+                nisn,
+                rombongan_belajar_id: rombel_id,
+                nama_rombel,
+                semester_id: c.semester_id.clone(),
+                alasan: c.alasan.clone(),
+                disetujui_oleh: c.disetujui_oleh.clone(),
+                dicatat_pada: c.dicatat_pada,
+            })
+        })
+        .collect())
+}
+
+/// Menandai siswa tinggal kelas pada semester asal. `proses_naik_kelas` untuk semester tersebut akan
+/// menempatkan siswa ini di rombel dengan tingkat yang sama.
+#[tauri::command]
+pub async fn tandai_tinggal_kelas(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    peserta_didik_id: SqlxUuid,
+    semester_id: String,
+    alasan: String,
+    disetujui_oleh: Option<String>,
+    pengguna_id: SqlxUuid,
+) -> Result<String, String> {
+    crate::emit_log(&app, &format!("CMD: tandai_tinggal_kelas - siswa: {}, semester: {}", peserta_didik_id, semester_id));
+    state.pastikan_skema_dapomaster()?;
+
+    if alasan.trim().is_empty() {
+        return Err("Alasan tinggal kelas wajib diisi.".to_string());
+    }
+
+    let nama: String = sqlx::query_scalar(
+        "SELECT pd.nama FROM peserta_didik pd
+         JOIN anggota_rombel ar ON ar.peserta_didik_id = pd.peserta_didik_id AND ar.soft_delete = 0
+         JOIN rombongan_belajar rb ON rb.rombongan_belajar_id = ar.rombongan_belajar_id AND rb.soft_delete = 0
+         WHERE pd.peserta_didik_id = $1 AND pd.soft_delete = 0 AND rb.semester_id = $2
+         LIMIT 1"
+    )
+    .bind(peserta_didik_id)
+    .bind(&semester_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Siswa tidak terdaftar di rombel semester {}.", semester_id))?;
+
+    // Catatan lama untuk semester yang sama diganti, jejaknya tetap tersimpan sebagai baris soft_delete
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query(
+        "UPDATE dapomaster.tinggal_kelas SET soft_delete = 1, last_update = NOW(), updater_id = $3
+         WHERE peserta_didik_id = $1 AND semester_id = $2 AND soft_delete = 0"
+    )
+    .bind(peserta_didik_id)
+    .bind(&semester_id)
+    .bind(pengguna_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal mengganti catatan tinggal kelas: {}", e))?;
+    sqlx::query(
+        "INSERT INTO dapomaster.tinggal_kelas (tinggal_kelas_id, peserta_didik_id, semester_id, alasan, disetujui_oleh,
+                                               dicatat_oleh, dicatat_pada, last_update, soft_delete, updater_id)
+         VALUES ($1, $2, $3, $4, $5, $6, NOW(), NOW(), 0, $6)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(peserta_didik_id)
+    .bind(&semester_id)
+    .bind(alasan.trim())
+    .bind(disetujui_oleh.map(|d| d.trim().to_string()).filter(|d| !d.is_empty()))
+    .bind(pengguna_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Gagal menyimpan catatan tinggal kelas: {}", e))?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(format!("{} ditandai tinggal kelas.", nama))
+}
+
+#[tauri::command]
+pub async fn batalkan_tinggal_kelas(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    peserta_didik_id: SqlxUuid,
+    semester_id: String,
+    pengguna_id: SqlxUuid,
+) -> Result<String, String> {
+    crate::emit_log(&app, &format!("CMD: batalkan_tinggal_kelas - siswa: {}, semester: {}", peserta_didik_id, semester_id));
+    state.pastikan_skema_dapomaster()?;
+
+    let dibatalkan = sqlx::query(
+        "UPDATE dapomaster.tinggal_kelas SET soft_delete = 1, last_update = NOW(), updater_id = $3
+         WHERE peserta_didik_id = $1 AND semester_id = $2 AND soft_delete = 0"
+    )
+    .bind(peserta_didik_id)
+    .bind(&semester_id)
+    .bind(pengguna_id)
+    .execute(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal membatalkan tinggal kelas: {}", e))?;
+    if dibatalkan.rows_affected() == 0 {
+        return Err("Siswa tidak tercatat tinggal kelas pada semester tersebut.".to_string());
+    }
+
+    Ok("Tanda tinggal kelas dibatalkan.".to_string())
+}
+
+/// Daftar siswa tinggal kelas untuk satu semester asal, misalnya semester genap tahun lalu
+#[tauri::command]
+pub async fn get_daftar_tinggal_kelas(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    semester_id: String,
+    rombongan_belajar_id: Option<SqlxUuid>,
+) -> Result<Vec<SiswaTinggalKelas>, String> {
+    crate::emit_log(&app, &format!("CMD: get_daftar_tinggal_kelas - semester: {}", semester_id));
+    state.pastikan_skema_dapomaster()?;
+    daftar_dengan_rombel(&state, &semester_id, rombongan_belajar_id).await
+}
+
+/// Ekspor daftar tinggal kelas per rombel, dengan kolom tanda tangan kepala sekolah
+#[tauri::command]
+pub async fn export_tinggal_kelas(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    semester_id: String,
+    rombongan_belajar_id: Option<SqlxUuid>,
+) -> Result<ExportResult, String> {
+    crate::emit_log(&app, &format!("CMD: export_tinggal_kelas - semester: {}", semester_id));
+    state.pastikan_skema_dapomaster()?;
+
+    let daftar = daftar_dengan_rombel(&state, &semester_id, rombongan_belajar_id).await?;
+    if daftar.is_empty() {
+        return Err("Tidak ada siswa tinggal kelas untuk diekspor.".to_string());
+    }
+
+    // Buat nama file dengan timestamp
+    let now: DateTime<Utc> = Utc::now();
+    let timestamp = now.format("%Y%m%d_%H%M%S");
+    let filename = format!("tinggal_kelas_{}_{}.xlsx", semester_id, timestamp);
+
+    let desktop_path = dirs::desktop_dir()
+        .ok_or("Tidak dapat menemukan folder Desktop")?
+        .join(&filename);
+
+    let file_path = desktop_path.to_string_lossy().to_string();
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Tinggal Kelas")
+        .map_err(|e| format!("Gagal mengatur nama worksheet: {}", e))?;
+
+    let title_format = Format::new().set_bold();
+    let header_format = Format::new()
+        .set_bold()
+        .set_align(FormatAlign::Center)
+        .set_border(FormatBorder::Thin)
+        .set_background_color(rust_xlsxwriter::Color::Gray);
+    let data_format = Format::new()
+        .set_border(FormatBorder::Thin)
+        .set_align(FormatAlign::Left);
+
+    worksheet.write_string_with_format(0, 0, format!("Daftar Siswa Tinggal Kelas Semester {}", semester_id), &title_format)
+        .map_err(|e| format!("Gagal menulis judul: {}", e))?;
+
+    let headers = ["No", "Nama", "NISN", "Rombel", "Alasan", "Disetujui Oleh", "Tanggal Dicatat"];
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(2, col as u16, *header, &header_format)
+            .map_err(|e| format!("Gagal menulis header: {}", e))?;
+    }
+
+    for (idx, s) in daftar.iter().enumerate() {
+        let row_num = (idx + 3) as u32;
+        let nilai = [
+            (idx + 1).to_string(),
+            s.nama.clone(),
+            s.nisn.clone().unwrap_or_else(|| "-".to_string()),
+            s.nama_rombel.clone().unwrap_or_else(|| "-".to_string()),
+            s.alasan.clone(),
+            s.disetujui_oleh.clone().unwrap_or_default(),
+            s.dicatat_pada.format("%d/%m/%Y").to_string(),
+        ];
+        for (col, isi) in nilai.iter().enumerate() {
+            worksheet.write_string_with_format(row_num, col as u16, isi, &data_format)
+                .map_err(|e| format!("Gagal menulis baris {}: {}", idx + 1, e))?;
+        }
+    }
+
+    let baris_ttd = (daftar.len() + 5) as u32;
+    worksheet.write_string(baris_ttd, 5, "Mengetahui, Kepala Sekolah")
+        .map_err(|e| format!("Gagal menulis kolom tanda tangan: {}", e))?;
+    worksheet.write_string(baris_ttd + 4, 5, "(................................)")
+        .map_err(|e| format!("Gagal menulis kolom tanda tangan: {}", e))?;
+
+    worksheet.autofit();
+
+    workbook.save(&file_path)
+        .map_err(|e| format!("Gagal menyimpan workbook: {}", e))?;
+
+    crate::emit_log(&app, &format!("CMD: export_tinggal_kelas - Exported {} records to {}", daftar.len(), file_path));
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Berhasil mengekspor {} siswa tinggal kelas ke file Excel: {}", daftar.len(), filename),
+        file_path: Some(file_path),
+    })
+}
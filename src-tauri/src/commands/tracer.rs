@@ -0,0 +1,448 @@
+//! Tracer lulusan: ke mana lulusan melanjutkan (sekolah lanjutan, bekerja, wirausaha) dan rekap angka
+//! melanjutkan per tahun lulus. Satu baris `tracer_lulusan` aktif per registrasi lulusan.
+//!
+//! Kolom yang dipakai: `registrasi_id`, `status_lanjut` (kode pada `STATUS_TRACER`), `nama_lembaga`,
+//! `jenjang_pendidikan_id` (ref.jenjang_pendidikan) dan `tanggal_mulai`. Kolom `dudi_id` belum diisi.
+
+use crate::app_state::DbPool;
+use crate::commands::export::{get_optional_date_value, get_optional_string_value, parse_tanggal_import, ExportResult, ImportError, ImportResult};
+use tauri::{AppHandle, State};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::Connection;
+use sqlx::types::Uuid as SqlxUuid;
+use bigdecimal::BigDecimal;
+use calamine::{open_workbook_auto, DataType, Reader};
+use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, Workbook};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Kode status tracer beserta label di Excel
+const STATUS_TRACER: [(&str, &str); 5] = [
+    ("melanjutkan", "Melanjutkan"),
+    ("bekerja", "Bekerja"),
+    ("wirausaha", "Wirausaha"),
+    ("mencari_kerja", "Mencari Kerja"),
+    ("tidak_diketahui", "Tidak Diketahui"),
+];
+
+const HEADER_TRACER: [&str; 9] = [
+    "ID", "No", "Nama", "NISN", "Tahun Lulus", "Status", "Nama Sekolah/Instansi Lanjutan", "Jenjang Lanjutan", "Tanggal Mulai",
+];
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct TracerLulusan {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nisn: Option<String>,
+    pub tahun_lulus: Option<i32>,
+    pub status: Option<String>,
+    pub nama_lembaga: Option<String>,
+    pub jenjang_pendidikan_id: Option<BigDecimal>,
+    pub jenjang: Option<String>,
+    pub tanggal_mulai: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+pub struct TracerPayload {
+    pub peserta_didik_id: SqlxUuid,
+    pub status: String,
+    pub nama_lembaga: Option<String>,
+    pub jenjang_pendidikan_id: Option<BigDecimal>,
+    pub tanggal_mulai: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RekapTracer {
+    pub tahun_lulus: i32,
+    pub jumlah_lulusan: i64,
+    /// Jumlah lulusan per kode status
+    pub per_status: HashMap<String, i64>,
+    pub belum_terdata: i64,
+    /// Persentase melanjutkan terhadap seluruh lulusan tahun tersebut
+    pub persen_melanjutkan: f64,
+    /// Persentase melanjutkan terhadap lulusan yang sudah terdata
+    pub persen_melanjutkan_terdata: f64,
+}
+
+fn label_status(kode: &str) -> &str {
+    STATUS_TRACER.iter().find(|(k, _)| *k == kode).map_or(kode, |(_, label)| label)
+}
+
+fn kode_status(teks: &str) -> Option<&'static str> {
+    let teks = teks.trim().to_lowercase().replace(' ', "_");
+    STATUS_TRACER
+        .iter()
+        .find(|(kode, label)| *kode == teks || label.to_lowercase().replace(' ', "_") == teks)
+        .map(|(kode, _)| *kode)
+}
+
+async fn ambil_tracer(state: &State<'_, DbPool>, tahun_lulus: Option<i32>) -> Result<Vec<TracerLulusan>, String> {
+    sqlx::query_as::<_, TracerLulusan>(
+        "SELECT pd.peserta_didik_id, pd.nama, pd.nisn, EXTRACT(YEAR FROM rpd.tanggal_keluar)::int AS tahun_lulus,
+                tl.status_lanjut AS status, tl.nama_lembaga, tl.jenjang_pendidikan_id, jp.nama AS jenjang, tl.tanggal_mulai
+         FROM peserta_didik pd
+         JOIN registrasi_peserta_didik rpd ON rpd.peserta_didik_id = pd.peserta_didik_id AND rpd.soft_delete = 0
+         LEFT JOIN tracer_lulusan tl ON tl.registrasi_id = rpd.registrasi_id AND tl.soft_delete = 0
+         LEFT JOIN ref.jenjang_pendidikan jp ON jp.jenjang_pendidikan_id = tl.jenjang_pendidikan_id
+         WHERE pd.soft_delete = 0 AND TRIM(rpd.jenis_keluar_id) = '1'
+         AND ($1::int IS NULL OR EXTRACT(YEAR FROM rpd.tanggal_keluar)::int = $1)
+         ORDER BY rpd.tanggal_keluar DESC NULLS LAST, pd.nama"
+    )
+    .bind(tahun_lulus)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil data tracer lulusan: {}", e))
+}
+
+/// Menyimpan tracer satu lulusan: mengubah baris yang ada atau menambah baris baru
+async fn simpan_satu_tracer(
+    conn: &mut sqlx::PgConnection,
+    peserta_didik_id: SqlxUuid,
+    status: &str,
+    nama_lembaga: Option<&str>,
+    jenjang_pendidikan_id: Option<&BigDecimal>,
+    tanggal_mulai: Option<NaiveDate>,
+    pengguna_id: SqlxUuid,
+) -> Result<(), String> {
+    let registrasi_id: SqlxUuid = sqlx::query_scalar(
+        "SELECT registrasi_id FROM registrasi_peserta_didik
+         WHERE peserta_didik_id = $1 AND soft_delete = 0 AND TRIM(jenis_keluar_id) = '1'"
+    )
+    .bind(peserta_didik_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or("Siswa tidak tercatat lulus")?;
+
+    let diupdate = sqlx::query(
+        "UPDATE tracer_lulusan
+         SET status_lanjut = $1, nama_lembaga = $2, jenjang_pendidikan_id = $3, tanggal_mulai = $4,
+             last_update = NOW(), updater_id = $5
+         WHERE registrasi_id = $6 AND soft_delete = 0"
+    )
+    .bind(status)
+    .bind(nama_lembaga)
+    .bind(jenjang_pendidikan_id)
+    .bind(tanggal_mulai)
+    .bind(pengguna_id)
+    .bind(registrasi_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Gagal mengupdate tracer_lulusan: {}", e))?
+    .rows_affected();
+
+    if diupdate == 0 {
+        sqlx::query(
+            "INSERT INTO tracer_lulusan (tracer_lulusan_id, registrasi_id, status_lanjut, nama_lembaga, jenjang_pendidikan_id, tanggal_mulai,
+                                         create_date, last_update, soft_delete, updater_id)
+             VALUES ($1, $2, $3, $4, $5, $6, NOW(), NOW(), 0, $7)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(registrasi_id)
+        .bind(status)
+        .bind(nama_lembaga)
+        .bind(jenjang_pendidikan_id)
+        .bind(tanggal_mulai)
+        .bind(pengguna_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Gagal menambah tracer_lulusan: {}", e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_daftar_tracer_lulusan(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    tahun_lulus: Option<i32>,
+) -> Result<Vec<TracerLulusan>, String> {
+    crate::emit_log(&app, &format!("CMD: get_daftar_tracer_lulusan - tahun lulus: {:?}", tahun_lulus));
+    let data = ambil_tracer(&state, tahun_lulus).await?;
+    crate::emit_log(&app, &format!("CMD: get_daftar_tracer_lulusan - {} lulusan", data.len()));
+    Ok(data)
+}
+
+#[tauri::command]
+pub async fn simpan_tracer_lulusan(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    payload: TracerPayload,
+    pengguna_id: SqlxUuid,
+) -> Result<String, String> {
+    crate::emit_log(&app, &format!("CMD: simpan_tracer_lulusan - siswa: {}", payload.peserta_didik_id));
+
+    let status = kode_status(&payload.status).ok_or_else(|| format!("Status tracer '{}' tidak dikenal", payload.status))?;
+    let tanggal_mulai = match payload.tanggal_mulai.as_deref().filter(|t| !t.trim().is_empty()) {
+        Some(t) => Some(NaiveDate::parse_from_str(t, "%Y-%m-%d").map_err(|e| format!("Format tanggal mulai salah: {}", e))?),
+        None => None,
+    };
+
+    let mut conn = state.pool.acquire().await.map_err(|e| e.to_string())?;
+    simpan_satu_tracer(
+        &mut conn,
+        payload.peserta_didik_id,
+        status,
+        payload.nama_lembaga.as_deref().map(str::trim).filter(|n| !n.is_empty()),
+        payload.jenjang_pendidikan_id.as_ref(),
+        tanggal_mulai,
+        pengguna_id,
+    )
+    .await?;
+
+    Ok("Data tracer lulusan berhasil disimpan.".to_string())
+}
+
+/// Rekap status lanjutan per tahun lulus, termasuk persentase melanjutkan untuk laporan dinas
+#[tauri::command]
+pub async fn get_rekap_tracer_lulusan(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+) -> Result<Vec<RekapTracer>, String> {
+    crate::emit_log(&app, "CMD: get_rekap_tracer_lulusan - Menyusun rekap tracer lulusan");
+
+    let rows = sqlx::query_as::<_, (i32, Option<String>, i64)>(
+        "SELECT EXTRACT(YEAR FROM rpd.tanggal_keluar)::int AS tahun_lulus, tl.status_lanjut, COUNT(*)
+         FROM registrasi_peserta_didik rpd
+         JOIN peserta_didik pd ON pd.peserta_didik_id = rpd.peserta_didik_id AND pd.soft_delete = 0
+         LEFT JOIN tracer_lulusan tl ON tl.registrasi_id = rpd.registrasi_id AND tl.soft_delete = 0
+         WHERE rpd.soft_delete = 0 AND TRIM(rpd.jenis_keluar_id) = '1' AND rpd.tanggal_keluar IS NOT NULL
+         GROUP BY 1, 2
+         ORDER BY 1 DESC"
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal menyusun rekap tracer lulusan: {}", e))?;
+
+    let mut rekap: Vec<RekapTracer> = Vec::new();
+    for (tahun_lulus, status, jumlah) in rows {
+        if rekap.last().is_none_or(|r| r.tahun_lulus != tahun_lulus) {
+            rekap.push(RekapTracer {
+                tahun_lulus,
+                jumlah_lulusan: 0,
+                per_status: STATUS_TRACER.iter().map(|(kode, _)| (kode.to_string(), 0)).collect(),
+                belum_terdata: 0,
+                persen_melanjutkan: 0.0,
+                persen_melanjutkan_terdata: 0.0,
+            });
+        }
+        let Some(r) = rekap.last_mut() else { continue };
+        r.jumlah_lulusan += jumlah;
+        match status {
+            Some(kode) => *r.per_status.entry(kode).or_insert(0) += jumlah,
+            None => r.belum_terdata += jumlah,
+        }
+    }
+
+    for r in &mut rekap {
+        let melanjutkan = r.per_status.get("melanjutkan").copied().unwrap_or(0) as f64;
+        let terdata = r.jumlah_lulusan - r.belum_terdata;
+        if r.jumlah_lulusan > 0 {
+            r.persen_melanjutkan = (melanjutkan * 1000.0 / r.jumlah_lulusan as f64).round() / 10.0;
+        }
+        if terdata > 0 {
+            r.persen_melanjutkan_terdata = (melanjutkan * 1000.0 / terdata as f64).round() / 10.0;
+        }
+    }
+
+    Ok(rekap)
+}
+
+#[tauri::command]
+pub async fn export_tracer_lulusan(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    tahun_lulus: Option<i32>,
+) -> Result<ExportResult, String> {
+    crate::emit_log(&app, &format!("CMD: export_tracer_lulusan - tahun lulus: {:?}", tahun_lulus));
+
+    let data = ambil_tracer(&state, tahun_lulus).await?;
+
+    // Buat nama file dengan timestamp
+    let now: DateTime<Utc> = Utc::now();
+    let timestamp = now.format("%Y%m%d_%H%M%S");
+    let filename = match tahun_lulus {
+        Some(tahun) => format!("tracer_lulusan_{}_{}.xlsx", tahun, timestamp),
+        None => format!("tracer_lulusan_{}.xlsx", timestamp),
+    };
+
+    let desktop_path = dirs::desktop_dir()
+        .ok_or("Tidak dapat menemukan folder Desktop")?
+        .join(&filename);
+
+    let file_path = desktop_path.to_string_lossy().to_string();
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Tracer Lulusan")
+        .map_err(|e| format!("Gagal mengatur nama worksheet: {}", e))?;
+
+    let header_format = Format::new()
+        .set_bold()
+        .set_align(FormatAlign::Center)
+        .set_border(FormatBorder::Thin)
+        .set_background_color(rust_xlsxwriter::Color::Gray);
+    let data_format = Format::new()
+        .set_border(FormatBorder::Thin)
+        .set_align(FormatAlign::Left);
+
+    for (col, header) in HEADER_TRACER.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *header, &header_format)
+            .map_err(|e| format!("Gagal menulis header: {}", e))?;
+    }
+
+    for (idx, t) in data.iter().enumerate() {
+        let row_num = (idx + 1) as u32;
+        let nilai = [
+            t.peserta_didik_id.to_string(),
+            (idx + 1).to_string(),
+            t.nama.clone(),
+            t.nisn.clone().unwrap_or_default(),
+            t.tahun_lulus.map(|th| th.to_string()).unwrap_or_default(),
+            t.status.as_deref().map(label_status).unwrap_or_default().to_string(),
+            t.nama_lembaga.clone().unwrap_or_default(),
+            t.jenjang.clone().unwrap_or_default(),
+            t.tanggal_mulai.map(|tgl| tgl.format("%d/%m/%Y").to_string()).unwrap_or_default(),
+        ];
+        for (col, isi) in nilai.iter().enumerate() {
+            worksheet.write_string_with_format(row_num, col as u16, isi, &data_format)
+                .map_err(|e| format!("Gagal menulis baris {}: {}", idx + 1, e))?;
+        }
+    }
+
+    // Lembar pilihan status agar operator tahu nilai yang diterima saat impor
+    let pilihan = workbook.add_worksheet();
+    pilihan.set_name("Pilihan Status")
+        .map_err(|e| format!("Gagal mengatur nama worksheet: {}", e))?;
+    pilihan.write_string_with_format(0, 0, "Status", &header_format)
+        .map_err(|e| format!("Gagal menulis header: {}", e))?;
+    for (idx, (_, label)) in STATUS_TRACER.iter().enumerate() {
+        pilihan.write_string((idx + 1) as u32, 0, *label)
+            .map_err(|e| format!("Gagal menulis pilihan status: {}", e))?;
+    }
+
+    let worksheet = workbook.worksheet_from_index(0).map_err(|e| e.to_string())?;
+    // Kolom ID dipakai saat impor, disembunyikan agar tidak diubah operator
+    worksheet.set_column_hidden(0).map_err(|e| e.to_string())?;
+    worksheet.autofit();
+
+    workbook.save(&file_path)
+        .map_err(|e| format!("Gagal menyimpan workbook: {}", e))?;
+
+    crate::emit_log(&app, &format!("CMD: export_tracer_lulusan - Exported {} records to {}", data.len(), file_path));
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Berhasil mengekspor {} data tracer lulusan ke file Excel: {}", data.len(), filename),
+        file_path: Some(file_path),
+    })
+}
+
+/// Impor isian tracer dari file hasil `export_tracer_lulusan`. Baris tanpa status dilewati.
+#[tauri::command]
+pub async fn import_tracer_lulusan_from_excel(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    file_path: String,
+    pengguna_id: SqlxUuid,
+) -> Result<ImportResult, String> {
+    crate::emit_log(&app, &format!("CMD: import_tracer_lulusan_from_excel - file: {}", file_path));
+
+    let mut workbook = open_workbook_auto(&file_path)
+        .map_err(|e| format!("Gagal membuka file Excel: {}", e))?;
+
+    let range = workbook.worksheet_range_at(0)
+        .ok_or("Tidak dapat menemukan worksheet")?
+        .map_err(|e| format!("Gagal membaca worksheet: {}", e))?;
+
+    let header: Vec<String> = range.rows().next().unwrap_or_default().iter().map(|c| c.to_string().trim().to_string()).collect();
+    if header.len() < HEADER_TRACER.len() || header.iter().zip(HEADER_TRACER).any(|(h, e)| h != e) {
+        return Err("Format file tidak sesuai. Gunakan file hasil ekspor tracer lulusan.".to_string());
+    }
+
+    let jenjang: HashMap<String, BigDecimal> = sqlx::query_as::<_, (BigDecimal, String)>(
+        "SELECT jenjang_pendidikan_id, nama FROM ref.jenjang_pendidikan WHERE expired_date IS NULL"
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil referensi jenjang: {}", e))?
+    .into_iter()
+    .map(|(id, nama)| (nama.trim().to_lowercase(), id))
+    .collect();
+
+    let mut errors = Vec::new();
+    let mut total_rows = 0;
+    let mut success_count = 0;
+    let mut error_count = 0;
+    let mut tx = state.pool.begin().await.map_err(|e| e.to_string())?;
+
+    for (row_idx, row) in range.rows().skip(1).enumerate() {
+        let excel_row = row_idx + 2;
+        let kosong = DataType::Empty;
+        let cell = |idx: usize| row.get(idx).unwrap_or(&kosong);
+        let Some(status_teks) = get_optional_string_value(cell(5), "Status", excel_row, &mut errors) else {
+            continue;
+        };
+        total_rows += 1;
+        let jumlah_error_awal = errors.len();
+        let push = |errors: &mut Vec<ImportError>, field: &str, message: String| {
+            errors.push(ImportError { row: excel_row as i32, field: field.to_string(), message });
+        };
+
+        let peserta_didik_id = SqlxUuid::parse_str(cell(0).to_string().trim()).ok();
+        if peserta_didik_id.is_none() {
+            push(&mut errors, "ID", "ID siswa tidak valid, jangan ubah kolom ID".to_string());
+        }
+        let status = kode_status(&status_teks);
+        if status.is_none() {
+            push(&mut errors, "Status", format!("Status '{}' tidak dikenal", status_teks));
+        }
+        let nama_lembaga = get_optional_string_value(cell(6), "Nama Sekolah/Instansi Lanjutan", excel_row, &mut errors);
+        let jenjang_pendidikan_id = match get_optional_string_value(cell(7), "Jenjang Lanjutan", excel_row, &mut errors) {
+            Some(nama) => {
+                let id = jenjang.get(&nama.trim().to_lowercase()).cloned();
+                if id.is_none() {
+                    push(&mut errors, "Jenjang Lanjutan", format!("Jenjang '{}' tidak ditemukan", nama));
+                }
+                id
+            }
+            None => None,
+        };
+        let tanggal_mulai = get_optional_date_value(cell(8), "Tanggal Mulai", excel_row, &mut errors)
+            .and_then(|t| parse_tanggal_import(&t));
+
+        if errors.len() > jumlah_error_awal {
+            error_count += 1;
+            continue;
+        }
+        let (Some(peserta_didik_id), Some(status)) = (peserta_didik_id, status) else { continue };
+
+        // Savepoint per baris: baris yang gagal dibatalkan sendiri dan baris berikutnya tetap diproses
+        let mut sp = tx.begin().await.map_err(|e| e.to_string())?;
+        match simpan_satu_tracer(&mut sp, peserta_didik_id, status, nama_lembaga.as_deref(), jenjang_pendidikan_id.as_ref(), tanggal_mulai, pengguna_id).await {
+            Ok(()) => {
+                sp.commit().await.map_err(|e| e.to_string())?;
+                success_count += 1;
+            }
+            Err(e) => {
+                sp.rollback().await.map_err(|e| e.to_string())?;
+                push(&mut errors, "Simpan", e);
+                error_count += 1;
+            }
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    crate::emit_log(&app, &format!("CMD: import_tracer_lulusan_from_excel - {} berhasil, {} gagal", success_count, error_count));
+
+    Ok(ImportResult {
+        success: error_count == 0,
+        message: format!("Import selesai. {} baris berhasil, {} baris bermasalah.", success_count, error_count),
+        total_rows,
+        success_count,
+        error_count,
+        errors,
+    })
+}
@@ -0,0 +1,253 @@
+//! Laporan anomali usia: siswa aktif yang usianya tidak wajar untuk tingkat rombelnya.
+
+use crate::app_state::DbPool;
+use crate::commands::export::ExportResult;
+use crate::commands::pengaturan::{muat_pengaturan, RentangUsiaJenjang};
+use tauri::{AppHandle, State};
+use serde::Serialize;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use sqlx::types::Uuid as SqlxUuid;
+use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, Workbook};
+
+#[derive(Serialize)]
+pub struct AnomaliUsia {
+    pub peserta_didik_id: SqlxUuid,
+    pub nama: String,
+    pub nisn: Option<String>,
+    pub tanggal_lahir: NaiveDate,
+    pub nama_rombel: String,
+    pub tingkat_pendidikan_id: i32,
+    pub usia: i32,
+    pub usia_min: i32,
+    pub usia_maks: i32,
+    /// terlalu_muda atau terlalu_tua
+    pub status: String,
+    /// Tahun lahir alternatif bila kemungkinan salah ketik satu digit dekade, misalnya 2019 untuk 2009
+    pub saran_tahun_lahir: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct LaporanAnomaliUsia {
+    pub tanggal_acuan: NaiveDate,
+    pub total_diperiksa: i32,
+    pub tanpa_rentang: i32,
+    pub anomali: Vec<AnomaliUsia>,
+}
+
+fn rentang_tingkat(rentang: &[RentangUsiaJenjang], tingkat: i32) -> Option<(i32, i32)> {
+    rentang
+        .iter()
+        .find(|r| (r.tingkat_awal..=r.tingkat_akhir).contains(&tingkat))
+        .map(|r| {
+            let selisih = tingkat - r.tingkat_awal;
+            (r.usia_min + selisih, r.usia_maks + selisih)
+        })
+}
+
+/// Usia dalam tahun penuh pada tanggal acuan
+fn hitung_usia(tanggal_lahir: NaiveDate, tanggal_acuan: NaiveDate) -> i32 {
+    let mut usia = tanggal_acuan.year() - tanggal_lahir.year();
+    if (tanggal_acuan.month(), tanggal_acuan.day()) < (tanggal_lahir.month(), tanggal_lahir.day()) {
+        usia -= 1;
+    }
+    usia
+}
+
+async fn susun_laporan(state: &State<'_, DbPool>, tanggal_acuan: Option<String>) -> Result<LaporanAnomaliUsia, String> {
+    let tanggal_acuan = match tanggal_acuan {
+        Some(t) => NaiveDate::parse_from_str(&t, "%Y-%m-%d").map_err(|e| format!("Format tanggal acuan salah: {}", e))?,
+        None => {
+            // Bawaan: 1 Juli tahun ajaran semester aktif
+            let tahun_ajaran: i32 = sqlx::query_scalar("SELECT tahun_ajaran_id::int FROM ref.semester WHERE periode_aktif = 1 LIMIT 1")
+                .fetch_optional(&state.pool)
+                .await
+                .map_err(|e| format!("Gagal mengambil semester aktif: {}", e))?
+                .ok_or("Semester aktif tidak ditemukan")?;
+            NaiveDate::from_ymd_opt(tahun_ajaran, 7, 1).ok_or("Tahun ajaran tidak valid")?
+        }
+    };
+
+    let siswa = sqlx::query_as::<_, (SqlxUuid, String, Option<String>, NaiveDate, String, i32)>(
+        "SELECT pd.peserta_didik_id, pd.nama, pd.nisn, pd.tanggal_lahir, rb.nama, rb.tingkat_pendidikan_id::int
+         FROM anggota_rombel ar
+         JOIN rombongan_belajar rb ON rb.rombongan_belajar_id = ar.rombongan_belajar_id
+         JOIN ref.semester s ON rb.semester_id = s.semester_id
+         JOIN peserta_didik pd ON pd.peserta_didik_id = ar.peserta_didik_id
+         JOIN registrasi_peserta_didik rpd ON rpd.peserta_didik_id = pd.peserta_didik_id
+         WHERE s.periode_aktif = 1 AND ar.soft_delete = 0 AND rb.soft_delete = 0 AND pd.soft_delete = 0
+         AND rpd.soft_delete = 0 AND rpd.jenis_keluar_id IS NULL
+         ORDER BY rb.tingkat_pendidikan_id, rb.nama, pd.nama"
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| format!("Gagal mengambil data siswa aktif: {}", e))?;
+
+    let rentang = muat_pengaturan().rentang_usia;
+    let total_diperiksa = siswa.len() as i32;
+    let mut tanpa_rentang = 0;
+    let mut anomali = Vec::new();
+
+    for (peserta_didik_id, nama, nisn, tanggal_lahir, nama_rombel, tingkat) in siswa {
+        let Some((usia_min, usia_maks)) = rentang_tingkat(&rentang, tingkat) else {
+            tanpa_rentang += 1;
+            continue;
+        };
+        let usia = hitung_usia(tanggal_lahir, tanggal_acuan);
+        if (usia_min..=usia_maks).contains(&usia) {
+            continue;
+        }
+
+        let saran_tahun_lahir = [-10, 10]
+            .iter()
+            .map(|geser| tanggal_lahir.year() + geser)
+            .find(|tahun| {
+                tanggal_lahir
+                    .with_year(*tahun)
+                    .is_some_and(|t| (usia_min..=usia_maks).contains(&hitung_usia(t, tanggal_acuan)))
+            });
+
+        anomali.push(AnomaliUsia {
+            peserta_didik_id,
+            nama,
+            nisn,
+            tanggal_lahir,
+            nama_rombel,
+            tingkat_pendidikan_id: tingkat,
+            usia,
+            usia_min,
+            usia_maks,
+            status: if usia < usia_min { "terlalu_muda" } else { "terlalu_tua" }.to_string(),
+            saran_tahun_lahir,
+        });
+    }
+
+    Ok(LaporanAnomaliUsia { tanggal_acuan, total_diperiksa, tanpa_rentang, anomali })
+}
+
+/// Memeriksa usia siswa aktif terhadap rentang usia per jenjang di pengaturan. Bila `tanggal_acuan`
+/// kosong, dipakai 1 Juli tahun ajaran semester aktif.
+#[tauri::command]
+pub async fn get_anomali_usia(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    tanggal_acuan: Option<String>,
+) -> Result<LaporanAnomaliUsia, String> {
+    crate::emit_log(&app, &format!("CMD: get_anomali_usia - tanggal acuan: {:?}", tanggal_acuan));
+    let laporan = susun_laporan(&state, tanggal_acuan).await?;
+    crate::emit_log(&app, &format!("CMD: get_anomali_usia - {} anomali dari {} siswa", laporan.anomali.len(), laporan.total_diperiksa));
+    Ok(laporan)
+}
+
+#[tauri::command]
+pub async fn export_anomali_usia(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    tanggal_acuan: Option<String>,
+) -> Result<ExportResult, String> {
+    crate::emit_log(&app, "CMD: export_anomali_usia - Starting export process.");
+
+    let laporan = susun_laporan(&state, tanggal_acuan).await?;
+
+    // Buat nama file dengan timestamp
+    let now: DateTime<Utc> = Utc::now();
+    let timestamp = now.format("%Y%m%d_%H%M%S");
+    let filename = format!("anomali_usia_{}.xlsx", timestamp);
+
+    let desktop_path = dirs::desktop_dir()
+        .ok_or("Tidak dapat menemukan folder Desktop")?
+        .join(&filename);
+
+    let file_path = desktop_path.to_string_lossy().to_string();
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Anomali Usia")
+        .map_err(|e| format!("Gagal mengatur nama worksheet: {}", e))?;
+
+    let title_format = Format::new().set_bold();
+    let header_format = Format::new()
+        .set_bold()
+        .set_align(FormatAlign::Center)
+        .set_border(FormatBorder::Thin)
+        .set_background_color(rust_xlsxwriter::Color::Gray);
+    let data_format = Format::new()
+        .set_border(FormatBorder::Thin)
+        .set_align(FormatAlign::Left);
+
+    worksheet.write_string_with_format(0, 0, format!("Anomali Usia per {}", laporan.tanggal_acuan.format("%d/%m/%Y")), &title_format)
+        .map_err(|e| format!("Gagal menulis judul: {}", e))?;
+
+    let headers = ["No", "Nama", "NISN", "Tanggal Lahir", "Rombel", "Tingkat", "Usia", "Rentang Wajar", "Keterangan", "Saran Tahun Lahir"];
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(2, col as u16, *header, &header_format)
+            .map_err(|e| format!("Gagal menulis header: {}", e))?;
+    }
+
+    for (idx, a) in laporan.anomali.iter().enumerate() {
+        let row_num = (idx + 3) as u32;
+        let nilai = [
+            (idx + 1).to_string(),
+            a.nama.clone(),
+            a.nisn.clone().unwrap_or_else(|| "-".to_string()),
+            a.tanggal_lahir.format("%d/%m/%Y").to_string(),
+            a.nama_rombel.clone(),
+            a.tingkat_pendidikan_id.to_string(),
+            a.usia.to_string(),
+            format!("{} - {} tahun", a.usia_min, a.usia_maks),
+            if a.status == "terlalu_muda" { "Terlalu muda" } else { "Terlalu tua" }.to_string(),
+            a.saran_tahun_lahir.map(|t| t.to_string()).unwrap_or_else(|| "-".to_string()),
+        ];
+        for (col, isi) in nilai.iter().enumerate() {
+            worksheet.write_string_with_format(row_num, col as u16, isi, &data_format)
+                .map_err(|e| format!("Gagal menulis baris {}: {}", idx + 1, e))?;
+        }
+    }
+
+    worksheet.autofit();
+
+    workbook.save(&file_path)
+        .map_err(|e| format!("Gagal menyimpan workbook: {}", e))?;
+
+    crate::emit_log(&app, &format!("CMD: export_anomali_usia - Exported {} records to {}", laporan.anomali.len(), file_path));
+
+    Ok(ExportResult {
+        success: true,
+        message: format!("Berhasil mengekspor {} anomali usia ke file Excel: {}", laporan.anomali.len(), filename),
+        file_path: Some(file_path),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::pengaturan::PengaturanAplikasi;
+
+    fn tanggal(tahun: i32, bulan: u32, hari: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(tahun, bulan, hari).unwrap()
+    }
+
+    #[test]
+    fn usia_bertambah_tepat_pada_hari_ulang_tahun() {
+        let lahir = tanggal(2012, 7, 1);
+        assert_eq!(hitung_usia(lahir, tanggal(2019, 6, 30)), 6);
+        assert_eq!(hitung_usia(lahir, tanggal(2019, 7, 1)), 7);
+        // Lahir 29 Februari berulang tahun setelah 28 Februari pada tahun bukan kabisat
+        let kabisat = tanggal(2008, 2, 29);
+        assert_eq!(hitung_usia(kabisat, tanggal(2016, 2, 28)), 7);
+        assert_eq!(hitung_usia(kabisat, tanggal(2015, 3, 1)), 7);
+        assert_eq!(hitung_usia(kabisat, tanggal(2015, 2, 28)), 6);
+    }
+
+    #[test]
+    fn rentang_usia_bergeser_per_tingkat_dan_batas_jenjang() {
+        let rentang = PengaturanAplikasi::default().rentang_usia;
+        assert_eq!(rentang_tingkat(&rentang, 1), Some((5, 8)));
+        assert_eq!(rentang_tingkat(&rentang, 6), Some((10, 13)));
+        assert_eq!(rentang_tingkat(&rentang, 7), Some((11, 15)));
+        assert_eq!(rentang_tingkat(&rentang, 9), Some((13, 17)));
+        assert_eq!(rentang_tingkat(&rentang, 10), Some((14, 18)));
+        assert_eq!(rentang_tingkat(&rentang, 13), Some((17, 21)));
+        assert_eq!(rentang_tingkat(&rentang, 0), None);
+        assert_eq!(rentang_tingkat(&rentang, 14), None);
+    }
+}
@@ -283,4 +283,113 @@ pub async fn auto_fix_nik_ibu_invalid_stealth(
     emit_log(&app, &format!("CMD: auto_fix_nik_ibu_invalid_stealth - Berhasil memperbaiki {} NIK ibu tidak valid (STEALTH MODE)", rows_affected));
     
     Ok(format!("Berhasil memperbaiki {} NIK ibu tidak valid menjadi NULL dan menghapus log validasi Dapodik (tanpa jejak audit).", rows_affected))
-} 
\ No newline at end of file
+}
+
+const NOMOR_IDENTITAS_DUMMY: [&str; 4] = ["0000000000000000", "1111111111111111", "9999999999999999", "1234567890123456"];
+
+/// Memeriksa format NIK: 16 digit angka, bukan nilai dummy, dan bagian tanggal lahir (digit 7-10) masuk akal.
+/// Mengembalikan `true` bila NIK menunjukkan perempuan (tanggal lahir ditambah 40).
+pub fn cek_format_nik(nik: &str) -> Result<bool, String> {
+    if nik.len() != 16 || !nik.chars().all(|c| c.is_ascii_digit()) {
+        return Err("NIK harus 16 digit angka".to_string());
+    }
+    if NOMOR_IDENTITAS_DUMMY.contains(&nik) {
+        return Err("NIK berisi nilai dummy".to_string());
+    }
+    let tanggal: u32 = nik[6..8].parse().unwrap_or(0);
+    let bulan: u32 = nik[8..10].parse().unwrap_or(0);
+    let perempuan = tanggal > 40;
+    let tanggal_asli = if perempuan { tanggal - 40 } else { tanggal };
+    if !(1..=31).contains(&tanggal_asli) || !(1..=12).contains(&bulan) {
+        return Err("Bagian tanggal lahir pada NIK tidak valid".to_string());
+    }
+    Ok(perempuan)
+}
+
+/// Memeriksa apakah digit 7-12 NIK (tanggal, bulan, dua digit tahun) sama dengan tanggal lahir.
+/// NIK harus sudah lolos `cek_format_nik`; tanggal perempuan dikurangi 40 sebelum dibandingkan.
+pub fn nik_sesuai_tanggal_lahir(nik: &str, tanggal_lahir: chrono::NaiveDate) -> bool {
+    use chrono::Datelike;
+    let tanggal: u32 = nik[6..8].parse().unwrap_or(0);
+    let bulan: u32 = nik[8..10].parse().unwrap_or(0);
+    let tahun: i32 = nik[10..12].parse().unwrap_or(-1);
+    let tanggal = if tanggal > 40 { tanggal - 40 } else { tanggal };
+    tanggal == tanggal_lahir.day() && bulan == tanggal_lahir.month() && tahun == tanggal_lahir.year().rem_euclid(100)
+}
+
+/// Memeriksa format nomor KK: 16 digit angka dan bukan nilai dummy
+pub fn cek_format_no_kk(no_kk: &str) -> Result<(), String> {
+    if no_kk.len() != 16 || !no_kk.chars().all(|c| c.is_ascii_digit()) {
+        return Err("No KK harus 16 digit angka".to_string());
+    }
+    if NOMOR_IDENTITAS_DUMMY.contains(&no_kk) {
+        return Err("No KK berisi nilai dummy".to_string());
+    }
+    Ok(())
+}
+
+/// Memeriksa format NISN: 10 digit angka
+pub fn cek_format_nisn(nisn: &str) -> Result<(), String> {
+    if nisn.len() != 10 || !nisn.chars().all(|c| c.is_ascii_digit()) {
+        return Err("NISN harus 10 digit angka".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn tanggal(tahun: i32, bulan: u32, hari: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(tahun, bulan, hari).unwrap()
+    }
+
+    #[test]
+    fn nik_laki_laki_dan_perempuan() {
+        assert_eq!(cek_format_nik("3201010508100001"), Ok(false));
+        // Perempuan: tanggal lahir ditambah 40
+        assert_eq!(cek_format_nik("3201014508100001"), Ok(true));
+        assert_eq!(cek_format_nik("3201017108100001"), Ok(true));
+    }
+
+    #[test]
+    fn nik_tanggal_tidak_valid() {
+        assert!(cek_format_nik("3201010008100001").is_err());
+        assert!(cek_format_nik("3201013208100001").is_err());
+        assert!(cek_format_nik("3201014008100001").is_err());
+        assert!(cek_format_nik("3201017208100001").is_err());
+        assert!(cek_format_nik("3201010500100001").is_err());
+        assert!(cek_format_nik("3201010513100001").is_err());
+    }
+
+    #[test]
+    fn nik_format_dan_dummy() {
+        assert!(cek_format_nik("320101050810001").is_err());
+        assert!(cek_format_nik("32010105081000012").is_err());
+        assert!(cek_format_nik("32010105081000a1").is_err());
+        assert!(cek_format_nik("1234567890123456").is_err());
+        assert!(cek_format_nik("0000000000000000").is_err());
+    }
+
+    #[test]
+    fn nik_dibandingkan_dengan_tanggal_lahir() {
+        assert!(nik_sesuai_tanggal_lahir("3201010508100001", tanggal(2010, 8, 5)));
+        assert!(nik_sesuai_tanggal_lahir("3201014508100001", tanggal(2010, 8, 5)));
+        assert!(nik_sesuai_tanggal_lahir("3201013112000001", tanggal(2000, 12, 31)));
+        assert!(!nik_sesuai_tanggal_lahir("3201010508100001", tanggal(2010, 8, 6)));
+        assert!(!nik_sesuai_tanggal_lahir("3201010508100001", tanggal(2010, 9, 5)));
+        assert!(!nik_sesuai_tanggal_lahir("3201010508100001", tanggal(2011, 8, 5)));
+    }
+
+    #[test]
+    fn nisn_dan_no_kk() {
+        assert!(cek_format_nisn("0012345678").is_ok());
+        assert!(cek_format_nisn("001234567").is_err());
+        assert!(cek_format_nisn("00123456789").is_err());
+        assert!(cek_format_nisn("00123456a8").is_err());
+        assert!(cek_format_no_kk("3201010101100001").is_ok());
+        assert!(cek_format_no_kk("320101010110001").is_err());
+        assert!(cek_format_no_kk("9999999999999999").is_err());
+    }
+}
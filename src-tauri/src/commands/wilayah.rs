@@ -0,0 +1,219 @@
+//! Indeks ref.mst_wilayah di memori. Dimuat saat startup (atau saat pertama kali dibutuhkan bila gagal)
+//! lalu dipakai bersama oleh picker alamat, validasi, dan ekspor.
+
+use crate::app_state::{DbPool, WilayahCache};
+use crate::commands::referensi::WilayahReferensi;
+use crate::emit_log;
+use tauri::{AppHandle, State};
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub const LEVEL_PROVINSI: i16 = 1;
+pub const LEVEL_KABUPATEN: i16 = 2;
+pub const LEVEL_KECAMATAN: i16 = 3;
+pub const LEVEL_DESA: i16 = 4;
+
+pub struct WilayahIndex {
+    wilayah: HashMap<String, WilayahReferensi>,
+    anak: HashMap<String, Vec<String>>,
+}
+
+impl WilayahIndex {
+    async fn muat(pool: &Pool<Postgres>) -> Result<Self, String> {
+        let semua = sqlx::query_as::<_, WilayahReferensi>(
+            "SELECT TRIM(kode_wilayah) AS kode_wilayah, TRIM(nama) AS nama, id_level_wilayah, TRIM(mst_kode_wilayah) AS mst_kode_wilayah
+             FROM ref.mst_wilayah ORDER BY nama"
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Gagal memuat referensi wilayah: {}", e))?;
+
+        let mut wilayah = HashMap::with_capacity(semua.len());
+        let mut anak: HashMap<String, Vec<String>> = HashMap::new();
+        for w in semua {
+            if let Some(induk) = w.mst_kode_wilayah.as_ref().filter(|k| !k.is_empty() && **k != w.kode_wilayah) {
+                anak.entry(induk.clone()).or_default().push(w.kode_wilayah.clone());
+            }
+            wilayah.insert(w.kode_wilayah.clone(), w);
+        }
+
+        Ok(Self { wilayah, anak })
+    }
+
+    pub fn get(&self, kode_wilayah: &str) -> Option<&WilayahReferensi> {
+        self.wilayah.get(kode_wilayah.trim())
+    }
+
+    /// Memeriksa apakah kode ada di referensi, dan bila `level` diisi, apakah levelnya sesuai
+    pub fn valid(&self, kode_wilayah: &str, level: Option<i16>) -> bool {
+        self.get(kode_wilayah)
+            .map(|w| level.is_none_or(|l| w.id_level_wilayah == l))
+            .unwrap_or(false)
+    }
+
+    /// Rantai wilayah dari level tertinggi sampai kode yang diminta, misalnya provinsi > kabupaten > kecamatan > desa
+    pub fn breadcrumb(&self, kode_wilayah: &str) -> Vec<&WilayahReferensi> {
+        let mut rantai = Vec::new();
+        let mut saat_ini = self.get(kode_wilayah);
+        while let Some(w) = saat_ini {
+            // Batas kedalaman mencegah loop tak berujung bila data induk melingkar
+            if rantai.len() > LEVEL_DESA as usize + 1 {
+                break;
+            }
+            rantai.push(w);
+            saat_ini = w.mst_kode_wilayah.as_deref().filter(|k| *k != w.kode_wilayah).and_then(|k| self.get(k));
+        }
+        rantai.reverse();
+        rantai
+    }
+
+    /// Wilayah induk pada level tertentu, misalnya kecamatan dari sebuah desa
+    pub fn induk_pada_level(&self, kode_wilayah: &str, level: i16) -> Option<&WilayahReferensi> {
+        self.breadcrumb(kode_wilayah).into_iter().find(|w| w.id_level_wilayah == level)
+    }
+
+    pub fn kecamatan_dari_desa(&self, kode_desa: &str) -> Option<&WilayahReferensi> {
+        self.get(kode_desa)
+            .filter(|w| w.id_level_wilayah == LEVEL_DESA)
+            .and_then(|w| self.induk_pada_level(&w.kode_wilayah, LEVEL_KECAMATAN))
+    }
+
+    /// Anak langsung dari sebuah wilayah, urut nama
+    pub fn anak(&self, kode_induk: &str) -> Vec<&WilayahReferensi> {
+        self.anak
+            .get(kode_induk.trim())
+            .map(|kode| kode.iter().filter_map(|k| self.wilayah.get(k)).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn per_level(&self, level: i16) -> Vec<&WilayahReferensi> {
+        let mut hasil: Vec<_> = self.wilayah.values().filter(|w| w.id_level_wilayah == level).collect();
+        hasil.sort_by(|a, b| a.nama.cmp(&b.nama));
+        hasil
+    }
+
+    /// Pencarian nama lintas level (atau satu level bila `level` diisi), urut level lalu nama
+    pub fn cari(&self, kata_kunci: &str, level: Option<i16>, batas: usize) -> Vec<&WilayahReferensi> {
+        let kata_kunci = kata_kunci.trim().to_lowercase();
+        if kata_kunci.is_empty() {
+            return Vec::new();
+        }
+        let mut hasil: Vec<_> = self
+            .wilayah
+            .values()
+            .filter(|w| level.is_none_or(|l| w.id_level_wilayah == l))
+            .filter(|w| w.nama.to_lowercase().contains(&kata_kunci))
+            .collect();
+        hasil.sort_by(|a, b| a.id_level_wilayah.cmp(&b.id_level_wilayah).then_with(|| a.nama.cmp(&b.nama)));
+        hasil.truncate(batas);
+        hasil
+    }
+
+    /// Nama lengkap berjenjang dari desa sampai provinsi, dipisah koma
+    pub fn nama_lengkap(&self, kode_wilayah: &str) -> Option<String> {
+        let rantai = self.breadcrumb(kode_wilayah);
+        if rantai.is_empty() {
+            return None;
+        }
+        Some(
+            rantai
+                .iter()
+                .rev()
+                .filter(|w| w.id_level_wilayah >= LEVEL_PROVINSI)
+                .map(|w| w.nama.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+}
+
+/// Mengambil indeks wilayah, memuatnya dari database bila belum ada
+pub(crate) async fn ambil_index_wilayah(pool: &Pool<Postgres>, cache: &WilayahCache) -> Result<Arc<WilayahIndex>, String> {
+    if let Some(index) = cache.index.read().map_err(|e| e.to_string())?.as_ref() {
+        return Ok(index.clone());
+    }
+    muat_ulang_wilayah_cache(pool, cache).await
+}
+
+pub(crate) async fn muat_ulang_wilayah_cache(pool: &Pool<Postgres>, cache: &WilayahCache) -> Result<Arc<WilayahIndex>, String> {
+    let index = Arc::new(WilayahIndex::muat(pool).await?);
+    *cache.index.write().map_err(|e| e.to_string())? = Some(index.clone());
+    Ok(index)
+}
+
+#[derive(serde::Serialize)]
+pub struct HasilCariWilayah {
+    pub wilayah: WilayahReferensi,
+    pub nama_lengkap: String,
+}
+
+#[tauri::command]
+pub async fn get_breadcrumb_wilayah(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, WilayahCache>,
+    kode_wilayah: String,
+) -> Result<Vec<WilayahReferensi>, String> {
+    emit_log(&app, &format!("CMD: get_breadcrumb_wilayah - kode: {}", kode_wilayah));
+    let index = ambil_index_wilayah(&state.pool, &cache).await?;
+    Ok(index.breadcrumb(&kode_wilayah).into_iter().cloned().collect())
+}
+
+#[tauri::command]
+pub async fn cari_wilayah(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, WilayahCache>,
+    kata_kunci: String,
+    level: Option<i16>,
+    batas: Option<usize>,
+) -> Result<Vec<HasilCariWilayah>, String> {
+    emit_log(&app, &format!("CMD: cari_wilayah - kata kunci: {}, level: {:?}", kata_kunci, level));
+    let index = ambil_index_wilayah(&state.pool, &cache).await?;
+    Ok(index
+        .cari(&kata_kunci, level, batas.unwrap_or(50))
+        .into_iter()
+        .map(|w| HasilCariWilayah {
+            nama_lengkap: index.nama_lengkap(&w.kode_wilayah).unwrap_or_else(|| w.nama.clone()),
+            wilayah: w.clone(),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn validasi_kode_wilayah(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, WilayahCache>,
+    kode_wilayah: String,
+    level: Option<i16>,
+) -> Result<bool, String> {
+    emit_log(&app, &format!("CMD: validasi_kode_wilayah - kode: {}, level: {:?}", kode_wilayah, level));
+    let index = ambil_index_wilayah(&state.pool, &cache).await?;
+    Ok(index.valid(&kode_wilayah, level))
+}
+
+#[tauri::command]
+pub async fn get_kecamatan_dari_desa(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, WilayahCache>,
+    kode_desa: String,
+) -> Result<Option<WilayahReferensi>, String> {
+    emit_log(&app, &format!("CMD: get_kecamatan_dari_desa - kode desa: {}", kode_desa));
+    let index = ambil_index_wilayah(&state.pool, &cache).await?;
+    Ok(index.kecamatan_dari_desa(&kode_desa).cloned())
+}
+
+/// Membuang indeks agar dimuat ulang dari database, misalnya setelah referensi wilayah diperbarui
+#[tauri::command]
+pub async fn muat_ulang_wilayah(
+    app: AppHandle,
+    state: State<'_, DbPool>,
+    cache: State<'_, WilayahCache>,
+) -> Result<String, String> {
+    emit_log(&app, "CMD: muat_ulang_wilayah - Memuat ulang referensi wilayah");
+    let index = muat_ulang_wilayah_cache(&state.pool, &cache).await?;
+    Ok(format!("Referensi wilayah dimuat ulang ({} wilayah).", index.wilayah.len()))
+}
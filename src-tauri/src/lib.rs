@@ -1,7 +1,7 @@
 // src-tauri/src/lib.rs
 
 use tauri::{AppHandle, Emitter};
-use crate::app_state::DbPool;
+use crate::app_state::{DbPool, ReferensiCache, WilayahCache};
 use sqlx::postgres::PgPoolOptions;
 
 // Deklarasi modul
@@ -30,11 +30,32 @@ pub fn run() {
             .await
             .expect("Failed to connect to database")
     });
-    let db_pool = DbPool { pool };
+    // Muat data referensi di awal; bila gagal, cache diisi saat pertama kali dibutuhkan
+    let referensi_cache = ReferensiCache::default();
+    if let Err(e) = tauri::async_runtime::block_on(commands::referensi::muat_ulang_referensi_cache(&pool, &referensi_cache)) {
+        eprintln!("Gagal memuat cache referensi saat start: {}", e);
+    }
+    let wilayah_cache = WilayahCache::default();
+    if let Err(e) = tauri::async_runtime::block_on(commands::wilayah::muat_ulang_wilayah_cache(&pool, &wilayah_cache)) {
+        eprintln!("Gagal memuat referensi wilayah saat start: {}", e);
+    }
+    // Bila tabel milik aplikasi gagal dibuat (misalnya role tidak boleh CREATE), aplikasi tetap berjalan dan hanya
+    // perintah yang memakai skema dapomaster yang menolak dengan pesan kegagalan ini
+    let skema_dapomaster = tauri::async_runtime::block_on(commands::dapomaster::siapkan_skema_dapomaster(&pool));
+    if let Err(e) = &skema_dapomaster {
+        eprintln!("{}", e);
+    }
+    let skema_gagal = skema_dapomaster.clone().err();
+    let db_pool = DbPool { pool, skema_dapomaster };
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .manage(db_pool)
-        .setup(|app| {
+        .manage(wilayah_cache)
+        .manage(referensi_cache)
+        .setup(move |app| {
+            if let Some(e) = skema_gagal {
+                emit_log(app.handle(), &format!("[SETUP] {}", e));
+            }
             crate::setup::setup_app(app)?;
             Ok(())
         })
@@ -53,6 +74,8 @@ pub fn run() {
             commands::referensi::get_all_jenis_pendaftaran,
             commands::referensi::get_all_hobby,
             commands::referensi::get_all_cita,
+            commands::referensi::get_referensi_bundle,
+            commands::referensi::muat_ulang_referensi,
             commands::referensi::get_wilayah_by_level_and_parent,
             
             // Siswa - Referensi tambahan
@@ -64,39 +87,124 @@ pub fn run() {
             commands::siswa::get_total_siswa,
             commands::siswa::get_daftar_siswa,
             commands::siswa::registrasi_siswa_baru,
+            commands::siswa::registrasi_siswa_pindahan,
+            commands::siswa::get_daftar_mutasi_masuk,
             commands::siswa::get_siswa_by_id,
             commands::siswa::update_siswa,
             commands::siswa::update_siswa_stealth,
             commands::siswa::update_siswa_ghost,
             commands::siswa::delete_siswa,
-            commands::siswa::fix_desa_kelurahan_format,
+            commands::siswa::list_siswa_terhapus,
+            commands::siswa::pulihkan_siswa,
+            commands::siswa::hapus_permanen,
             commands::siswa::test_update_siswa_stealth,
             
+            // Longitudinal
+            commands::longitudinal::get_longitudinal_rombel,
+            commands::longitudinal::simpan_longitudinal,
+            commands::longitudinal::simpan_longitudinal_massal,
+            commands::longitudinal::salin_longitudinal_semester_lalu,
+            commands::longitudinal::export_longitudinal_rombel,
+            commands::longitudinal::import_longitudinal_from_excel,
+            
+            // Riwayat Perubahan
+            commands::riwayat::get_riwayat_perubahan_siswa,
+            commands::riwayat::export_riwayat_perubahan_siswa,
+            
             // Lulusan
             commands::lulusan::get_total_siswa_lulus,
             commands::lulusan::get_daftar_siswa_lulus,
             commands::lulusan::update_bulk_ijazah,
             commands::lulusan::get_all_jenis_ijazah,
+            commands::lulusan::preview_kelulusan,
+            commands::lulusan::proses_kelulusan,
+            commands::nomor_ijazah::cek_duplikat_nomor_ijazah,
+            commands::nomor_ijazah::alokasi_nomor_ijazah,
+            commands::skl::get_placeholder_skl,
+            commands::skl::generate_skl,
+            commands::skl::get_nomor_awal_skl,
+            
+            // Tracer Lulusan
+            commands::tracer::get_daftar_tracer_lulusan,
+            commands::tracer::simpan_tracer_lulusan,
+            commands::tracer::get_rekap_tracer_lulusan,
+            commands::tracer::export_tracer_lulusan,
+            commands::tracer::import_tracer_lulusan_from_excel,
+            
+            // Nilai
+            commands::nilai::get_nilai_rapor,
+            commands::nilai::get_mata_pelajaran_rombel,
+            commands::nilai::get_leger_nilai,
+            commands::nilai::export_leger_nilai,
             
             // Keluar
             commands::keluar::get_total_siswa_keluar,
             commands::keluar::get_daftar_siswa_keluar,
+            commands::keluar::proses_siswa_keluar,
+            commands::keluar::batalkan_siswa_keluar,
+            commands::keluar::statistik_siswa_keluar,
+            commands::surat_pindah::get_placeholder_surat_pindah,
+            commands::surat_pindah::generate_surat_pindah,
+            commands::surat_pindah::get_nomor_awal_surat_pindah,
             
             // Export
             commands::export::export_lulusan_to_excel,
             commands::export::export_siswa_keluar_to_excel,
-            commands::export::import_lulusan_from_excel,
+            commands::import_lulusan::import_lulusan_from_excel,
+            commands::export::get_daftar_sheet_excel,
             commands::export::open_import_dialog,
             
+            // PPDB
+            commands::ppdb::preview_import_ppdb,
+            commands::ppdb::import_ppdb_from_excel,
+            commands::ppdb::export_template_ppdb,
+            
             // Validasi
             commands::validasi::auto_fix_hobby_minus_one_stealth,
             commands::validasi::auto_fix_cita_null_zero_stealth,
             commands::validasi::auto_fix_nik_ayah_invalid_stealth,
                         commands::validasi::auto_fix_nik_ibu_invalid_stealth,
             
+            // Alamat
+            commands::alamat::cek_konsistensi_alamat,
+            commands::alamat::terapkan_perbaikan_alamat,
+            
+            // Wilayah
+            commands::wilayah::get_breadcrumb_wilayah,
+            commands::wilayah::cari_wilayah,
+            commands::wilayah::validasi_kode_wilayah,
+            commands::wilayah::get_kecamatan_dari_desa,
+            commands::wilayah::muat_ulang_wilayah,
+            
+            // Anomali Usia
+            commands::usia::get_anomali_usia,
+            commands::usia::export_anomali_usia,
+            
             // Naik Kelas
             commands::naik_kelas::get_siswa_naik_kelas,
             commands::naik_kelas::get_daftar_semester,
+            commands::naik_kelas::proses_naik_kelas,
+            commands::naik_kelas::sarankan_pemetaan_rombel,
+            commands::naik_kelas::sarankan_rombel_tinggal_kelas,
+            commands::jenjang::get_aturan_jenjang,
+            
+            // Tinggal Kelas
+            commands::tinggal_kelas::tandai_tinggal_kelas,
+            commands::tinggal_kelas::batalkan_tinggal_kelas,
+            commands::tinggal_kelas::get_daftar_tinggal_kelas,
+            commands::tinggal_kelas::export_tinggal_kelas,
+            
+            // Rombel
+            commands::rombel::pindah_rombel,
+            commands::rombel::pindah_rombel_massal,
+            
+            // NIPD
+            commands::nipd::preview_nipd,
+            commands::nipd::generate_nipd_massal,
+            
+            // Pengaturan
+            commands::pengaturan::get_pengaturan,
+            commands::pengaturan::simpan_pengaturan,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
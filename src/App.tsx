@@ -25,7 +25,7 @@ function App() {
     if (activePath.startsWith("/lulusan")) {
       const page = activePath.split("/")[2] || "daftar";
       const pageTitle = page.charAt(0).toUpperCase() + page.slice(1);
-      return <LulusanView pageTitle={pageTitle} semester={selectedSemester} tahunAjaran={selectedTahunAjaran} />;
+      return <LulusanView pageTitle={pageTitle} user={user} semester={selectedSemester} tahunAjaran={selectedTahunAjaran} />;
     }
     
     if (activePath.startsWith("/keluar")) {
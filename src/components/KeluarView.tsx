@@ -15,6 +15,9 @@ export type SiswaKeluar = {
   jenis_keluar_id: string;
   ket_keluar: string;
   tanggal_keluar: string | null;
+  rombongan_belajar_id: string | null;
+  nama_rombel: string | null;
+  tingkat_pendidikan_id: number | null;
 };
 
 export default function KeluarView({ 
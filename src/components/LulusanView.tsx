@@ -1,7 +1,7 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { Loader2, AlertTriangle, ChevronLeft, ChevronRight, Search, FileSpreadsheet, Edit, Upload } from "lucide-react";
-import type { Semester, TahunAjaran } from "./PemilihanPenggunaView";
+import type { Pengguna, Semester, TahunAjaran } from "./PemilihanPenggunaView";
 
 export type SiswaLulus = {
   peserta_didik_id: string;
@@ -31,6 +31,40 @@ export type ImportResult = {
   errors: ImportError[];
 };
 
+export type KonfirmasiLulusan = {
+  row: number;
+  nama_excel: string;
+  nama_db: string;
+  nisn_db: string | null;
+  alasan: string;
+  peserta_didik_id: string;
+  jenis_ijazah_id: string | null;
+  nomor: string | null;
+  penandatangan: string | null;
+  tanggal_tanda_tangan: string | null;
+};
+
+export type ImportLulusanResult = ImportResult & {
+  perlu_konfirmasi: KonfirmasiLulusan[];
+};
+
+export type Kecocokan = "ketat" | "sedang" | "longgar";
+
+export type HasilBulkIjazah = {
+  diupdate: number;
+  ditambahkan: number;
+  tidak_ditemukan: number;
+  gagal: number;
+  dibatalkan: boolean;
+  pesan: string;
+  hasil: {
+    index: number;
+    peserta_didik_id: string;
+    status: "diupdate" | "ditambahkan" | "tidak_ditemukan" | "tidak_valid" | "gagal";
+    pesan: string | null;
+  }[];
+};
+
 export type ImportError = {
   row: number;
   field: string;
@@ -39,10 +73,12 @@ export type ImportError = {
 
 export default function LulusanView({ 
   pageTitle, 
+  user,
   semester, 
   tahunAjaran 
 }: { 
   pageTitle: string; 
+  user: Pengguna | null;
   semester: Semester | null;
   tahunAjaran: TahunAjaran | null;
 }) {
@@ -57,7 +93,13 @@ export default function LulusanView({
   const [editingData, setEditingData] = useState<{ [key: string]: Partial<SiswaLulus> }>({});
   const [bulkUpdateLoading, setBulkUpdateLoading] = useState(false);
   const [importLoading, setImportLoading] = useState(false);
-  const [importResult, setImportResult] = useState<ImportResult | null>(null);
+  const [importResult, setImportResult] = useState<ImportLulusanResult | null>(null);
+  const [importFile, setImportFile] = useState<string | null>(null);
+  const [daftarSheet, setDaftarSheet] = useState<string[]>([]);
+  const [namaSheet, setNamaSheet] = useState("");
+  const [kecocokan, setKecocokan] = useState<Kecocokan>("sedang");
+  const [konfirmasiDipilih, setKonfirmasiDipilih] = useState<number[]>([]);
+  const [konfirmasiLoading, setKonfirmasiLoading] = useState(false);
   const pageSize = 10;
   const totalPages = Math.ceil(totalSiswa / pageSize);
 
@@ -91,6 +133,7 @@ export default function LulusanView({
   };
 
   const handleBulkUpdate = async () => {
+    if (!user) return;
     try {
       setBulkUpdateLoading(true);
       const updates = Object.entries(editingData).map(([peserta_didik_id, data]) => ({
@@ -101,13 +144,16 @@ export default function LulusanView({
         tanggal_tanda_tangan: data.tanggal_tanda_tangan || null,
       }));
 
-      await invoke("update_bulk_ijazah", { payload: { updates } });
+      const hasil = await invoke<HasilBulkIjazah>("update_bulk_ijazah", { payload: { updates, pengguna_id: user.pengguna_id } });
       
       // Refresh data
       await fetchData(currentPage, searchTerm);
-      setIsEditMode(false);
-      setEditingData({});
-      alert("Berhasil mengupdate data ijazah!");
+      const gagal = hasil.hasil.filter(h => h.pesan);
+      if (gagal.length === 0) {
+        setIsEditMode(false);
+        setEditingData({});
+      }
+      alert([hasil.pesan, ...gagal.map(h => `Baris ${h.index + 1}: ${h.pesan}`)].join("\n"));
     } catch (err) {
       console.error("Error updating bulk:", err);
       alert("Gagal mengupdate data: " + err);
@@ -156,37 +202,187 @@ export default function LulusanView({
     }
   };
 
+  const handlePilihFileImport = async () => {
+    try {
+      setImportResult(null);
+      const filePath = await invoke<string | null>("open_import_dialog");
+      if (!filePath) return;
+
+      const sheets = await invoke<string[]>("get_daftar_sheet_excel", { filePath });
+      setImportFile(filePath);
+      setDaftarSheet(sheets);
+      setNamaSheet(sheets[0] || "");
+    } catch (err) {
+      setError(err as string);
+    }
+  };
+
   const handleImportExcel = async () => {
+    if (!importFile || !user) return;
     try {
       setImportLoading(true);
       setImportResult(null);
-      
-      const filePath = await invoke<string>("open_import_dialog");
-
-      if (filePath) {
-        try {
-          const result = await invoke<ImportResult>("import_lulusan_from_excel", { filePath });
-          setImportResult(result);
-          
-          if (result.success) {
-            alert(`Import berhasil!\n${result.message}`);
-            // Refresh data setelah import berhasil
-            await fetchData(currentPage, searchTerm);
-          } else {
-            alert(`Import selesai dengan beberapa error:\n${result.message}\n\nTotal baris: ${result.total_rows}\nBerhasil: ${result.success_count}\nError: ${result.error_count}`);
-          }
-        } catch (err) {
-          setError(err as string);
-        } finally {
-          setImportLoading(false);
-        }
+
+      const result = await invoke<ImportLulusanResult>("import_lulusan_from_excel", {
+        filePath: importFile,
+        penggunaId: user.pengguna_id,
+        opsi: { nama_sheet: namaSheet || null, kecocokan },
+      });
+      setImportResult(result);
+      setKonfirmasiDipilih([]);
+      setImportFile(null);
+      await fetchData(currentPage, searchTerm);
+
+      if (result.success) {
+        alert(`Import berhasil!\n${result.message}`);
+      } else {
+        alert(`Import selesai dengan catatan:\n${result.message}\n\nTotal baris: ${result.total_rows}\nBerhasil: ${result.success_count}\nError: ${result.error_count}\nPerlu konfirmasi: ${result.perlu_konfirmasi.length}`);
       }
     } catch (err) {
-      setError(err as string);
+      alert("Gagal mengimport data: " + err);
+    } finally {
       setImportLoading(false);
     }
   };
 
+  const toggleKonfirmasi = (row: number) => {
+    setKonfirmasiDipilih(prev => prev.includes(row) ? prev.filter(r => r !== row) : [...prev, row]);
+  };
+
+  const handleSimpanKonfirmasi = async () => {
+    if (!importResult) return;
+    const dipilih = importResult.perlu_konfirmasi.filter(k => konfirmasiDipilih.includes(k.row));
+    if (dipilih.length === 0 || !user) return;
+    try {
+      setKonfirmasiLoading(true);
+      const updates = dipilih.map(k => ({
+        peserta_didik_id: k.peserta_didik_id,
+        jenis_ijazah_id: k.jenis_ijazah_id,
+        nomor: k.nomor,
+        penandatangan: k.penandatangan,
+        tanggal_tanda_tangan: k.tanggal_tanda_tangan,
+      }));
+      const hasil = await invoke<HasilBulkIjazah>("update_bulk_ijazah", {
+        payload: { updates, pengguna_id: user.pengguna_id },
+      });
+
+      // Baris yang tersimpan dihapus dari daftar konfirmasi
+      const tersimpan = new Set(
+        hasil.hasil.filter(h => h.status === "diupdate" || h.status === "ditambahkan").map(h => dipilih[h.index].row)
+      );
+      setImportResult({
+        ...importResult,
+        success_count: importResult.success_count + tersimpan.size,
+        perlu_konfirmasi: importResult.perlu_konfirmasi.filter(k => !tersimpan.has(k.row)),
+      });
+      setKonfirmasiDipilih([]);
+      await fetchData(currentPage, searchTerm);
+
+      const gagal = hasil.hasil.filter(h => h.pesan);
+      alert([hasil.pesan, ...gagal.map(h => `Baris ${dipilih[h.index].row}: ${h.pesan}`)].join("\n"));
+    } catch (err) {
+      alert("Gagal menyimpan konfirmasi: " + err);
+    } finally {
+      setKonfirmasiLoading(false);
+    }
+  };
+
+  const panelOpsiImport = importFile && (
+    <div className="mb-6 p-4 bg-gray-900 border border-gray-700 rounded-lg text-left">
+      <p className="text-sm text-gray-400 mb-3 break-all">File: <span className="text-white">{importFile}</span></p>
+      <div className="flex flex-wrap items-end gap-4">
+        <label className="flex flex-col gap-1 text-sm text-gray-300">
+          Sheet
+          <select
+            value={namaSheet}
+            onChange={(e) => setNamaSheet(e.target.value)}
+            className="px-3 py-2 bg-gray-700 border border-gray-600 rounded-md text-white"
+          >
+            {daftarSheet.map((sheet) => (
+              <option key={sheet} value={sheet}>{sheet}</option>
+            ))}
+          </select>
+        </label>
+        <label className="flex flex-col gap-1 text-sm text-gray-300">
+          Tingkat Kecocokan
+          <select
+            value={kecocokan}
+            onChange={(e) => setKecocokan(e.target.value as Kecocokan)}
+            className="px-3 py-2 bg-gray-700 border border-gray-600 rounded-md text-white"
+          >
+            <option value="ketat">Ketat - nama dan nama ibu harus sama</option>
+            <option value="sedang">Sedang - nama harus sama</option>
+            <option value="longgar">Longgar - cukup ID, NISN, atau NIK</option>
+          </select>
+        </label>
+        <button
+          onClick={handleImportExcel}
+          disabled={importLoading}
+          className="flex items-center gap-2 px-4 py-2 bg-orange-600 text-white font-semibold rounded-md hover:bg-orange-700 transition disabled:bg-gray-600"
+        >
+          {importLoading ? <Loader2 className="animate-spin" size={18} /> : <Upload size={18} />}
+          {importLoading ? "Importing..." : "Mulai Import"}
+        </button>
+        <button
+          onClick={() => setImportFile(null)}
+          disabled={importLoading}
+          className="px-4 py-2 bg-gray-600 text-white rounded-md hover:bg-gray-700 transition"
+        >
+          Batal
+        </button>
+      </div>
+    </div>
+  );
+
+  const daftarKonfirmasi = importResult && importResult.perlu_konfirmasi.length > 0 && (
+    <div className="mt-3">
+      <h4 className="text-yellow-400 font-medium mb-2">Perlu Konfirmasi ({importResult.perlu_konfirmasi.length}):</h4>
+      <div className="max-h-60 overflow-y-auto bg-gray-900 rounded">
+        <table className="w-full text-left text-sm">
+          <thead className="text-gray-400">
+            <tr>
+              <th className="px-3 py-2">
+                <input
+                  type="checkbox"
+                  checked={konfirmasiDipilih.length === importResult.perlu_konfirmasi.length}
+                  onChange={(e) => setKonfirmasiDipilih(e.target.checked ? importResult.perlu_konfirmasi.map(k => k.row) : [])}
+                />
+              </th>
+              <th className="px-3 py-2">Baris</th>
+              <th className="px-3 py-2">Nama di Excel</th>
+              <th className="px-3 py-2">Nama di Database</th>
+              <th className="px-3 py-2">NISN</th>
+              <th className="px-3 py-2">Nomor Ijazah</th>
+              <th className="px-3 py-2">Alasan</th>
+            </tr>
+          </thead>
+          <tbody className="text-gray-300">
+            {importResult.perlu_konfirmasi.map((k) => (
+              <tr key={k.row} className="border-t border-gray-700">
+                <td className="px-3 py-2">
+                  <input type="checkbox" checked={konfirmasiDipilih.includes(k.row)} onChange={() => toggleKonfirmasi(k.row)} />
+                </td>
+                <td className="px-3 py-2">{k.row}</td>
+                <td className="px-3 py-2">{k.nama_excel || "-"}</td>
+                <td className="px-3 py-2">{k.nama_db}</td>
+                <td className="px-3 py-2">{k.nisn_db || "-"}</td>
+                <td className="px-3 py-2">{k.nomor || "-"}</td>
+                <td className="px-3 py-2 text-yellow-400">{k.alasan}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      </div>
+      <button
+        onClick={handleSimpanKonfirmasi}
+        disabled={konfirmasiLoading || konfirmasiDipilih.length === 0}
+        className="mt-3 px-4 py-2 bg-green-600 text-white text-sm font-semibold rounded hover:bg-green-700 transition disabled:bg-gray-600"
+      >
+        {konfirmasiLoading ? "Menyimpan..." : `Simpan ${konfirmasiDipilih.length} Baris Terkonfirmasi`}
+      </button>
+    </div>
+  );
+
   if (loading && siswaLulus.length === 0) {
     return (
       <div className="flex items-center justify-center h-64">
@@ -242,9 +438,9 @@ export default function LulusanView({
             <div className="bg-blue-900/20 border border-blue-600 rounded-lg p-4 mb-6 text-left">
               <h3 className="text-blue-400 font-semibold mb-2">Petunjuk Import:</h3>
               <ul className="text-gray-300 text-sm space-y-1">
-                <li>• Kolom Nama, NISN, dan Nama Ibu adalah kunci untuk mencocokkan data</li>
-                <li>• Sistem akan otomatis mencocokkan data berdasarkan ketiga kolom tersebut</li>
-                <li>• Jika ada perbedaan nama, sistem akan memberikan peringatan</li>
+                <li>• Siswa dicocokkan lewat kolom ID, NISN, atau NIK; kolom dikenali dari nama header</li>
+                <li>• Tingkat kecocokan ketat mewajibkan kolom Nama dan Nama Ibu, sedang mewajibkan kolom Nama</li>
+                <li>• Baris yang namanya berbeda dengan database ditampilkan untuk dikonfirmasi sebelum disimpan</li>
                 <li>• Hanya kolom Jenis Ijazah, Nomor Ijazah, Penandatangan, dan Tanggal Tanda Tangan yang dapat diubah</li>
                 <li>• Format tanggal yang didukung: YYYY-MM-DD, DD/MM/YYYY, DD-MM-YYYY</li>
               </ul>
@@ -252,12 +448,12 @@ export default function LulusanView({
 
             <div className="flex justify-center gap-4">
               <button
-                onClick={handleImportExcel}
+                onClick={handlePilihFileImport}
                 disabled={importLoading}
                 className="flex items-center gap-2 px-6 py-3 bg-orange-600 text-white font-semibold rounded-md hover:bg-orange-700 transition disabled:bg-gray-600"
               >
                 <Upload size={20} />
-                {importLoading ? "Importing..." : "Pilih File Excel"}
+                Pilih File Excel
               </button>
               
               <button
@@ -271,6 +467,8 @@ export default function LulusanView({
             </div>
           </div>
 
+          {panelOpsiImport}
+
           {importResult && (
             <div className={`p-4 rounded-lg border ${importResult.success ? 'bg-green-900/20 border-green-600' : 'bg-orange-900/20 border-orange-600'}`}>
               <h3 className={`font-semibold mb-2 ${importResult.success ? 'text-green-400' : 'text-orange-400'}`}>
@@ -281,6 +479,7 @@ export default function LulusanView({
                 <p>Total baris: {importResult.total_rows}</p>
                 <p>Berhasil: {importResult.success_count}</p>
                 <p>Error: {importResult.error_count}</p>
+                <p>Perlu konfirmasi: {importResult.perlu_konfirmasi.length}</p>
               </div>
               {importResult.errors.length > 0 && (
                 <div>
@@ -294,6 +493,7 @@ export default function LulusanView({
                   </div>
                 </div>
               )}
+              {daftarKonfirmasi}
               <button
                 onClick={() => setImportResult(null)}
                 className="mt-4 px-4 py-2 bg-gray-600 text-white rounded hover:bg-gray-700 transition"
@@ -316,6 +516,7 @@ export default function LulusanView({
                 <p>Total baris: {importResult.total_rows}</p>
                 <p>Berhasil: {importResult.success_count}</p>
                 <p>Error: {importResult.error_count}</p>
+                <p>Perlu konfirmasi: {importResult.perlu_konfirmasi.length}</p>
               </div>
               {importResult.errors.length > 0 && (
                 <div className="mt-3">
@@ -329,6 +530,7 @@ export default function LulusanView({
                   </div>
                 </div>
               )}
+              {daftarKonfirmasi}
               <button
                 onClick={() => setImportResult(null)}
                 className="mt-3 px-3 py-1 bg-gray-600 text-white text-sm rounded hover:bg-gray-700 transition"
@@ -338,6 +540,8 @@ export default function LulusanView({
             </div>
           )}
 
+          {panelOpsiImport}
+
           <div className="flex justify-between items-center mb-6">
             <div className="flex items-center gap-4">
               <div className="relative">
@@ -366,7 +570,7 @@ export default function LulusanView({
                     Edit Bulk
                   </button>
                   <button
-                    onClick={handleImportExcel}
+                    onClick={handlePilihFileImport}
                     disabled={importLoading}
                     className="flex items-center gap-2 px-4 py-2 bg-orange-600 text-white font-semibold rounded-md hover:bg-orange-700 transition disabled:bg-gray-600"
                   >